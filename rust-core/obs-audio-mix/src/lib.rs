@@ -15,6 +15,9 @@ pub use clamping::*;
 pub use mixer::*;
 pub use types::*;
 
+// Encoders of both pipelines share one set of policies
+pub use obs_video::backpressure::BackpressurePolicy;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -26,4 +29,20 @@ mod tests {
         assert_eq!(mix.frames_per_buffer(), 1024);
         assert_eq!(mix.channels(), 8);
     }
+
+    #[test]
+    fn test_audio_timebase() {
+        let config = AudioConfig::default();
+        // 1024 frames at 48kHz
+        assert_eq!(config.buffer_duration_ns(), 21_333_333);
+        // An hour of buffers lands exactly on the sample grid
+        assert_eq!(config.frames_to_ns(48_000 * 3600), 3600 * 1_000_000_000);
+
+        let cd = AudioConfig {
+            sample_rate: 44_100,
+            ..config
+        };
+        assert_eq!(cd.ns_to_frames(1_000_000, obs_video::Rounding::Down), 44);
+        assert_eq!(cd.ns_to_frames(1_000_000, obs_video::Rounding::Up), 45);
+    }
 }
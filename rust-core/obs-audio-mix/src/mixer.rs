@@ -2,10 +2,15 @@
 
 use crate::clamping::*;
 use crate::types::*;
-use crossbeam::channel::{self, Receiver, Sender};
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use obs_video::backpressure::{deliver, BackpressurePolicy, Delivery, DeliveryCounters};
+use obs_video::thread_tuning::{
+    forget_current_thread, thread_config, tune_current_thread_with, ThreadConfig, ThreadRole,
+};
 use parking_lot::RwLock;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 const MAX_AUDIO_MIXES: usize = 6;
 
@@ -13,30 +18,63 @@ const MAX_AUDIO_MIXES: usize = 6;
 pub struct AudioMixer {
     _config: AudioConfig, // Stored for future use (runtime reconfiguration)
     mixes: Vec<Arc<RwLock<AudioMix>>>,
+    // Dedicated mixer threads when the thread config pins or prioritizes
+    // audio; otherwise mixes run on the global rayon pool
+    pool: Option<rayon::ThreadPool>,
     running: Arc<AtomicBool>,
     frames_processed: Arc<AtomicU64>,
 }
 
 impl AudioMixer {
     /// Create a new audio mixer
+    ///
+    /// Mixer threads are placed according to the process-wide thread config.
     pub fn new(config: AudioConfig) -> Self {
+        Self::with_thread_config(config, &thread_config())
+    }
+
+    /// Create a new audio mixer with explicit thread placement
+    pub fn with_thread_config(config: AudioConfig, threads: &ThreadConfig) -> Self {
         let mut mixes = Vec::with_capacity(MAX_AUDIO_MIXES);
         for _ in 0..MAX_AUDIO_MIXES {
-            mixes.push(Arc::new(RwLock::new(AudioMix::new(
-                config.sample_rate,
-                config.frames,
-                config.channels,
-            ))));
+            mixes.push(Arc::new(RwLock::new(AudioMix::with_config(config))));
         }
 
         Self {
             _config: config,
             mixes,
+            pool: Self::build_mixer_pool(threads),
             running: Arc::new(AtomicBool::new(true)),
             frames_processed: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Build the mixer's own thread pool, if audio threads need tuning
+    fn build_mixer_pool(config: &ThreadConfig) -> Option<rayon::ThreadPool> {
+        if config.core_for(ThreadRole::Audio).is_none() && !config.realtime_priority {
+            return None;
+        }
+
+        let threads = config.audio_threads.unwrap_or(1).max(1);
+        let config = config.clone();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("obs-audio-mix-{}", index))
+            .start_handler(move |_| {
+                tune_current_thread_with(&config, ThreadRole::Audio, None);
+            })
+            .exit_handler(|_| forget_current_thread())
+            .build();
+
+        match pool {
+            Ok(pool) => Some(pool),
+            Err(err) => {
+                log::warn!("Could not start audio mixer threads: {}", err);
+                None
+            }
+        }
+    }
+
     /// Get a specific mix
     pub fn get_mix(&self, index: usize) -> Option<Arc<RwLock<AudioMix>>> {
         if index < self.mixes.len() {
@@ -51,12 +89,18 @@ impl AudioMixer {
         use rayon::prelude::*;
 
         // Parallel processing of all mixes
-        self.mixes.par_iter().for_each(|mix| {
-            let mut mix_guard = mix.write();
-            if mix_guard.has_inputs() {
-                mix_guard.process();
-            }
-        });
+        let process = || {
+            self.mixes.par_iter().for_each(|mix| {
+                let mut mix_guard = mix.write();
+                if mix_guard.has_inputs() {
+                    mix_guard.process();
+                }
+            })
+        };
+        match &self.pool {
+            Some(pool) => pool.install(process),
+            None => process(),
+        }
 
         self.frames_processed.fetch_add(1, Ordering::Relaxed);
     }
@@ -89,9 +133,12 @@ impl AudioMixer {
 
 /// Individual audio mix
 pub struct AudioMix {
-    sample_rate: u32,
-    frames_per_buffer: usize,
-    channels: usize,
+    config: AudioConfig,
+
+    // Output timeline: buffers are stamped at `start_timestamp` plus the
+    // duration of the sample frames mixed since
+    start_timestamp: u64,
+    frames_mixed: u64,
 
     // Mix buffers (clamped and unclamped versions)
     buffer_clamped: AudioData,
@@ -103,56 +150,184 @@ pub struct AudioMix {
 
 struct EncoderConnection {
     tx: Sender<AudioData>,
+    // Receiver clone for evicting queued buffers (DropOldest only)
+    evict_rx: Option<Receiver<AudioData>>,
     use_unclamped: bool,
+    backpressure: BackpressurePolicy,
+    shared: Arc<ConnectionShared>,
+}
+
+struct ConnectionShared {
+    counters: DeliveryCounters,
+    // Cleared when the handle is dropped or the policy disconnects
+    connected: AtomicBool,
+}
+
+/// Owned connection between an `AudioMix` and one encoder
+///
+/// Audio buffers are received through the handle. Dropping it removes the
+/// encoder from the mix on the next `process` call.
+pub struct AudioEncoderHandle {
+    rx: Receiver<AudioData>,
+    shared: Arc<ConnectionShared>,
+}
+
+impl AudioEncoderHandle {
+    /// Channel the encoder receives audio on
+    pub fn receiver(&self) -> &Receiver<AudioData> {
+        &self.rx
+    }
+
+    /// Receive a buffer without blocking
+    pub fn try_recv(&self) -> Result<AudioData, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Receive a buffer, waiting up to `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<AudioData, RecvTimeoutError> {
+        self.rx.recv_timeout(timeout)
+    }
+
+    /// Check if the mix still delivers audio to this encoder
+    pub fn is_connected(&self) -> bool {
+        self.shared.connected.load(Ordering::Acquire)
+    }
+
+    /// Get delivery statistics for this encoder
+    pub fn stats(&self) -> AudioEncoderStats {
+        let counters = &self.shared.counters;
+        AudioEncoderStats {
+            received_buffers: counters.delivered.load(Ordering::Relaxed),
+            dropped_buffers: counters.dropped.load(Ordering::Relaxed),
+            replaced_buffers: counters.replaced.load(Ordering::Relaxed),
+            blocked_buffers: counters.blocked.load(Ordering::Relaxed),
+            consecutive_drops: counters.consecutive_drops.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for AudioEncoderHandle {
+    fn drop(&mut self) {
+        self.shared.connected.store(false, Ordering::Release);
+    }
+}
+
+/// Per-encoder delivery statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AudioEncoderStats {
+    /// Buffers delivered into the encoder's channel
+    pub received_buffers: u64,
+    /// Buffers dropped because the encoder's channel was full
+    pub dropped_buffers: u64,
+    /// Queued buffers evicted to make room for newer ones (DropOldest)
+    pub replaced_buffers: u64,
+    /// Deliveries that had to wait for channel space (Block)
+    pub blocked_buffers: u64,
+    /// Drops since the last successful delivery
+    pub consecutive_drops: u64,
 }
 
 impl AudioMix {
     /// Create a new audio mix
     pub fn new(sample_rate: u32, frames_per_buffer: usize, channels: usize) -> Self {
-        Self {
+        Self::with_config(AudioConfig {
             sample_rate,
-            frames_per_buffer,
             channels,
-            buffer_clamped: AudioData::new(channels, frames_per_buffer),
-            buffer_unclamped: AudioData::new(channels, frames_per_buffer),
+            frames: frames_per_buffer,
+            ..AudioConfig::default()
+        })
+    }
+
+    /// Create a new audio mix for a buffer configuration
+    pub fn with_config(config: AudioConfig) -> Self {
+        Self {
+            config,
+            start_timestamp: 0,
+            frames_mixed: 0,
+            buffer_clamped: AudioData::new(config.channels, config.frames),
+            buffer_unclamped: AudioData::new(config.channels, config.frames),
             encoders: Vec::new(),
         }
     }
 
     pub fn sample_rate(&self) -> u32 {
-        self.sample_rate
+        self.config.sample_rate
     }
 
     pub fn frames_per_buffer(&self) -> usize {
-        self.frames_per_buffer
+        self.config.frames
     }
 
     pub fn channels(&self) -> usize {
-        self.channels
+        self.config.channels
+    }
+
+    /// Restart the output timeline: the next buffer is stamped `timestamp`
+    pub fn set_start_timestamp(&mut self, timestamp: u64) {
+        self.start_timestamp = timestamp;
+        self.frames_mixed = 0;
+    }
+
+    /// Timestamp the next processed buffer will carry
+    ///
+    /// Derived from the sample count rather than accumulated buffer
+    /// durations, so rounding never drifts.
+    pub fn next_timestamp(&self) -> u64 {
+        self.start_timestamp + self.config.frames_to_ns(self.frames_mixed)
     }
 
     pub fn has_inputs(&self) -> bool {
-        !self.encoders.is_empty()
+        self.input_count() > 0
     }
 
     pub fn input_count(&self) -> usize {
-        self.encoders.len()
+        self.encoders
+            .iter()
+            .filter(|enc| enc.shared.connected.load(Ordering::Acquire))
+            .count()
     }
 
     /// Connect an encoder to this mix
     ///
-    /// Returns a receiver channel for the encoder to receive audio data.
-    pub fn connect_encoder(&mut self, use_unclamped: bool) -> Receiver<AudioData> {
+    /// Returns a handle for the encoder to receive audio data. Buffers are
+    /// dropped when the encoder falls behind.
+    pub fn connect_encoder(&mut self, use_unclamped: bool) -> AudioEncoderHandle {
+        self.connect_encoder_with_policy(use_unclamped, BackpressurePolicy::DropNewest)
+    }
+
+    /// Connect an encoder with an explicit backpressure policy
+    pub fn connect_encoder_with_policy(
+        &mut self,
+        use_unclamped: bool,
+        backpressure: BackpressurePolicy,
+    ) -> AudioEncoderHandle {
         let (tx, rx) = channel::bounded(4);
+        let shared = Arc::new(ConnectionShared {
+            counters: DeliveryCounters::default(),
+            connected: AtomicBool::new(true),
+        });
 
-        self.encoders.push(EncoderConnection { tx, use_unclamped });
+        let evict_rx = match backpressure {
+            BackpressurePolicy::DropOldest => Some(rx.clone()),
+            _ => None,
+        };
+
+        self.encoders.push(EncoderConnection {
+            tx,
+            evict_rx,
+            use_unclamped,
+            backpressure,
+            shared: shared.clone(),
+        });
 
-        rx
+        AudioEncoderHandle { rx, shared }
     }
 
     /// Disconnect all encoders
     pub fn disconnect_all(&mut self) {
-        self.encoders.clear();
+        for encoder in self.encoders.drain(..) {
+            encoder.shared.connected.store(false, Ordering::Release);
+        }
     }
 
     /// Mix audio sources into the buffer
@@ -167,12 +342,12 @@ impl AudioMix {
         // Mix all sources (simple addition)
         for source in sources {
             for (ch_idx, channel) in source.data.iter().enumerate() {
-                if ch_idx >= self.channels {
+                if ch_idx >= self.config.channels {
                     break;
                 }
 
                 for (frame_idx, sample) in channel.iter().enumerate() {
-                    if frame_idx >= self.frames_per_buffer {
+                    if frame_idx >= self.config.frames {
                         break;
                     }
 
@@ -195,22 +370,43 @@ impl AudioMix {
         // In a real implementation, sources would be passed in
         // For now, we'll just demonstrate encoder dispatch
 
-        // Dispatch to encoders (disconnected ones will fail to send)
-        for encoder in &self.encoders {
+        let timestamp = self.next_timestamp();
+        self.buffer_clamped.timestamp = timestamp;
+        self.buffer_unclamped.timestamp = timestamp;
+        self.frames_mixed += self.config.frames as u64;
+
+        // Dispatch to encoders according to their backpressure policy
+        let buffer_unclamped = &self.buffer_unclamped;
+        let buffer_clamped = &self.buffer_clamped;
+
+        self.encoders.retain(|encoder| {
+            if !encoder.shared.connected.load(Ordering::Acquire) {
+                return false;
+            }
+
             let data = if encoder.use_unclamped {
                 // Send unclamped data for encoders that support it
-                self.buffer_unclamped.clone()
+                buffer_unclamped.clone()
             } else {
                 // Send clamped data for safety
-                self.buffer_clamped.clone()
+                buffer_clamped.clone()
             };
 
-            // Non-blocking send (ignore errors from disconnected channels)
-            let _ = encoder.tx.try_send(data);
-        }
-
-        // Clean up disconnected encoders
-        self.encoders.retain(|enc| !enc.tx.is_full());
+            let delivery = deliver(
+                &encoder.tx,
+                encoder.evict_rx.as_ref(),
+                |_| false,
+                encoder.backpressure,
+                &encoder.shared.counters,
+                data,
+            );
+
+            if delivery == Delivery::Disconnected {
+                encoder.shared.connected.store(false, Ordering::Release);
+                return false;
+            }
+            true
+        });
     }
 }
 
@@ -248,7 +444,7 @@ mod tests {
 
     #[test]
     fn test_audio_mix() {
-        let mut mix = AudioMix::new(48000, 1024, 2);
+        let mix = AudioMix::new(48000, 1024, 2);
 
         assert_eq!(mix.sample_rate(), 48000);
         assert_eq!(mix.frames_per_buffer(), 1024);
@@ -260,12 +456,17 @@ mod tests {
     fn test_encoder_connection() {
         let mut mix = AudioMix::new(48000, 1024, 2);
 
-        let rx = mix.connect_encoder(false);
+        let encoder = mix.connect_encoder(false);
         assert!(mix.has_inputs());
         assert_eq!(mix.input_count(), 1);
 
-        // Keep rx alive - disconnection happens when encoder drops
-        assert!(rx.is_empty()); // Channel should be empty initially
+        // Keep the handle alive - disconnection happens when encoder drops
+        assert!(encoder.receiver().is_empty()); // Channel should be empty initially
+
+        drop(encoder);
+        assert!(!mix.has_inputs());
+        mix.process();
+        assert_eq!(mix.encoders.len(), 0);
     }
 
     #[test]
@@ -322,10 +523,11 @@ mod tests {
         let mixer = AudioMixer::new(config);
 
         // Connect encoders to multiple mixes
+        let mut handles = Vec::new();
         for i in 0..3 {
             let mix_arc = mixer.get_mix(i).unwrap();
             let mut mix = mix_arc.write();
-            mix.connect_encoder(false);
+            handles.push(mix.connect_encoder(false));
         }
 
         // Process all mixes in parallel
@@ -335,4 +537,178 @@ mod tests {
         assert_eq!(stats.active_mixes, 3);
         assert_eq!(stats.frames_processed, 1);
     }
+
+    #[test]
+    fn test_mixer_threads_follow_thread_config() {
+        let threads = ThreadConfig {
+            realtime_priority: true,
+            audio_threads: Some(2),
+            ..Default::default()
+        };
+        let mixer = AudioMixer::with_thread_config(AudioConfig::default(), &threads);
+        assert_eq!(
+            mixer.pool.as_ref().map(|pool| pool.current_num_threads()),
+            Some(2)
+        );
+
+        let handle = mixer.get_mix(0).unwrap().write().connect_encoder(false);
+        mixer.process_all_mixes();
+        assert_eq!(handle.stats().received_buffers, 1);
+
+        // Without pinning or priorities, mixes use the global pool
+        let mixer =
+            AudioMixer::with_thread_config(AudioConfig::default(), &ThreadConfig::default());
+        assert!(mixer.pool.is_none());
+    }
+
+    fn tagged_mix() -> AudioMix {
+        AudioMix::new(48000, 16, 1)
+    }
+
+    /// Run `count` mix cycles
+    fn process_tagged(mix: &mut AudioMix, count: u64) {
+        for _ in 0..count {
+            mix.process();
+        }
+    }
+
+    /// Index of the mix cycle that produced a buffer, from its timestamp
+    fn buffer_index(mix: &AudioMix, data: &AudioData) -> u64 {
+        mix.config
+            .ns_to_frames(data.timestamp, obs_video::Rounding::Up)
+            / mix.frames_per_buffer() as u64
+    }
+
+    #[test]
+    fn test_backpressure_drop_newest() {
+        let mut mix = tagged_mix();
+        let encoder = mix.connect_encoder(false);
+
+        process_tagged(&mut mix, 10);
+
+        let indices: Vec<u64> = encoder
+            .receiver()
+            .try_iter()
+            .map(|d| buffer_index(&mix, &d))
+            .collect();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_buffers, 4);
+        assert_eq!(stats.dropped_buffers, 6);
+        assert_eq!(stats.consecutive_drops, 6);
+        assert!(encoder.is_connected());
+    }
+
+    #[test]
+    fn test_backpressure_drop_oldest() {
+        let mut mix = tagged_mix();
+        let encoder = mix.connect_encoder_with_policy(false, BackpressurePolicy::DropOldest);
+
+        process_tagged(&mut mix, 10);
+
+        let indices: Vec<u64> = encoder
+            .receiver()
+            .try_iter()
+            .map(|d| buffer_index(&mix, &d))
+            .collect();
+        assert_eq!(indices, vec![6, 7, 8, 9]);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_buffers, 10);
+        assert_eq!(stats.replaced_buffers, 6);
+        assert_eq!(stats.dropped_buffers, 0);
+    }
+
+    #[test]
+    fn test_backpressure_block_slow_consumer() {
+        use std::thread;
+
+        let mut mix = tagged_mix();
+        let encoder = mix
+            .connect_encoder_with_policy(false, BackpressurePolicy::Block(Duration::from_secs(1)));
+
+        // Slow encoder: reads one buffer every 5ms
+        let consumer = thread::spawn(move || {
+            let mut timestamps = Vec::new();
+            while let Ok(data) = encoder.recv_timeout(Duration::from_secs(1)) {
+                timestamps.push(data.timestamp);
+                if timestamps.len() == 20 {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            (encoder, timestamps)
+        });
+
+        process_tagged(&mut mix, 20);
+
+        let (encoder, timestamps) = consumer.join().unwrap();
+        let expected: Vec<u64> = (0..20).map(|i| mix.config.frames_to_ns(i * 16)).collect();
+        assert_eq!(timestamps, expected);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.dropped_buffers, 0);
+        assert!(stats.blocked_buffers > 0);
+    }
+
+    #[test]
+    fn test_backpressure_block_timeout() {
+        let mut mix = tagged_mix();
+        let encoder = mix.connect_encoder_with_policy(
+            false,
+            BackpressurePolicy::Block(Duration::from_millis(2)),
+        );
+
+        process_tagged(&mut mix, 6);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_buffers, 4);
+        assert_eq!(stats.blocked_buffers, 2);
+        assert_eq!(stats.dropped_buffers, 2);
+    }
+
+    #[test]
+    fn test_backpressure_disconnect_after() {
+        let mut mix = tagged_mix();
+        let encoder =
+            mix.connect_encoder_with_policy(false, BackpressurePolicy::DisconnectAfter(3));
+
+        process_tagged(&mut mix, 6);
+        assert!(encoder.is_connected());
+
+        process_tagged(&mut mix, 1);
+        assert!(!encoder.is_connected());
+        assert!(!mix.has_inputs());
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_buffers, 4);
+        assert_eq!(stats.dropped_buffers, 3);
+    }
+
+    #[test]
+    fn test_output_timestamps_follow_sample_count() {
+        let config = AudioConfig {
+            sample_rate: 44100,
+            ..AudioConfig::default()
+        };
+        let mut mix = AudioMix::with_config(config);
+        mix.set_start_timestamp(5_000);
+        let encoder = mix.connect_encoder(false);
+
+        process_tagged(&mut mix, 3);
+
+        let timestamps: Vec<u64> = encoder.receiver().try_iter().map(|d| d.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![5_000, 5_000 + 23_219_954, 5_000 + 46_439_909]
+        );
+        assert_eq!(timestamps[1] - timestamps[0], config.buffer_duration_ns());
+        // Rounding does not accumulate across buffers
+        assert_eq!(mix.next_timestamp(), 5_000 + 69_659_863);
+        assert_ne!(
+            mix.next_timestamp(),
+            5_000 + 3 * config.buffer_duration_ns()
+        );
+    }
 }
@@ -1,5 +1,6 @@
 //! Audio data types
 
+use obs_video::rational::{rescale, Rational, Rounding, TIMEBASE_NS};
 use std::fmt;
 
 /// Audio sample format
@@ -90,6 +91,33 @@ impl Default for AudioConfig {
     }
 }
 
+impl AudioConfig {
+    /// Duration of one sample as a timebase (1/sample_rate)
+    pub fn sample_timebase(&self) -> Rational {
+        Rational::new(1, self.sample_rate)
+    }
+
+    /// Timestamp offset of sample frame `frames`, rounded down
+    pub fn frames_to_ns(&self, frames: u64) -> u64 {
+        rescale(
+            frames as i64,
+            self.sample_timebase(),
+            TIMEBASE_NS,
+            Rounding::Down,
+        ) as u64
+    }
+
+    /// Sample frame at timestamp offset `ns`
+    pub fn ns_to_frames(&self, ns: u64, rounding: Rounding) -> u64 {
+        rescale(ns as i64, TIMEBASE_NS, self.sample_timebase(), rounding) as u64
+    }
+
+    /// Duration of one mix buffer, rounded down
+    pub fn buffer_duration_ns(&self) -> u64 {
+        self.frames_to_ns(self.frames as u64)
+    }
+}
+
 /// Audio data buffer
 pub struct AudioData {
     pub data: Vec<Vec<f32>>, // Per-channel planar data
@@ -9,7 +9,7 @@ use obs_compositor::{render_scene, Scene, SceneItem};
 fn bench_scene_iteration(c: &mut Criterion) {
     let mut group = c.benchmark_group("scene_iteration");
 
-    for item_count in [10, 50, 100, 200].iter() {
+    for item_count in [10, 50, 100, 200, 1000].iter() {
         let scene = Scene::new(1920, 1080);
 
         // Populate scene
@@ -41,7 +41,7 @@ fn bench_transform_calculation(c: &mut Criterion) {
     // Benchmark with dirty flag enabled (recalculates)
     group.bench_function("with_recalc", |b| {
         let scene = Scene::new(1920, 1080);
-        let id = scene.add_item(SceneItem::new(0, 100));
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
 
         b.iter(|| {
             // Mark dirty to force recalculation
@@ -67,13 +67,43 @@ fn bench_transform_calculation(c: &mut Criterion) {
         });
     });
 
+    // Every item dirty, as after a source resize or a frame of animation
+    for item_count in [10, 100, 300, 1000].iter() {
+        let scene = Scene::new(1920, 1080);
+        for i in 0..*item_count {
+            let mut item = SceneItem::new(0, i as u64 % 16);
+            item.pos = Vec2::new(i as f32, i as f32);
+            item.rotation = i as f32;
+            scene.add_item(item);
+        }
+        let dimensions: Vec<_> = (0..16).map(|i| (i as u64, 1920, 1080)).collect();
+        let ids: Vec<_> = scene
+            .get_items_snapshot()
+            .iter()
+            .map(|item| item.id)
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("all_dirty", item_count),
+            item_count,
+            |b, _| {
+                b.iter(|| {
+                    for &id in &ids {
+                        scene.update_item(id, |item| item.pos.x += 1.0);
+                    }
+                    scene.update_transforms(black_box(&dimensions));
+                });
+            },
+        );
+    }
+
     group.finish();
 }
 
 fn bench_render_scene(c: &mut Criterion) {
     let mut group = c.benchmark_group("render_scene");
 
-    for item_count in [10, 50, 100].iter() {
+    for item_count in [10, 50, 100, 1000].iter() {
         let scene = Scene::new(1920, 1080);
 
         for i in 0..*item_count {
@@ -155,7 +185,7 @@ fn bench_item_operations(c: &mut Criterion) {
 
         // Pre-populate
         let ids: Vec<_> = (0..1000)
-            .map(|i| scene.add_item(SceneItem::new(0, i)))
+            .map(|i| scene.add_item(SceneItem::new(0, i)).unwrap())
             .collect();
 
         let mut idx = 0;
@@ -167,7 +197,7 @@ fn bench_item_operations(c: &mut Criterion) {
 
     group.bench_function("update_item", |b| {
         let scene = Scene::new(1920, 1080);
-        let id = scene.add_item(SceneItem::new(0, 100));
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
 
         b.iter(|| {
             scene.update_item(id, |item| {
@@ -176,6 +206,21 @@ fn bench_item_operations(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("update_item_1000", |b| {
+        let scene = Scene::new(1920, 1080);
+        let ids: Vec<_> = (0..1000)
+            .map(|i| scene.add_item(SceneItem::new(0, i)).unwrap())
+            .collect();
+
+        let mut idx = 0;
+        b.iter(|| {
+            scene.update_item(ids[idx % ids.len()], |item| {
+                item.pos.x += 1.0;
+            });
+            idx += 7;
+        });
+    });
+
     group.finish();
 }
 
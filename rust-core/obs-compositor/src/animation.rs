@@ -0,0 +1,633 @@
+//! Keyframe animation of scene item properties
+//!
+//! An [`ItemAnimation`] holds one keyframe track per property (position,
+//! scale, rotation, crop and opacity) and a loop mode. A [`SceneAnimator`]
+//! plays animations on items of one scene: each `tick` samples the tracks
+//! at the render timestamp, writes the values into the items and marks
+//! their transforms dirty for the next `update_transforms`. Items whose
+//! animation has finished are no longer touched, so a still scene costs
+//! nothing.
+//!
+//! Timestamps are in nanoseconds, like frame timestamps. Animated values
+//! are not signaled; like source resizes they change every frame.
+
+use crate::scene::Scene;
+use crate::signals::SceneSignal;
+use crate::types::{SceneItem, SceneItemCrop};
+use glam::Vec2;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+/// Curve from one keyframe to the next
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// CSS `ease-in`
+    EaseIn,
+    /// CSS `ease-out`
+    EaseOut,
+    /// CSS `ease-in-out`
+    EaseInOut,
+    /// CSS `cubic-bezier(x1, y1, x2, y2)`; x values are clamped to 0..1
+    CubicBezier(f32, f32, f32, f32),
+    /// Damped spring settling on the next value
+    ///
+    /// `frequency` is in oscillations per segment; a `damping` ratio
+    /// below 1 overshoots, 1 or more does not.
+    Spring { frequency: f32, damping: f32 },
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t` in 0..1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                cubic_bezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2, t)
+            }
+            Easing::Spring { frequency, damping } => spring(frequency, damping, t),
+        }
+    }
+}
+
+/// Solve the bezier for `x` and return its `y`
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let curve = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    let slope = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * a + 6.0 * r * s * (b - a) + 3.0 * s * s * (1.0 - b)
+    };
+
+    // Newton's method, falling back to bisection on flat spots
+    let mut s = x;
+    for _ in 0..8 {
+        let error = curve(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            return curve(y1, y2, s);
+        }
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s = (s - error / d).clamp(0.0, 1.0);
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = curve(x1, x2, s);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    curve(y1, y2, s)
+}
+
+/// Step response of a damped spring, ending on 1
+///
+/// A slow or loose spring is still away from 1 when the segment ends; that
+/// residual is blended in over the segment (weighted by `t²`, so the start
+/// stays at rest) instead of jumping to the target on the last frame.
+fn spring(frequency: f32, damping: f32, t: f32) -> f32 {
+    let omega = TAU * frequency;
+    if omega <= 0.0 {
+        return t;
+    }
+    let zeta = damping.max(0.0);
+    let response = |t: f32| {
+        if zeta < 1.0 {
+            let damped = omega * (1.0 - zeta * zeta).sqrt();
+            let decay = (-zeta * omega * t).exp();
+            1.0 - decay * ((damped * t).cos() + zeta * omega / damped * (damped * t).sin())
+        } else {
+            // Overdamped springs are treated as critically damped
+            1.0 - (-omega * t).exp() * (1.0 + omega * t)
+        }
+    };
+    response(t) + (1.0 - response(1.0)) * t * t
+}
+
+/// A property value that can be interpolated
+pub trait Animatable: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Animatable for Vec2 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Animatable for SceneItemCrop {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        let edge = |a: i32, b: i32| (f32::lerp(a as f32, b as f32, t).round() as i32).max(0);
+        SceneItemCrop {
+            left: edge(a.left, b.left),
+            right: edge(a.right, b.right),
+            top: edge(a.top, b.top),
+            bottom: edge(a.bottom, b.bottom),
+        }
+    }
+}
+
+/// A value at a point in an animation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    /// Nanoseconds from the start of the animation
+    pub time: u64,
+    pub value: T,
+    /// Curve towards the next keyframe
+    pub easing: Easing,
+}
+
+/// Keyframes of one property, sorted by time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+        }
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder form of `insert`
+    pub fn key(mut self, time: u64, value: T, easing: Easing) -> Self {
+        self.insert(time, value, easing);
+        self
+    }
+
+    /// Add a keyframe, replacing any at the same time
+    pub fn insert(&mut self, time: u64, value: T, easing: Easing) {
+        let keyframe = Keyframe {
+            time,
+            value,
+            easing,
+        };
+        match self.keyframes.binary_search_by_key(&time, |k| k.time) {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    /// Remove the keyframe at `time`
+    pub fn remove(&mut self, time: u64) -> bool {
+        match self.keyframes.binary_search_by_key(&time, |k| k.time) {
+            Ok(index) => {
+                self.keyframes.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Time of the last keyframe
+    pub fn duration(&self) -> u64 {
+        self.keyframes.last().map_or(0, |k| k.time)
+    }
+
+    /// Value at `time`, holding the first and last keyframes outside them
+    pub fn sample(&self, time: u64) -> Option<T> {
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return self.keyframes.first().map(|k| k.value);
+        }
+        let from = &self.keyframes[next - 1];
+        let Some(to) = self.keyframes.get(next) else {
+            return Some(from.value);
+        };
+        let t = (time - from.time) as f32 / (to.time - from.time) as f32;
+        Some(T::lerp(from.value, to.value, from.easing.apply(t)))
+    }
+}
+
+/// What happens after the last keyframe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    /// Hold the last values and finish
+    #[default]
+    Once,
+    /// Start over from the first keyframe
+    Loop,
+    /// Play backwards to the start, then forwards again
+    PingPong,
+}
+
+/// Keyframe tracks for the animated properties of one item
+///
+/// Empty tracks leave their property alone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemAnimation {
+    pub pos: Track<Vec2>,
+    pub scale: Track<Vec2>,
+    /// Degrees
+    pub rotation: Track<f32>,
+    pub crop: Track<SceneItemCrop>,
+    pub opacity: Track<f32>,
+    pub loop_mode: LoopMode,
+}
+
+impl ItemAnimation {
+    /// Time of the last keyframe of any track
+    pub fn duration(&self) -> u64 {
+        self.pos
+            .duration()
+            .max(self.scale.duration())
+            .max(self.rotation.duration())
+            .max(self.crop.duration())
+            .max(self.opacity.duration())
+    }
+
+    /// Track time for `elapsed` nanoseconds of playback, and whether the
+    /// animation has finished
+    pub fn local_time(&self, elapsed: u64) -> (u64, bool) {
+        let duration = self.duration();
+        if duration == 0 {
+            return (0, true);
+        }
+        match self.loop_mode {
+            LoopMode::Once => (elapsed.min(duration), elapsed >= duration),
+            LoopMode::Loop => (elapsed % duration, false),
+            LoopMode::PingPong => {
+                let phase = elapsed % (2 * duration);
+                let time = if phase > duration {
+                    2 * duration - phase
+                } else {
+                    phase
+                };
+                (time, false)
+            }
+        }
+    }
+
+    /// Write the values at `time` into `item`, returning whether its
+    /// transform changed
+    pub fn apply(&self, item: &mut SceneItem, time: u64) -> bool {
+        let mut changed = false;
+        if let Some(pos) = self.pos.sample(time) {
+            changed |= item.pos != pos;
+            item.pos = pos;
+        }
+        if let Some(scale) = self.scale.sample(time) {
+            changed |= item.scale != scale;
+            item.scale = scale;
+        }
+        if let Some(rotation) = self.rotation.sample(time) {
+            changed |= item.rotation != rotation;
+            item.rotation = rotation;
+        }
+        if let Some(crop) = self.crop.sample(time) {
+            changed |= item.crop != crop;
+            item.crop = crop;
+        }
+        if let Some(opacity) = self.opacity.sample(time) {
+            item.opacity = opacity.clamp(0.0, 1.0);
+        }
+        if changed {
+            item.mark_transform_dirty();
+        }
+        changed
+    }
+}
+
+struct Playback {
+    animation: ItemAnimation,
+    // Set by the first tick when not given
+    start: Option<u64>,
+}
+
+/// Plays item animations on a scene
+pub struct SceneAnimator {
+    scene: Scene,
+    playing: HashMap<i64, Playback>,
+}
+
+impl SceneAnimator {
+    pub fn new(scene: Scene) -> Self {
+        Self {
+            scene,
+            playing: HashMap::new(),
+        }
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Play `animation` on an item from the next tick, replacing any
+    /// animation it has
+    pub fn play(&mut self, item: i64, animation: ItemAnimation) -> bool {
+        self.start(item, animation, None)
+    }
+
+    /// Play `animation` on an item as if it started at `start`
+    pub fn play_at(&mut self, item: i64, animation: ItemAnimation, start: u64) -> bool {
+        self.start(item, animation, Some(start))
+    }
+
+    fn start(&mut self, item: i64, animation: ItemAnimation, start: Option<u64>) -> bool {
+        if self.scene.item_index(item).is_none() {
+            return false;
+        }
+        self.playing.insert(item, Playback { animation, start });
+        true
+    }
+
+    /// Stop an item's animation, keeping its current values
+    pub fn stop(&mut self, item: i64) -> bool {
+        self.playing.remove(&item).is_some()
+    }
+
+    pub fn stop_all(&mut self) {
+        self.playing.clear();
+    }
+
+    pub fn is_playing(&self, item: i64) -> bool {
+        self.playing.contains_key(&item)
+    }
+
+    /// Whether any animation is playing
+    pub fn is_active(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    /// Number of items being animated
+    pub fn active_count(&self) -> usize {
+        self.playing.len()
+    }
+
+    /// Apply every animation at render `timestamp`
+    ///
+    /// Finished animations and animations of removed items are dropped
+    /// after their last update. Emits `TransformChanged` for every item
+    /// whose transform was marked dirty and returns their number.
+    pub fn tick(&mut self, timestamp: u64) -> usize {
+        if self.playing.is_empty() {
+            return 0;
+        }
+        let mut items = self.scene.items.write();
+        let mut signals = Vec::new();
+        self.playing.retain(|&id, playback| {
+            let Some(mut item) = items.get_mut(id) else {
+                return false;
+            };
+            let start = *playback.start.get_or_insert(timestamp);
+            let (time, finished) = playback
+                .animation
+                .local_time(timestamp.saturating_sub(start));
+            if playback.animation.apply(&mut item, time) {
+                signals.push(SceneSignal::TransformChanged { item: id });
+            }
+            !finished
+        });
+        drop(items);
+
+        let dirty = signals.len();
+        self.scene.signals.emit(&signals);
+        dirty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Alignment;
+    use glam::{Mat4, Vec3};
+
+    const MS: u64 = 1_000_000;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_easing_curves() {
+        let curves = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            Easing::Spring {
+                frequency: 2.0,
+                damping: 0.5,
+            },
+        ];
+        for easing in curves {
+            assert_near(easing.apply(0.0), 0.0);
+            assert_near(easing.apply(1.0), 1.0);
+        }
+
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert_near(Easing::EaseInOut.apply(0.5), 0.5);
+        // CSS `ease` is about 0.8 at the halfway point
+        assert_near(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0).apply(0.5), 0.8024);
+        assert_near(Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3), 0.3);
+
+        // Underdamped springs overshoot, critically damped ones do not
+        let bouncy = Easing::Spring {
+            frequency: 2.0,
+            damping: 0.2,
+        };
+        assert!((0..100).any(|i| bouncy.apply(i as f32 / 100.0) > 1.0));
+        let stiff = Easing::Spring {
+            frequency: 2.0,
+            damping: 1.0,
+        };
+        assert!((0..100).all(|i| stiff.apply(i as f32 / 100.0) <= 1.0));
+    }
+
+    #[test]
+    fn test_spring_ends_continuously() {
+        // Far from settled at the end of the segment
+        let springs = [(0.5, 0.2), (0.25, 0.0), (0.3, 1.0), (2.0, 0.5)];
+        for (frequency, damping) in springs {
+            let spring = Easing::Spring { frequency, damping };
+            assert_eq!(spring.apply(1.0), 1.0);
+            assert_near(spring.apply(0.0), 0.0);
+            for i in 0..1000 {
+                let (a, b) = (i as f32 / 1000.0, (i + 1) as f32 / 1000.0);
+                let step = (spring.apply(b) - spring.apply(a)).abs();
+                assert!(step < 0.02, "{frequency}/{damping} jumps {step} at {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_track_sampling() {
+        let mut track = Track::new()
+            .key(100, 10.0, Easing::Linear)
+            .key(300, 30.0, Easing::Linear)
+            .key(200, 20.0, Easing::EaseIn);
+        assert_eq!(track.keyframes()[1].value, 20.0);
+        assert_eq!(track.duration(), 300);
+
+        assert_eq!(track.sample(0), Some(10.0));
+        assert_eq!(track.sample(150), Some(15.0));
+        assert_eq!(track.sample(200), Some(20.0));
+        assert!(track.sample(250).unwrap() < 25.0);
+        assert_eq!(track.sample(1000), Some(30.0));
+
+        track.insert(200, 0.0, Easing::Linear);
+        assert_eq!(track.sample(150), Some(5.0));
+        assert!(track.remove(200));
+        assert!(!track.remove(200));
+        assert_eq!(track.sample(150), Some(15.0));
+        assert_eq!(Track::<f32>::new().sample(0), None);
+
+        let crop = Track::new()
+            .key(0, SceneItemCrop::default(), Easing::Linear)
+            .key(
+                10,
+                SceneItemCrop {
+                    left: 10,
+                    right: 0,
+                    top: 5,
+                    bottom: 0,
+                },
+                Easing::Linear,
+            );
+        let half = crop.sample(5).unwrap();
+        assert_eq!((half.left, half.top), (5, 3));
+    }
+
+    #[test]
+    fn test_loop_modes() {
+        let mut animation = ItemAnimation {
+            opacity: Track::new()
+                .key(0, 0.0, Easing::Linear)
+                .key(100, 1.0, Easing::Linear),
+            ..Default::default()
+        };
+        assert_eq!(animation.local_time(50), (50, false));
+        assert_eq!(animation.local_time(150), (100, true));
+
+        animation.loop_mode = LoopMode::Loop;
+        assert_eq!(animation.local_time(150), (50, false));
+
+        animation.loop_mode = LoopMode::PingPong;
+        assert_eq!(animation.local_time(130), (70, false));
+        assert_eq!(animation.local_time(230), (30, false));
+
+        assert_eq!(ItemAnimation::default().local_time(10), (0, true));
+    }
+
+    #[test]
+    fn test_animator_drives_scene() {
+        let scene = Scene::new(1920, 1080);
+        let mut item = SceneItem::new(0, 1);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        let id = scene.add_item(item).unwrap();
+        let still = scene.add_item(SceneItem::new(0, 2)).unwrap();
+        scene.update_transforms(&[(1, 100, 100), (2, 100, 100)]);
+
+        // Slide in from the left while fading in
+        let mut animator = SceneAnimator::new(scene.clone());
+        let signals = scene.subscribe();
+        let slide = ItemAnimation {
+            pos: Track::new()
+                .key(0, Vec2::new(-100.0, 50.0), Easing::Linear)
+                .key(1000 * MS, Vec2::new(100.0, 50.0), Easing::Linear),
+            opacity: Track::new()
+                .key(0, 0.0, Easing::Linear)
+                .key(500 * MS, 1.0, Easing::Linear),
+            ..Default::default()
+        };
+        assert!(animator.play(id, slide));
+        assert!(!animator.play(99, ItemAnimation::default()));
+
+        assert_eq!(animator.tick(5000 * MS), 1);
+        assert_eq!(
+            signals.try_iter().collect::<Vec<_>>(),
+            vec![SceneSignal::TransformChanged { item: id }]
+        );
+        let item = scene.find_item(id).unwrap();
+        assert_eq!(item.pos, Vec2::new(-100.0, 50.0));
+        assert_eq!(item.opacity, 0.0);
+        assert!(item.transform_dirty);
+        assert!(!scene.find_item(still).unwrap().transform_dirty);
+
+        animator.tick(5250 * MS);
+        scene.update_transforms(&[(1, 100, 100), (2, 100, 100)]);
+        let item = scene.find_item(id).unwrap();
+        assert_eq!(item.opacity, 0.5);
+        assert!(item
+            .draw_transform
+            .abs_diff_eq(Mat4::from_translation(Vec3::new(-50.0, 50.0, 0.0)), 1e-3));
+
+        // The last tick lands on the final values, then nothing is touched
+        assert_eq!(animator.tick(6500 * MS), 1);
+        assert!(!animator.is_active());
+        scene.update_transforms(&[(1, 100, 100), (2, 100, 100)]);
+        assert_eq!(animator.tick(7000 * MS), 0);
+        assert_eq!(signals.try_iter().count(), 2);
+        let item = scene.find_item(id).unwrap();
+        assert_eq!((item.pos, item.opacity), (Vec2::new(100.0, 50.0), 1.0));
+        assert!(!item.transform_dirty);
+    }
+
+    #[test]
+    fn test_stop_and_removed_items() {
+        let scene = Scene::new(1920, 1080);
+        let a = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let b = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let spin = ItemAnimation {
+            rotation: Track::new().key(0, 0.0, Easing::Linear).key(
+                1000 * MS,
+                360.0,
+                Easing::Linear,
+            ),
+            loop_mode: LoopMode::Loop,
+            ..Default::default()
+        };
+
+        let mut animator = SceneAnimator::new(scene.clone());
+        animator.play_at(a, spin.clone(), 0);
+        animator.play_at(b, spin, 0);
+        animator.tick(2250 * MS);
+        assert_eq!(scene.find_item(a).unwrap().rotation, 90.0);
+
+        assert!(animator.stop(a));
+        scene.remove_item(b);
+        animator.tick(2500 * MS);
+        assert_eq!(scene.find_item(a).unwrap().rotation, 90.0);
+        assert!(!animator.is_playing(b));
+        assert_eq!(animator.active_count(), 0);
+    }
+}
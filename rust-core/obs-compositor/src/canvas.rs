@@ -0,0 +1,501 @@
+//! Canvases: several outputs rendered from one set of sources
+//!
+//! A canvas owns a set of named scenes, a resolution and the `VideoOutput`
+//! its encoders connect to. Sources live in a `SourceRegistry` shared by all
+//! canvases, so a 16:9 and a 9:16 canvas can show the same camera with
+//! different layouts. `CanvasSet::tick` renders every canvas from one
+//! graphics tick, against one snapshot of the source sizes.
+
+use crate::cull::render_scene_culled;
+use crate::render::RenderCommand;
+use crate::scene::Scene;
+use obs_video::{FrameRate, VideoFrame, VideoOutput, VideoOutputInfo};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Source table shared by every canvas of a `CanvasSet`
+#[derive(Clone, Default)]
+pub struct SourceRegistry {
+    sources: Arc<RwLock<HashMap<u64, (u32, u32)>>>,
+    // Sources without alpha, whose items hide what is under them
+    opaque: Arc<RwLock<HashSet<u64>>>,
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a source or update its size
+    pub fn set_source_size(&self, source_id: u64, width: u32, height: u32) {
+        self.sources.write().insert(source_id, (width, height));
+    }
+
+    pub fn source_size(&self, source_id: u64) -> Option<(u32, u32)> {
+        self.sources.read().get(&source_id).copied()
+    }
+
+    pub fn contains(&self, source_id: u64) -> bool {
+        self.sources.read().contains_key(&source_id)
+    }
+
+    /// Mark a source as having no alpha, so items under it can be culled
+    pub fn set_source_opaque(&self, source_id: u64, opaque: bool) {
+        if opaque {
+            self.opaque.write().insert(source_id);
+        } else {
+            self.opaque.write().remove(&source_id);
+        }
+    }
+
+    pub fn is_opaque(&self, source_id: u64) -> bool {
+        self.opaque.read().contains(&source_id)
+    }
+
+    pub fn opaque_sources(&self) -> HashSet<u64> {
+        self.opaque.read().clone()
+    }
+
+    pub fn remove_source(&self, source_id: u64) -> bool {
+        self.opaque.write().remove(&source_id);
+        self.sources.write().remove(&source_id).is_some()
+    }
+
+    pub fn source_count(&self) -> usize {
+        self.sources.read().len()
+    }
+
+    /// Sizes in the form `Scene::update_transforms` takes
+    pub fn dimensions(&self) -> Vec<(u64, u32, u32)> {
+        self.sources
+            .read()
+            .iter()
+            .map(|(&id, &(width, height))| (id, width, height))
+            .collect()
+    }
+}
+
+/// One output canvas: scenes, resolution and video output
+pub struct Canvas {
+    id: u64,
+    name: String,
+    width: u32,
+    height: u32,
+    scenes: BTreeMap<String, Scene>,
+    program: Option<String>,
+    output: VideoOutput,
+    rendered_frames: AtomicU64,
+    skipped_frames: AtomicU64,
+}
+
+impl Canvas {
+    fn new(id: u64, name: &str, width: u32, height: u32, frame_rate: FrameRate) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            width,
+            height,
+            scenes: BTreeMap::new(),
+            program: None,
+            output: VideoOutput::new(width, height, frame_rate.num(), frame_rate.den()),
+            rendered_frames: AtomicU64::new(0),
+            skipped_frames: AtomicU64::new(0),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Video output encoders of this canvas connect to
+    pub fn output(&self) -> &VideoOutput {
+        &self.output
+    }
+
+    /// Add an empty scene at the canvas resolution
+    ///
+    /// The first scene added becomes the program scene. Returns None if the
+    /// name is taken.
+    pub fn add_scene(&mut self, name: &str) -> Option<&Scene> {
+        if self.scenes.contains_key(name) {
+            return None;
+        }
+        if self.program.is_none() {
+            self.program = Some(name.to_string());
+        }
+        Some(
+            self.scenes
+                .entry(name.to_string())
+                .or_insert_with(|| Scene::new(self.width, self.height)),
+        )
+    }
+
+    /// Remove a scene; removing the program scene leaves the canvas empty
+    pub fn remove_scene(&mut self, name: &str) -> bool {
+        if self.scenes.remove(name).is_none() {
+            return false;
+        }
+        if self.program.as_deref() == Some(name) {
+            self.program = None;
+        }
+        true
+    }
+
+    pub fn scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    pub fn scene_names(&self) -> impl Iterator<Item = &str> {
+        self.scenes.keys().map(String::as_str)
+    }
+
+    /// Switch the scene this canvas outputs
+    pub fn set_program(&mut self, name: &str) -> bool {
+        if !self.scenes.contains_key(name) {
+            return false;
+        }
+        self.program = Some(name.to_string());
+        true
+    }
+
+    pub fn program_name(&self) -> Option<&str> {
+        self.program.as_deref()
+    }
+
+    pub fn program(&self) -> Option<&Scene> {
+        self.program.as_ref().and_then(|name| self.scenes.get(name))
+    }
+
+    /// Change the canvas resolution, resizing its scenes and output
+    ///
+    /// Connected encoders receive a format change event. Returns false and
+    /// leaves the canvas unchanged if the output rejects the size.
+    pub fn set_resolution(&mut self, width: u32, height: u32) -> bool {
+        let info = VideoOutputInfo {
+            width,
+            height,
+            ..self.output.info()
+        };
+        if !self.output.reconfigure(info) {
+            return false;
+        }
+
+        self.width = width;
+        self.height = height;
+        for scene in self.scenes.values_mut() {
+            scene.set_dimensions(width, height);
+        }
+        true
+    }
+
+    /// Remove every item showing `source_id` from all scenes
+    fn remove_source_items(&self, source_id: u64) -> usize {
+        let mut removed = 0;
+        for scene in self.scenes.values() {
+            for item in scene.get_items_snapshot() {
+                if item.source_id == source_id && scene.remove_item(item.id) {
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    pub fn stats(&self) -> CanvasStats {
+        CanvasStats {
+            rendered_frames: self.rendered_frames.load(Ordering::Relaxed),
+            skipped_frames: self.skipped_frames.load(Ordering::Relaxed),
+            encoders: self.output.encoder_count(),
+        }
+    }
+}
+
+/// Per-canvas render statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanvasStats {
+    /// Frames rendered and submitted to the output
+    pub rendered_frames: u64,
+    /// Ticks without a free output frame
+    pub skipped_frames: u64,
+    /// Encoders connected to the canvas output
+    pub encoders: usize,
+}
+
+/// Canvases driven by one graphics tick
+pub struct CanvasSet {
+    frame_rate: FrameRate,
+    sources: SourceRegistry,
+    canvases: Vec<Canvas>,
+    next_id: u64,
+}
+
+impl CanvasSet {
+    /// All canvases of a set run at `frame_rate`
+    pub fn new(frame_rate: FrameRate) -> Self {
+        Self {
+            frame_rate,
+            sources: SourceRegistry::new(),
+            canvases: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn frame_rate(&self) -> FrameRate {
+        self.frame_rate
+    }
+
+    /// Sources shared by all canvases
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    /// Add a canvas and return its ID
+    pub fn add_canvas(&mut self, name: &str, width: u32, height: u32) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.canvases
+            .push(Canvas::new(id, name, width, height, self.frame_rate));
+        id
+    }
+
+    /// Remove a canvas, shutting down its output
+    pub fn remove_canvas(&mut self, id: u64) -> bool {
+        match self.canvases.iter().position(|canvas| canvas.id == id) {
+            Some(index) => {
+                self.canvases.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn canvas(&self, id: u64) -> Option<&Canvas> {
+        self.canvases.iter().find(|canvas| canvas.id == id)
+    }
+
+    pub fn canvas_mut(&mut self, id: u64) -> Option<&mut Canvas> {
+        self.canvases.iter_mut().find(|canvas| canvas.id == id)
+    }
+
+    pub fn canvases(&self) -> &[Canvas] {
+        &self.canvases
+    }
+
+    pub fn canvas_count(&self) -> usize {
+        self.canvases.len()
+    }
+
+    /// Remove a shared source and every item showing it, in all canvases
+    ///
+    /// Returns the number of scene items removed.
+    pub fn remove_source(&self, source_id: u64) -> usize {
+        self.sources.remove_source(source_id);
+        self.canvases
+            .iter()
+            .map(|canvas| canvas.remove_source_items(source_id))
+            .sum()
+    }
+
+    /// Render one frame on every canvas
+    ///
+    /// Transforms are updated from one snapshot of the source sizes, then
+    /// `render` draws each canvas's program scene into a frame locked from
+    /// its output, which is submitted at `timestamp`. Items off the canvas
+    /// or under opaque sources are culled. Canvases without a program
+    /// scene are skipped. Returns the number of frames submitted.
+    pub fn tick<F>(&self, timestamp: u64, mut render: F) -> usize
+    where
+        F: FnMut(&Canvas, &[RenderCommand], &mut VideoFrame),
+    {
+        let dimensions = self.sources.dimensions();
+        let opaque = self.sources.opaque_sources();
+        let mut submitted = 0;
+
+        for canvas in &self.canvases {
+            let Some(scene) = canvas.program() else {
+                continue;
+            };
+            scene.update_transforms(&dimensions);
+            let commands = render_scene_culled(scene, &opaque);
+
+            let Some(mut frame) = canvas.output.lock_frame() else {
+                canvas.skipped_frames.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            render(canvas, &commands, &mut frame);
+            if canvas.output.unlock_frame(frame, timestamp) {
+                canvas.rendered_frames.fetch_add(1, Ordering::Relaxed);
+                submitted += 1;
+            } else {
+                canvas.skipped_frames.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        submitted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Alignment, SceneItem};
+    use glam::Vec2;
+    use obs_video::EncoderEvent;
+    use std::time::Duration;
+
+    fn dual_output() -> (CanvasSet, u64, u64) {
+        let mut set = CanvasSet::new(FrameRate::new(30, 1));
+        let horizontal = set.add_canvas("horizontal", 1920, 1080);
+        let vertical = set.add_canvas("vertical", 1080, 1920);
+        (set, horizontal, vertical)
+    }
+
+    #[test]
+    fn test_canvas_scenes_and_program() {
+        let (mut set, horizontal, _) = dual_output();
+        let canvas = set.canvas_mut(horizontal).unwrap();
+
+        assert!(canvas.add_scene("Main").is_some());
+        assert!(canvas.add_scene("Main").is_none());
+        assert!(canvas.add_scene("BRB").is_some());
+        assert_eq!(canvas.program_name(), Some("Main"));
+        assert_eq!(canvas.scene("BRB").unwrap().dimensions(), (1920, 1080));
+
+        assert!(canvas.set_program("BRB"));
+        assert!(!canvas.set_program("Missing"));
+        assert_eq!(canvas.program_name(), Some("BRB"));
+
+        assert!(canvas.remove_scene("BRB"));
+        assert!(canvas.program().is_none());
+        assert_eq!(canvas.scene_names().collect::<Vec<_>>(), vec!["Main"]);
+    }
+
+    #[test]
+    fn test_shared_source_in_two_layouts() {
+        let (mut set, horizontal, vertical) = dual_output();
+        set.sources().set_source_size(7, 1920, 1080);
+
+        let h_scene = set
+            .canvas_mut(horizontal)
+            .unwrap()
+            .add_scene("Main")
+            .unwrap();
+        let h_item = h_scene.add_item(SceneItem::new(0, 7)).unwrap();
+
+        let v_scene = set.canvas_mut(vertical).unwrap().add_scene("Main").unwrap();
+        let v_item = v_scene.add_item(SceneItem::new(0, 7)).unwrap();
+        v_scene.update_item(v_item, |item| item.scale = Vec2::splat(0.5625));
+
+        let mut commands = HashMap::new();
+        assert_eq!(
+            set.tick(0, |canvas, cmds, _| {
+                commands.insert(canvas.id(), cmds.to_vec());
+            }),
+            2
+        );
+        assert_eq!(commands[&horizontal][0].source_id, 7);
+        assert_eq!(commands[&vertical][0].source_id, 7);
+        assert_ne!(
+            commands[&horizontal][0].transform,
+            commands[&vertical][0].transform
+        );
+
+        // Both canvases picked up the shared source size
+        let h_canvas = set.canvas(horizontal).unwrap();
+        let h_item = h_canvas.scene("Main").unwrap().find_item(h_item).unwrap();
+        assert_eq!((h_item.last_width, h_item.last_height), (1920, 1080));
+
+        assert_eq!(set.remove_source(7), 2);
+        assert!(!set.sources().contains(7));
+        assert_eq!(h_canvas.scene("Main").unwrap().item_count(), 0);
+    }
+
+    #[test]
+    fn test_tick_culls_hidden_items() {
+        let (mut set, horizontal, _) = dual_output();
+        set.sources().set_source_size(1, 1280, 720);
+        set.sources().set_source_size(2, 1920, 1080);
+        let scene = set
+            .canvas_mut(horizontal)
+            .unwrap()
+            .add_scene("Main")
+            .unwrap();
+        scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let mut camera = SceneItem::new(0, 2);
+        camera.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        scene.add_item(camera).unwrap();
+
+        let mut count = 0;
+        set.tick(0, |_, cmds, _| count = cmds.len());
+        assert_eq!(count, 2);
+
+        set.sources().set_source_opaque(2, true);
+        assert!(set.sources().is_opaque(2));
+        set.tick(1, |_, cmds, _| count = cmds.len());
+        assert_eq!(count, 1);
+        let stats = set.canvas(horizontal).unwrap().program().unwrap().stats();
+        assert_eq!(stats.culled_covered, 1);
+
+        set.remove_source(2);
+        assert!(!set.sources().is_opaque(2));
+    }
+
+    #[test]
+    fn test_tick_feeds_independent_encoders() {
+        let (mut set, horizontal, vertical) = dual_output();
+        set.canvas_mut(horizontal).unwrap().add_scene("Main");
+        set.canvas_mut(vertical).unwrap().add_scene("Main");
+
+        let h_encoder = set.canvas(horizontal).unwrap().output().connect_encoder(1);
+        let v_encoder = set.canvas(vertical).unwrap().output().connect_encoder(1);
+
+        assert_eq!(set.tick(1000, |_, _, _| {}), 2);
+
+        let recv = |encoder: &obs_video::EncoderHandle| {
+            encoder
+                .recv_timeout(Duration::from_millis(500))
+                .ok()
+                .and_then(EncoderEvent::into_frame)
+                .expect("no frame received")
+        };
+        let h_frame = recv(&h_encoder);
+        let v_frame = recv(&v_encoder);
+        assert_eq!((h_frame.width, h_frame.height), (1920, 1080));
+        assert_eq!((v_frame.width, v_frame.height), (1080, 1920));
+
+        assert_eq!(set.canvas(horizontal).unwrap().stats().encoders, 1);
+        assert_eq!(set.canvas(vertical).unwrap().stats().rendered_frames, 1);
+
+        // Canvases without a program scene are not rendered
+        set.canvas_mut(vertical).unwrap().remove_scene("Main");
+        assert_eq!(set.tick(2000, |_, _, _| {}), 1);
+    }
+
+    #[test]
+    fn test_canvas_resolution_change() {
+        let (mut set, _, vertical) = dual_output();
+        let canvas = set.canvas_mut(vertical).unwrap();
+        canvas.add_scene("Main");
+
+        assert!(canvas.set_resolution(720, 1280));
+        assert_eq!(canvas.dimensions(), (720, 1280));
+        assert_eq!(canvas.scene("Main").unwrap().dimensions(), (720, 1280));
+        assert_eq!(canvas.output().info().width, 720);
+
+        assert!(!canvas.set_resolution(0, 1280));
+        assert_eq!(canvas.dimensions(), (720, 1280));
+
+        assert!(set.remove_canvas(vertical));
+        assert_eq!(set.canvas_count(), 1);
+    }
+}
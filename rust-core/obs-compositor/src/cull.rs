@@ -0,0 +1,251 @@
+//! Culling of items that cannot be seen
+//!
+//! Items whose box lies outside the scene, and items hidden under an
+//! opaque item above them, produce no render command. An item counts as
+//! opaque when its source has no alpha, it blends normally at full
+//! opacity and its size is known; which sources have no alpha is up to the
+//! caller. Culled counts are added to the scene's stats.
+//!
+//! Groups have no size of their own, so their items are never culled as
+//! off-canvas.
+
+use crate::render::RenderCommand;
+use crate::scene::{ItemRef, Scene};
+use crate::types::{BlendMode, BoundsType};
+use glam::{Mat4, Vec2, Vec3};
+use std::collections::HashSet;
+
+/// An item considered for drawing
+struct Candidate {
+    command: RenderCommand,
+    // Position among the visible items
+    index: usize,
+    // Canvas-space bounding box, if the item has a size
+    extent: Option<(Vec2, Vec2)>,
+    // Drawn quad, if it hides what is under it
+    opaque: Option<[Vec2; 4]>,
+}
+
+fn corners(transform: &Mat4, width: f32, height: f32) -> [Vec2; 4] {
+    [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+        .map(|(x, y)| transform.transform_point3(Vec3::new(x, y, 0.0)).truncate())
+}
+
+fn extent(points: &[Vec2]) -> (Vec2, Vec2) {
+    let min = points.iter().copied().reduce(Vec2::min).unwrap();
+    let max = points.iter().copied().reduce(Vec2::max).unwrap();
+    (min, max)
+}
+
+fn candidate(item: ItemRef<'_>, opaque_sources: &HashSet<u64>, index: usize) -> Candidate {
+    let cached = item.transform;
+    let command = RenderCommand::from_parts(item.inputs, cached);
+    if cached.last_width == 0 || cached.last_height == 0 {
+        return Candidate {
+            command,
+            index,
+            extent: None,
+            opaque: None,
+        };
+    }
+
+    let drawn = corners(
+        &cached.draw_transform,
+        command.crop.calc_width(cached.last_width) as f32,
+        command.crop.calc_height(cached.last_height) as f32,
+    );
+    let boxed = corners(&cached.box_transform, 1.0, 1.0);
+    // Without crop_to_bounds a scaled-outer source spills out of its box
+    let extent = if item.bounds_type == BoundsType::None {
+        extent(&boxed)
+    } else {
+        let (a_min, a_max) = extent(&boxed);
+        let (b_min, b_max) = extent(&drawn);
+        (a_min.min(b_min), a_max.max(b_max))
+    };
+    let opaque = (opaque_sources.contains(&item.source_id)
+        && item.blend_mode == BlendMode::Normal
+        && command.opacity >= 1.0)
+        .then_some(drawn);
+
+    Candidate {
+        command,
+        index,
+        extent: Some(extent),
+        opaque,
+    }
+}
+
+/// Whether `point` is inside the convex quad `quad`
+fn quad_contains(quad: &[Vec2; 4], point: Vec2) -> bool {
+    let mut sign = 0.0f32;
+    for i in 0..4 {
+        let (a, b) = (quad[i], quad[(i + 1) % 4]);
+        let cross = (b - a).perp_dot(point - a);
+        if cross.abs() < 1e-3 {
+            continue;
+        }
+        if sign != 0.0 && cross.signum() != sign {
+            return false;
+        }
+        sign = cross.signum();
+    }
+    true
+}
+
+/// Render commands for the scene's visible items, back to front, without
+/// the ones that cannot be seen
+///
+/// Items of sources in `opaque_sources` hide what is under them.
+pub fn render_scene_culled(scene: &Scene, opaque_sources: &HashSet<u64>) -> Vec<RenderCommand> {
+    let mut commands = Vec::new();
+    cull_items(scene, opaque_sources, |command, _| commands.push(command));
+    commands
+}
+
+/// Pass the render command of each item left after culling to `emit`,
+/// back to front, with the item borrowed in place
+pub(crate) fn cull_items<F>(scene: &Scene, opaque_sources: &HashSet<u64>, mut emit: F)
+where
+    F: FnMut(RenderCommand, ItemRef<'_>),
+{
+    scene.render_visible(|items| {
+        let candidates = items
+            .iter()
+            .enumerate()
+            .map(|(index, &item)| candidate(item, opaque_sources, index));
+        for (command, index) in cull(scene, candidates) {
+            emit(command, items[index]);
+        }
+    });
+}
+
+/// The commands of the candidates that can be seen, with their positions
+fn cull(
+    scene: &Scene,
+    candidates: impl DoubleEndedIterator<Item = Candidate> + ExactSizeIterator,
+) -> Vec<(RenderCommand, usize)> {
+    let (width, height) = scene.dimensions();
+    let canvas = (Vec2::ZERO, Vec2::new(width as f32, height as f32));
+    let clip = width > 0 && height > 0;
+
+    let mut covers: Vec<[Vec2; 4]> = Vec::new();
+    let mut commands = Vec::with_capacity(candidates.len());
+    let (mut offscreen, mut covered) = (0, 0);
+    for candidate in candidates.rev() {
+        let Some((mut min, mut max)) = candidate.extent else {
+            commands.push((candidate.command, candidate.index));
+            continue;
+        };
+        if clip {
+            min = min.max(canvas.0);
+            max = max.min(canvas.1);
+            if min.x >= max.x || min.y >= max.y {
+                offscreen += 1;
+                continue;
+            }
+        }
+        let visible = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        if covers
+            .iter()
+            .any(|quad| visible.iter().all(|&p| quad_contains(quad, p)))
+        {
+            covered += 1;
+            continue;
+        }
+        if let Some(quad) = candidate.opaque {
+            covers.push(quad);
+        }
+        commands.push((candidate.command, candidate.index));
+    }
+    commands.reverse();
+
+    scene.record_culled(offscreen, covered);
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Alignment, SceneItem};
+
+    fn top_left(source_id: u64, x: f32, y: f32) -> SceneItem {
+        let mut item = SceneItem::new(0, source_id);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        item.pos = Vec2::new(x, y);
+        item
+    }
+
+    fn sources(commands: &[RenderCommand]) -> Vec<u64> {
+        commands.iter().map(|c| c.source_id).collect()
+    }
+
+    #[test]
+    fn test_offscreen_items() {
+        let scene = Scene::new(1920, 1080);
+        scene.add_item(top_left(1, 100.0, 100.0)).unwrap();
+        scene.add_item(top_left(2, 1920.0, 0.0)).unwrap();
+        scene.add_item(top_left(3, -100.0, -100.0)).unwrap();
+        let mut rotated = top_left(4, -50.0, 500.0);
+        rotated.rotation = 45.0;
+        scene.add_item(rotated).unwrap();
+        // Size unknown until the source reports one
+        scene.add_item(top_left(5, -5000.0, 0.0)).unwrap();
+        scene.update_transforms(&[(1, 100, 100), (2, 100, 100), (3, 100, 100), (4, 100, 100)]);
+
+        let commands = render_scene_culled(&scene, &HashSet::new());
+        assert_eq!(sources(&commands), [1, 4, 5]);
+        let stats = scene.stats();
+        assert_eq!((stats.culled_offscreen, stats.culled_covered), (2, 0));
+    }
+
+    #[test]
+    fn test_opaque_cover() {
+        let scene = Scene::new(1920, 1080);
+        scene.add_item(top_left(1, 0.0, 0.0)).unwrap();
+        scene.add_item(top_left(2, 10.0, 10.0)).unwrap();
+        let mut background = top_left(3, 0.0, 0.0);
+        background.bounds_type = BoundsType::Stretch;
+        background.bounds = Vec2::new(1920.0, 1080.0);
+        let background = scene.add_item(background).unwrap();
+        scene.add_item(top_left(4, 50.0, 50.0)).unwrap();
+        scene.update_transforms(&[(1, 1920, 1080), (2, 100, 100), (3, 640, 360), (4, 64, 64)]);
+
+        // Only sources known to be opaque cover anything
+        assert_eq!(render_scene_culled(&scene, &HashSet::new()).len(), 4);
+        let opaque = HashSet::from([3, 4]);
+        assert_eq!(sources(&render_scene_culled(&scene, &opaque)), [3, 4]);
+        assert_eq!(scene.stats().culled_covered, 2);
+
+        // Translucent or non-normal items hide nothing
+        scene.update_item(background, |item| item.opacity = 0.9);
+        assert_eq!(render_scene_culled(&scene, &opaque).len(), 4);
+        scene.update_item(background, |item| {
+            item.opacity = 1.0;
+            item.blend_mode = BlendMode::Additive;
+        });
+        assert_eq!(render_scene_culled(&scene, &opaque).len(), 4);
+
+        scene.update_item(background, |item| item.blend_mode = BlendMode::Normal);
+        scene.update_transforms(&[(1, 1920, 1080), (2, 100, 100), (3, 640, 360), (4, 64, 64)]);
+        assert_eq!(render_scene_culled(&scene, &opaque).len(), 2);
+
+        // A smaller opaque item hides only what lies inside it
+        let scene = Scene::new(1920, 1080);
+        scene.add_item(top_left(2, 60.0, 60.0)).unwrap();
+        scene.add_item(top_left(2, 600.0, 60.0)).unwrap();
+        scene.add_item(top_left(4, 50.0, 50.0)).unwrap();
+        scene.update_transforms(&[(2, 100, 100), (4, 200, 200)]);
+        let commands = render_scene_culled(&scene, &opaque);
+        assert_eq!(sources(&commands), [2, 4]);
+        assert_eq!(commands[0].transform.w_axis.x, 600.0);
+    }
+
+    #[test]
+    fn test_groups_are_not_clipped() {
+        let group = Scene::new_group();
+        group.add_item(top_left(1, -500.0, -500.0)).unwrap();
+        group.update_transforms(&[(1, 100, 100)]);
+        assert_eq!(render_scene_culled(&group, &HashSet::new()).len(), 1);
+    }
+}
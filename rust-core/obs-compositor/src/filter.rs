@@ -0,0 +1,656 @@
+//! Video filter chains
+//!
+//! Sources and scene items carry an ordered list of named filters that can
+//! be switched on and off, like the filter stacks of OBS sources. A
+//! `FilterPipeline` holds the source chains and turns a scene into a
+//! `RenderPlan`: filter passes that render each chain into render targets,
+//! then the scene's commands drawing those targets in place of the
+//! sources. Item filters run after the filters of the item's source.
+//!
+//! Disabled and identity filters produce no pass. Chain outputs are cached
+//! by source, filters and input size; while a source reports the same
+//! frame version, its passes are not emitted again.
+
+use crate::cull::cull_items;
+use crate::render::RenderCommand;
+use crate::scene::Scene;
+use crate::transform::update_item_transform;
+use std::collections::{HashMap, HashSet};
+
+/// Color correction, as in `color-correction-filter.c`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCorrection {
+    /// -3 to 3; positive brightens midtones
+    pub gamma: f32,
+    /// -4 to 4
+    pub contrast: f32,
+    /// -1 to 1, added to every channel
+    pub brightness: f32,
+    /// -1 (grayscale) to 5
+    pub saturation: f32,
+    /// Degrees
+    pub hue_shift: f32,
+    /// 0 to 1
+    pub opacity: f32,
+    pub multiply: [f32; 3],
+    pub add: [f32; 3],
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self {
+            gamma: 0.0,
+            contrast: 0.0,
+            brightness: 0.0,
+            saturation: 0.0,
+            hue_shift: 0.0,
+            opacity: 1.0,
+            multiply: [1.0; 3],
+            add: [0.0; 3],
+        }
+    }
+}
+
+/// Crop or pad each edge, as in `crop-filter.c`
+///
+/// Positive values cut pixels off, negative values add transparent ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CropPad {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/// Chroma key, as in `chroma_key_filter_v2.effect`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaKey {
+    pub key_color: [u8; 3],
+    /// Chroma distance keyed out completely (0 to 1)
+    pub similarity: f32,
+    /// Width of the partially transparent edge
+    pub smoothness: f32,
+    /// Width of the desaturated edge that removes color spill
+    pub spill: f32,
+}
+
+impl Default for ChromaKey {
+    fn default() -> Self {
+        Self {
+            key_color: [0, 255, 0],
+            similarity: 0.4,
+            smoothness: 0.08,
+            spill: 0.1,
+        }
+    }
+}
+
+/// Sharpen, as in `sharpness.effect`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sharpen {
+    /// 0 to 1
+    pub amount: f32,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self { amount: 0.08 }
+    }
+}
+
+/// A video filter and its settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    ColorCorrection(ColorCorrection),
+    CropPad(CropPad),
+    ChromaKey(ChromaKey),
+    Sharpen(Sharpen),
+}
+
+impl Filter {
+    /// Whether the filter leaves every input unchanged
+    pub fn is_identity(&self) -> bool {
+        match self {
+            Filter::ColorCorrection(c) => *c == ColorCorrection::default(),
+            Filter::CropPad(c) => *c == CropPad::default(),
+            Filter::ChromaKey(_) => false,
+            Filter::Sharpen(s) => s.amount == 0.0,
+        }
+    }
+
+    /// Output size for an input of `width` x `height`
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Filter::CropPad(c) => (
+                (width as i64 - c.left as i64 - c.right as i64).max(0) as u32,
+                (height as i64 - c.top as i64 - c.bottom as i64).max(0) as u32,
+            ),
+            _ => (width, height),
+        }
+    }
+}
+
+/// A filter in a chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterInstance {
+    pub name: String,
+    pub enabled: bool,
+    pub filter: Filter,
+}
+
+/// Ordered filters of one source or item, applied first to last
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterChain {
+    filters: Vec<FilterInstance>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an enabled filter; false if the name is taken
+    pub fn add(&mut self, name: &str, filter: Filter) -> bool {
+        self.insert(self.filters.len(), name, filter)
+    }
+
+    /// Insert an enabled filter at `index`; false if the name is taken
+    pub fn insert(&mut self, index: usize, name: &str, filter: Filter) -> bool {
+        if self.index_of(name).is_some() {
+            return false;
+        }
+        let index = index.min(self.filters.len());
+        self.filters.insert(
+            index,
+            FilterInstance {
+                name: name.to_string(),
+                enabled: true,
+                filter,
+            },
+        );
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<FilterInstance> {
+        let index = self.index_of(name)?;
+        Some(self.filters.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FilterInstance> {
+        self.filters.iter().find(|f| f.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut FilterInstance> {
+        self.filters.iter_mut().find(|f| f.name == name)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.get_mut(name) {
+            Some(instance) => {
+                instance.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move a filter to `index`, clamped to the end
+    pub fn move_filter(&mut self, name: &str, index: usize) -> bool {
+        let Some(from) = self.index_of(name) else {
+            return false;
+        };
+        let instance = self.filters.remove(from);
+        let index = index.min(self.filters.len());
+        self.filters.insert(index, instance);
+        true
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.filters.iter().position(|f| f.name == name)
+    }
+
+    pub fn filters(&self) -> &[FilterInstance] {
+        &self.filters
+    }
+
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Bytes held on the heap by the chain
+    pub fn heap_size(&self) -> usize {
+        self.filters.capacity() * std::mem::size_of::<FilterInstance>()
+            + self
+                .filters
+                .iter()
+                .map(|f| f.name.capacity())
+                .sum::<usize>()
+    }
+
+    /// Filters that change their input, in order
+    pub fn active(&self) -> impl Iterator<Item = &Filter> {
+        self.filters
+            .iter()
+            .filter(|f| f.enabled && !f.filter.is_identity())
+            .map(|f| &f.filter)
+    }
+
+    /// Output size of the chain for an input of `width` x `height`
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        self.active()
+            .fold((width, height), |(w, h), filter| filter.output_size(w, h))
+    }
+}
+
+/// Texture a filter pass reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassInput {
+    Source(u64),
+    Target(u64),
+}
+
+/// One filter applied into a render target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterPass {
+    pub input: PassInput,
+    pub output: u64,
+    pub filter: Filter,
+    /// Size of the output target
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Everything to draw for one frame of a filtered scene
+#[derive(Debug, Clone, Default)]
+pub struct RenderPlan {
+    /// Filter passes, run in order before the commands
+    pub passes: Vec<FilterPass>,
+    /// Scene commands, back to front
+    pub commands: Vec<RenderCommand>,
+    /// Render targets no longer used, to be freed
+    pub released: Vec<u64>,
+}
+
+/// A cached chain output
+struct CacheEntry {
+    source: u64,
+    filters: Vec<Filter>,
+    input_size: (u32, u32),
+    version: Option<u64>,
+    // One per pass; the last holds the output
+    targets: Vec<u64>,
+    used: bool,
+}
+
+/// Source filter chains and the render targets of their outputs
+#[derive(Default)]
+pub struct FilterPipeline {
+    sources: HashMap<u64, FilterChain>,
+    cache: Vec<CacheEntry>,
+    next_target: u64,
+}
+
+impl FilterPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn source_filters(&self, source: u64) -> Option<&FilterChain> {
+        self.sources.get(&source)
+    }
+
+    /// Filters of `source`, created empty on first use
+    pub fn source_filters_mut(&mut self, source: u64) -> &mut FilterChain {
+        self.sources.entry(source).or_default()
+    }
+
+    pub fn remove_source(&mut self, source: u64) -> Option<FilterChain> {
+        self.sources.remove(&source)
+    }
+
+    /// Source sizes after their filters
+    pub fn filtered_dimensions(&self, dimensions: &[(u64, u32, u32)]) -> Vec<(u64, u32, u32)> {
+        dimensions
+            .iter()
+            .map(|&(id, width, height)| match self.sources.get(&id) {
+                Some(chain) => {
+                    let (width, height) = chain.output_size(width, height);
+                    (id, width, height)
+                }
+                None => (id, width, height),
+            })
+            .collect()
+    }
+
+    /// Update the scene's transforms for the filtered sizes and plan the
+    /// frame
+    ///
+    /// `versions` identifies each source's current frame; sources without
+    /// a version are filtered every frame. Items outside the scene are
+    /// culled as by `render_scene` and get no filter passes.
+    pub fn plan(
+        &mut self,
+        scene: &Scene,
+        dimensions: &[(u64, u32, u32)],
+        versions: &HashMap<u64, u64>,
+    ) -> RenderPlan {
+        self.plan_culled(scene, dimensions, versions, &HashSet::new())
+    }
+
+    /// Plan the frame, also culling items hidden under items of
+    /// `opaque_sources`, as by `render_scene_culled`
+    pub fn plan_culled(
+        &mut self,
+        scene: &Scene,
+        dimensions: &[(u64, u32, u32)],
+        versions: &HashMap<u64, u64>,
+        opaque_sources: &HashSet<u64>,
+    ) -> RenderPlan {
+        let filtered = self.filtered_dimensions(dimensions);
+        scene.update_transforms(&filtered);
+        fit_item_filters(scene, &filtered);
+
+        let mut plan = RenderPlan::default();
+        let mut filters = Vec::new();
+        cull_items(scene, opaque_sources, |mut command, item| {
+            let source = command.source_id;
+            let Some(&(_, width, height)) = dimensions.iter().find(|d| d.0 == source) else {
+                plan.commands.push(command);
+                return;
+            };
+            let version = versions.get(&source).copied();
+
+            filters.clear();
+            if let Some(chain) = self.sources.get(&source) {
+                filters.extend(chain.active().copied());
+            }
+            let source_filters = filters.len();
+            filters.extend(item.filters.active().copied());
+
+            let mut input = PassInput::Source(source);
+            if source_filters > 0 {
+                input = self.stage(
+                    source,
+                    &filters[..source_filters],
+                    (width, height),
+                    version,
+                    &mut plan,
+                );
+            }
+            if filters.len() > source_filters {
+                input = self.stage(source, &filters, (width, height), version, &mut plan);
+            }
+            if let PassInput::Target(target) = input {
+                command.filtered = Some(target);
+            }
+            plan.commands.push(command);
+        });
+
+        self.cache.retain_mut(|entry| {
+            if !entry.used {
+                plan.released.extend(&entry.targets);
+            }
+            std::mem::take(&mut entry.used)
+        });
+        plan
+    }
+
+    /// Target holding `source` through `filters`, adding passes to `plan`
+    /// unless the cached output is current
+    ///
+    /// `filters` is the whole chain from the source; passes already run
+    /// for a prefix of it are reused.
+    fn stage(
+        &mut self,
+        source: u64,
+        filters: &[Filter],
+        input_size: (u32, u32),
+        version: Option<u64>,
+        plan: &mut RenderPlan,
+    ) -> PassInput {
+        let found = self.cache.iter().position(|entry| {
+            entry.source == source && entry.filters == filters && entry.input_size == input_size
+        });
+        let index = match found {
+            Some(index) => index,
+            None => {
+                let targets = filters
+                    .iter()
+                    .map(|_| {
+                        self.next_target += 1;
+                        self.next_target
+                    })
+                    .collect();
+                self.cache.push(CacheEntry {
+                    source,
+                    filters: filters.to_vec(),
+                    input_size,
+                    version: None,
+                    targets,
+                    used: false,
+                });
+                self.cache.len() - 1
+            }
+        };
+
+        let entry = &self.cache[index];
+        let output = PassInput::Target(*entry.targets.last().unwrap());
+        if entry.used || (version.is_some() && entry.version == version) {
+            self.cache[index].used = true;
+            return output;
+        }
+
+        // Start from the longest cached prefix rendered this frame
+        let mut input = PassInput::Source(source);
+        let mut start = 0;
+        for len in (1..filters.len()).rev() {
+            let prefix = self.cache.iter().find(|e| {
+                e.used
+                    && e.source == source
+                    && e.input_size == input_size
+                    && e.filters == filters[..len]
+            });
+            if let Some(prefix) = prefix {
+                input = PassInput::Target(*prefix.targets.last().unwrap());
+                start = len;
+                break;
+            }
+        }
+
+        let entry = &mut self.cache[index];
+        let (mut width, mut height) = input_size;
+        for (i, filter) in filters.iter().enumerate() {
+            (width, height) = filter.output_size(width, height);
+            if i < start {
+                continue;
+            }
+            let output = entry.targets[i];
+            plan.passes.push(FilterPass {
+                input,
+                output,
+                filter: *filter,
+                width,
+                height,
+            });
+            input = PassInput::Target(output);
+        }
+        entry.version = version;
+        entry.used = true;
+        output
+    }
+}
+
+/// Recompute transforms of items whose own filters change their size
+fn fit_item_filters(scene: &Scene, dimensions: &[(u64, u32, u32)]) {
+    let mut items = scene.items.write();
+    for mut item in items.iter_mut() {
+        let Some(&(_, width, height)) = dimensions.iter().find(|d| d.0 == item.source_id) else {
+            continue;
+        };
+        let (width, height) = item.filters.output_size(width, height);
+        if item.source_size_changed(width, height) {
+            item.mark_transform_dirty();
+            update_item_transform(&mut item, width, height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SceneItem;
+
+    fn crop(left: i32) -> Filter {
+        Filter::CropPad(CropPad {
+            left,
+            ..Default::default()
+        })
+    }
+
+    fn sharpen(amount: f32) -> Filter {
+        Filter::Sharpen(Sharpen { amount })
+    }
+
+    #[test]
+    fn test_chain_editing() {
+        let mut chain = FilterChain::new();
+        assert!(chain.add("crop", crop(10)));
+        assert!(chain.add("sharpen", sharpen(0.5)));
+        assert!(!chain.add("crop", crop(20)));
+        assert!(chain.insert(0, "color", Filter::ColorCorrection(Default::default())));
+
+        let names = |chain: &FilterChain| -> Vec<String> {
+            chain.filters().iter().map(|f| f.name.clone()).collect()
+        };
+        assert_eq!(names(&chain), ["color", "crop", "sharpen"]);
+        assert!(chain.move_filter("color", 99));
+        assert_eq!(names(&chain), ["crop", "sharpen", "color"]);
+
+        // Identity and disabled filters are skipped
+        assert_eq!(chain.active().count(), 2);
+        assert!(chain.set_enabled("crop", false));
+        assert_eq!(chain.active().collect::<Vec<_>>(), [&sharpen(0.5)]);
+        assert_eq!(chain.output_size(100, 50), (100, 50));
+        chain.set_enabled("crop", true);
+        assert_eq!(chain.output_size(100, 50), (90, 50));
+
+        assert!(chain.remove("crop").is_some());
+        assert!(chain.remove("crop").is_none());
+        assert!(!chain.set_enabled("crop", true));
+    }
+
+    #[test]
+    fn test_plan_passes_and_sizes() {
+        let scene = Scene::new(1920, 1080);
+        let plain = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let mut item = SceneItem::new(0, 2);
+        item.filters.add("pad", crop(-20));
+        let padded = scene.add_item(item).unwrap();
+
+        let mut pipeline = FilterPipeline::new();
+        pipeline.source_filters_mut(2).add("crop", crop(10));
+        pipeline.source_filters_mut(2).add("off", sharpen(0.0));
+
+        let dims = [(1, 100, 100), (2, 100, 100)];
+        let plan = pipeline.plan(&scene, &dims, &HashMap::new());
+        assert_eq!(plan.commands.len(), 2);
+        assert_eq!(plan.commands[0].filtered, None);
+
+        // Source crop, then the item's pad on top of it
+        let targets: Vec<_> = plan.passes.iter().map(|p| p.output).collect();
+        assert_eq!(plan.passes.len(), 2);
+        assert_eq!(plan.passes[0].input, PassInput::Source(2));
+        assert_eq!((plan.passes[0].width, plan.passes[0].height), (90, 100));
+        assert_eq!(plan.passes[1].input, PassInput::Target(targets[0]));
+        assert_eq!((plan.passes[1].width, plan.passes[1].height), (110, 100));
+        assert_eq!(plan.commands[1].filtered, Some(targets[1]));
+
+        let item = scene.find_item(padded).unwrap();
+        assert_eq!((item.last_width, item.last_height), (110, 100));
+        assert_eq!(scene.find_item(plain).unwrap().last_width, 100);
+    }
+
+    #[test]
+    fn test_cache_by_version() {
+        let scene = Scene::new(1920, 1080);
+        scene.add_item(SceneItem::new(0, 1)).unwrap();
+        scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let mut pipeline = FilterPipeline::new();
+        pipeline.source_filters_mut(1).add("sharpen", sharpen(0.5));
+
+        let dims = [(1, 64, 64)];
+        let mut versions = HashMap::from([(1, 7)]);
+        // Both items share one filtered output
+        let plan = pipeline.plan(&scene, &dims, &versions);
+        assert_eq!(plan.passes.len(), 1);
+        assert_eq!(plan.commands[0].filtered, plan.commands[1].filtered);
+
+        let plan = pipeline.plan(&scene, &dims, &versions);
+        assert!(plan.passes.is_empty());
+        assert!(plan.commands[0].filtered.is_some());
+
+        versions.insert(1, 8);
+        assert_eq!(pipeline.plan(&scene, &dims, &versions).passes.len(), 1);
+        // Unversioned sources are filtered every frame
+        assert_eq!(
+            pipeline.plan(&scene, &dims, &HashMap::new()).passes.len(),
+            1
+        );
+        assert_eq!(
+            pipeline.plan(&scene, &dims, &HashMap::new()).passes.len(),
+            1
+        );
+
+        // Changing the chain frees the old output
+        let old = plan.commands[0].filtered.unwrap();
+        pipeline
+            .source_filters_mut(1)
+            .get_mut("sharpen")
+            .unwrap()
+            .filter = sharpen(0.25);
+        let plan = pipeline.plan(&scene, &dims, &versions);
+        assert_eq!(plan.passes.len(), 1);
+        assert_eq!(plan.released, [old]);
+
+        pipeline.source_filters_mut(1).set_enabled("sharpen", false);
+        let plan = pipeline.plan(&scene, &dims, &versions);
+        assert!(plan.passes.is_empty());
+        assert_eq!(plan.commands[0].filtered, None);
+        assert_eq!(plan.released.len(), 1);
+    }
+
+    #[test]
+    fn test_plan_skips_culled_items() {
+        let scene = Scene::new(1920, 1080);
+        let mut offscreen = SceneItem::new(0, 1);
+        offscreen.pos.x = 5000.0;
+        scene.add_item(offscreen).unwrap();
+        scene.add_item(SceneItem::new(0, 2)).unwrap();
+        let mut cover = SceneItem::new(0, 3);
+        cover.bounds_type = crate::types::BoundsType::Stretch;
+        cover.bounds = glam::Vec2::new(1920.0, 1080.0);
+        scene.add_item(cover).unwrap();
+
+        let mut pipeline = FilterPipeline::new();
+        for source in 1..=3 {
+            pipeline
+                .source_filters_mut(source)
+                .add("sharpen", sharpen(0.5));
+        }
+        let dims = [(1, 64, 64), (2, 64, 64), (3, 64, 64)];
+
+        let plan = pipeline.plan(&scene, &dims, &HashMap::new());
+        let drawn: Vec<u64> = plan.commands.iter().map(|c| c.source_id).collect();
+        assert_eq!(drawn, [2, 3]);
+        assert_eq!(plan.passes.len(), 2);
+
+        let plan = pipeline.plan_culled(&scene, &dims, &HashMap::new(), &HashSet::from([3]));
+        let drawn: Vec<u64> = plan.commands.iter().map(|c| c.source_id).collect();
+        assert_eq!(drawn, [3]);
+        assert_eq!(plan.passes.len(), 1);
+    }
+}
@@ -0,0 +1,558 @@
+//! Undo and redo for scene edits
+//!
+//! `SceneHistory` wraps a scene handle and mirrors its mutating methods,
+//! recording each change as a reversible command before returning. Edits
+//! made between `begin_group` and `end_group` undo as one step, and
+//! repeated merged updates, like the mouse moves of one drag, collapse
+//! into a single step until `seal` is called.
+//!
+//! Item selection is not part of the history: undoing an edit leaves the
+//! item's selection state as it is.
+
+use crate::scene::Scene;
+use crate::types::SceneItem;
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+/// Default memory cap for recorded steps
+pub const DEFAULT_HISTORY_MEMORY: usize = 16 * 1024 * 1024;
+
+/// One reversible scene change
+#[derive(Debug, Clone)]
+pub enum SceneCommand {
+    AddItem {
+        index: usize,
+        item: SceneItem,
+    },
+    RemoveItem {
+        index: usize,
+        item: SceneItem,
+    },
+    UpdateItem {
+        before: Box<SceneItem>,
+        after: Box<SceneItem>,
+    },
+    /// Item IDs in drawing order before and after
+    Reorder {
+        before: Vec<i64>,
+        after: Vec<i64>,
+    },
+}
+
+impl SceneCommand {
+    fn memory_size(&self) -> usize {
+        let heap = match self {
+            SceneCommand::AddItem { item, .. } | SceneCommand::RemoveItem { item, .. } => {
+                item.filters.heap_size()
+            }
+            SceneCommand::UpdateItem { before, after } => {
+                2 * size_of::<SceneItem>() + before.filters.heap_size() + after.filters.heap_size()
+            }
+            SceneCommand::Reorder { before, after } => {
+                (before.len() + after.len()) * size_of::<i64>()
+            }
+        };
+        size_of::<Self>() + heap
+    }
+
+    fn apply(&self, scene: &Scene, undo: bool) {
+        match (self, undo) {
+            (SceneCommand::AddItem { index, item }, false)
+            | (SceneCommand::RemoveItem { index, item }, true) => {
+                scene.insert_item(*index, item.clone());
+            }
+            (SceneCommand::AddItem { item, .. }, true)
+            | (SceneCommand::RemoveItem { item, .. }, false) => {
+                scene.remove_item(item.id);
+            }
+            (SceneCommand::UpdateItem { before, after }, undo) => {
+                let state = if undo { before } else { after };
+                scene.update_item(state.id, |item| {
+                    let selected = item.selected;
+                    *item = SceneItem::clone(state);
+                    item.selected = selected;
+                });
+            }
+            (SceneCommand::Reorder { before, after }, undo) => {
+                let order = if undo { before } else { after };
+                let order: Vec<(i64, usize)> =
+                    order.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+                scene.reorder_items(&order);
+            }
+        }
+    }
+}
+
+/// A named group of commands undone together
+#[derive(Debug, Clone)]
+struct HistoryStep {
+    name: String,
+    commands: Vec<SceneCommand>,
+    /// Merged updates with this key extend the step
+    merge_key: Option<String>,
+}
+
+impl HistoryStep {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            commands: Vec::new(),
+            merge_key: None,
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        size_of::<Self>()
+            + self.name.len()
+            + self
+                .commands
+                .iter()
+                .map(SceneCommand::memory_size)
+                .sum::<usize>()
+    }
+}
+
+/// Undo and redo stacks for one scene
+pub struct SceneHistory {
+    scene: Scene,
+    undo: VecDeque<HistoryStep>,
+    redo: Vec<HistoryStep>,
+    /// Step being built by an open group
+    group: Option<HistoryStep>,
+    group_depth: usize,
+    memory_limit: usize,
+}
+
+impl SceneHistory {
+    pub fn new(scene: Scene) -> Self {
+        Self::with_memory_limit(scene, DEFAULT_HISTORY_MEMORY)
+    }
+
+    /// History that drops its oldest steps past `memory_limit` bytes
+    pub fn with_memory_limit(scene: Scene, memory_limit: usize) -> Self {
+        Self {
+            scene,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: None,
+            group_depth: 0,
+            memory_limit,
+        }
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Name of the step `undo` would revert, for menu labels
+    pub fn undo_name(&self) -> Option<&str> {
+        self.undo.back().map(|step| step.name.as_str())
+    }
+
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo.last().map(|step| step.name.as_str())
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Approximate memory held by recorded steps
+    pub fn memory_used(&self) -> usize {
+        self.undo
+            .iter()
+            .chain(&self.redo)
+            .map(HistoryStep::memory_size)
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Start a step that collects every edit until the matching
+    /// `end_group`; groups nest, the outermost name is used
+    pub fn begin_group(&mut self, name: &str) {
+        if self.group_depth == 0 {
+            self.group = Some(HistoryStep::new(name));
+        }
+        self.group_depth += 1;
+    }
+
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth == 0 {
+            if let Some(step) = self.group.take() {
+                if !step.commands.is_empty() {
+                    self.push_step(step);
+                }
+            }
+        }
+    }
+
+    /// Stop merging into the last step, e.g. when a drag ends
+    pub fn seal(&mut self) {
+        if let Some(step) = self.undo.back_mut() {
+            step.merge_key = None;
+        }
+    }
+
+    /// Revert the last step; returns false if there is none
+    pub fn undo(&mut self) -> bool {
+        self.end_open_group();
+        let Some(mut step) = self.undo.pop_back() else {
+            return false;
+        };
+        for command in step.commands.iter().rev() {
+            command.apply(&self.scene, true);
+        }
+        step.merge_key = None;
+        self.redo.push(step);
+        true
+    }
+
+    /// Reapply the last undone step; returns false if there is none
+    pub fn redo(&mut self) -> bool {
+        self.end_open_group();
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        for command in &step.commands {
+            command.apply(&self.scene, false);
+        }
+        self.undo.push_back(step);
+        true
+    }
+
+    /// Add an item, as `Scene::add_item`
+    pub fn add_item(&mut self, item: SceneItem) -> Option<i64> {
+        let id = self.scene.add_item(item)?;
+        if let (Some(index), Some(item)) = (self.scene.item_index(id), self.scene.find_item(id)) {
+            self.record("Add item", SceneCommand::AddItem { index, item });
+        }
+        Some(id)
+    }
+
+    pub fn remove_item(&mut self, id: i64) -> bool {
+        let (Some(index), Some(item)) = (self.scene.item_index(id), self.scene.find_item(id))
+        else {
+            return false;
+        };
+        self.scene.remove_item(id);
+        self.record("Remove item", SceneCommand::RemoveItem { index, item });
+        true
+    }
+
+    pub fn move_item(&mut self, id: i64, new_index: usize) -> bool {
+        let before = self.scene.item_order();
+        if !self.scene.move_item(id, new_index) {
+            return false;
+        }
+        self.record_order("Move item", before);
+        true
+    }
+
+    pub fn reorder_items(&mut self, order: &[(i64, usize)]) {
+        let before = self.scene.item_order();
+        self.scene.reorder_items(order);
+        self.record_order("Reorder items", before);
+    }
+
+    /// Update one item as its own step
+    pub fn update_item<F>(&mut self, id: i64, update_fn: F) -> bool
+    where
+        F: FnMut(&mut SceneItem),
+    {
+        self.update_items("Update item", None, &[id], update_fn) > 0
+    }
+
+    /// Update an item, merging into the last step if it was a merged
+    /// update with the same `key` and has not been sealed
+    pub fn update_item_merged<F>(&mut self, id: i64, key: &str, update_fn: F) -> bool
+    where
+        F: FnMut(&mut SceneItem),
+    {
+        self.update_items(key, Some(key), &[id], update_fn) > 0
+    }
+
+    /// Update several items as one step named `name`
+    ///
+    /// With a `merge_key`, merges like `update_item_merged`. Returns how
+    /// many items were found and updated.
+    pub fn update_items<F>(
+        &mut self,
+        name: &str,
+        merge_key: Option<&str>,
+        ids: &[i64],
+        mut update_fn: F,
+    ) -> usize
+    where
+        F: FnMut(&mut SceneItem),
+    {
+        let mut commands = Vec::new();
+        for &id in ids {
+            let Some(before) = self.scene.find_item(id) else {
+                continue;
+            };
+            if !self.scene.update_item(id, &mut update_fn) {
+                continue;
+            }
+            if let Some(after) = self.scene.find_item(id) {
+                commands.push(SceneCommand::UpdateItem {
+                    before: Box::new(before),
+                    after: Box::new(after),
+                });
+            }
+        }
+        let count = commands.len();
+        if count == 0 {
+            return 0;
+        }
+
+        if let Some(key) = merge_key {
+            if self.group.is_none() && self.merge(key, &commands) {
+                return count;
+            }
+        }
+        let mut step = HistoryStep::new(name);
+        step.commands = commands;
+        step.merge_key = merge_key.map(str::to_string);
+        self.push(step);
+        count
+    }
+
+    /// Fold updates into the last step, keeping its original `before`s
+    fn merge(&mut self, key: &str, commands: &[SceneCommand]) -> bool {
+        let Some(step) = self.undo.back_mut() else {
+            return false;
+        };
+        if step.merge_key.as_deref() != Some(key) || !self.redo.is_empty() {
+            return false;
+        }
+        for command in commands {
+            let SceneCommand::UpdateItem { before, after } = command else {
+                continue;
+            };
+            let existing = step
+                .commands
+                .iter_mut()
+                .find_map(|existing| match existing {
+                    SceneCommand::UpdateItem { after: last, .. } if last.id == after.id => {
+                        Some(last)
+                    }
+                    _ => None,
+                });
+            match existing {
+                Some(last) => *last = after.clone(),
+                None => step.commands.push(SceneCommand::UpdateItem {
+                    before: before.clone(),
+                    after: after.clone(),
+                }),
+            }
+        }
+        true
+    }
+
+    fn record_order(&mut self, name: &str, before: Vec<i64>) {
+        let after = self.scene.item_order();
+        if after != before {
+            self.record(name, SceneCommand::Reorder { before, after });
+        }
+    }
+
+    fn record(&mut self, name: &str, command: SceneCommand) {
+        let mut step = HistoryStep::new(name);
+        step.commands.push(command);
+        self.push(step);
+    }
+
+    /// Add a step to the open group, or to the undo stack
+    fn push(&mut self, step: HistoryStep) {
+        match &mut self.group {
+            Some(group) => group.commands.extend(step.commands),
+            None => self.push_step(step),
+        }
+    }
+
+    fn push_step(&mut self, step: HistoryStep) {
+        self.redo.clear();
+        self.seal();
+        self.undo.push_back(step);
+        while self.memory_used() > self.memory_limit && self.undo.len() > 1 {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Close a group left open, so undo never splits it
+    fn end_open_group(&mut self) {
+        while self.group_depth > 0 {
+            self.end_group();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+
+    fn history_with(count: usize) -> (SceneHistory, Vec<i64>) {
+        let mut history = SceneHistory::new(Scene::new(1920, 1080));
+        let ids = (0..count)
+            .map(|i| history.add_item(SceneItem::new(0, i as u64)).unwrap())
+            .collect();
+        history.clear();
+        (history, ids)
+    }
+
+    fn pos(history: &SceneHistory, id: i64) -> Vec2 {
+        history.scene().find_item(id).unwrap().pos
+    }
+
+    #[test]
+    fn test_add_remove_move_round_trip() {
+        let (mut history, ids) = history_with(3);
+        let scene = history.scene().clone();
+
+        history.remove_item(ids[1]);
+        let added = history.add_item(SceneItem::new(0, 9)).unwrap();
+        history.move_item(added, 0);
+        assert_eq!(scene.item_order(), vec![added, ids[0], ids[2]]);
+        assert_eq!(history.undo_name(), Some("Move item"));
+
+        assert!(history.undo());
+        assert_eq!(scene.item_order(), vec![ids[0], ids[2], added]);
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(scene.item_order(), ids);
+        assert!(!history.undo());
+
+        // Redo restores the same IDs in the same places
+        while history.redo() {}
+        assert_eq!(scene.item_order(), vec![added, ids[0], ids[2]]);
+        assert_eq!(scene.find_item(added).unwrap().source_id, 9);
+    }
+
+    #[test]
+    fn test_update_keeps_selection() {
+        let (mut history, ids) = history_with(1);
+        history.update_item(ids[0], |item| item.pos = Vec2::new(5.0, 5.0));
+        history.scene().set_selected(ids[0], true);
+
+        history.undo();
+        let item = history.scene().find_item(ids[0]).unwrap();
+        assert_eq!(item.pos, Vec2::ZERO);
+        assert!(item.selected);
+        assert!(item.transform_dirty);
+
+        history.redo();
+        assert_eq!(pos(&history, ids[0]), Vec2::new(5.0, 5.0));
+        // A new edit drops the redo stack
+        history.undo();
+        history.update_item(ids[0], |item| item.rotation = 90.0);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_drag_is_one_step() {
+        let (mut history, ids) = history_with(2);
+        for x in 1..=10 {
+            history.update_item_merged(ids[0], "Move", |item| item.pos.x = x as f32);
+        }
+        history.seal();
+        history.update_item_merged(ids[0], "Move", |item| item.pos.x = 50.0);
+        assert_eq!(history.undo_count(), 2);
+
+        history.undo();
+        assert_eq!(pos(&history, ids[0]).x, 10.0);
+        history.undo();
+        assert_eq!(pos(&history, ids[0]).x, 0.0);
+
+        // A different key, or another edit in between, starts a new step
+        history.update_item_merged(ids[0], "Move", |item| item.pos.x = 1.0);
+        history.update_item_merged(ids[0], "Rotate", |item| item.rotation = 1.0);
+        history.update_item(ids[1], |item| item.visible = false);
+        history.update_item_merged(ids[0], "Rotate", |item| item.rotation = 2.0);
+        assert_eq!(history.undo_count(), 4);
+    }
+
+    #[test]
+    fn test_groups() {
+        let (mut history, ids) = history_with(3);
+        history.begin_group("Align");
+        history.update_items("Nudge", None, &ids, |item| item.pos.y += 10.0);
+        history.begin_group("Inner");
+        history.remove_item(ids[2]);
+        history.end_group();
+        history.end_group();
+        assert_eq!(history.undo_count(), 1);
+        assert_eq!(history.undo_name(), Some("Align"));
+
+        history.undo();
+        assert_eq!(history.scene().item_count(), 3);
+        assert!(ids.iter().all(|&id| pos(&history, id).y == 0.0));
+
+        // An empty group records nothing; undo closes a group left open
+        history.begin_group("Nothing");
+        history.end_group();
+        history.begin_group("Open");
+        history.update_item(ids[0], |item| item.locked = true);
+        assert!(history.undo());
+        assert!(!history.scene().find_item(ids[0]).unwrap().locked);
+    }
+
+    #[test]
+    fn test_memory_limit_drops_oldest() {
+        let scene = Scene::new(1920, 1080);
+        let id = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let step_size = {
+            let mut probe = SceneHistory::new(scene.clone());
+            probe.update_item(id, |_| {});
+            probe.memory_used()
+        };
+
+        let mut history = SceneHistory::with_memory_limit(scene, step_size * 3);
+        for x in 0..10 {
+            history.update_item(id, |item| item.pos.x = x as f32);
+        }
+        assert_eq!(history.undo_count(), 3);
+        assert!(history.memory_used() <= step_size * 3);
+        while history.undo() {}
+        assert_eq!(pos(&history, id).x, 6.0);
+    }
+
+    #[test]
+    fn test_memory_counts_filters() {
+        use crate::filter::{Filter, Sharpen};
+
+        let scene = Scene::new(1920, 1080);
+        let id = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let mut history = SceneHistory::new(scene);
+        history.update_item(id, |_| {});
+        let plain = history.memory_used();
+
+        history.clear();
+        history.update_item(id, |item| {
+            let name = "x".repeat(1000);
+            item.filters
+                .add(&name, Filter::Sharpen(Sharpen { amount: 0.5 }));
+        });
+        assert!(history.memory_used() >= plain + 1000);
+    }
+}
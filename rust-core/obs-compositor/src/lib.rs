@@ -8,14 +8,40 @@
 //! - SIMD matrix operations via glam
 //! - Dirty flag system for transform caching
 
+pub mod animation;
+pub mod canvas;
+pub mod collection;
+pub mod cull;
+pub mod filter;
+pub mod history;
+pub mod rasterizer;
 pub mod render;
 pub mod scene;
+pub mod scene_tree;
+pub mod selection;
+pub mod signals;
 pub mod transform;
+pub mod transform_edit;
+pub mod transform_ops;
+pub mod transition;
 pub mod types;
 
+pub use animation::*;
+pub use canvas::*;
+pub use collection::*;
+pub use cull::*;
+pub use filter::*;
+pub use history::*;
+pub use rasterizer::*;
 pub use render::*;
 pub use scene::*;
+pub use scene_tree::*;
+pub use selection::*;
+pub use signals::*;
 pub use transform::*;
+pub use transform_edit::*;
+pub use transform_ops::*;
+pub use transition::*;
 pub use types::*;
 
 pub fn version() -> &'static str {
@@ -0,0 +1,1290 @@
+//! CPU reference renderer for `RenderCommand`s
+//!
+//! Executes the commands produced by `render_scene` into an RGBA frame
+//! without a GPU. It is slow but exact, so it serves both as the golden
+//! reference GPU backends are compared against and as a headless fallback.
+//!
+//! Sources and target are straight-alpha RGBA. Filtering happens on
+//! premultiplied values; blending follows the separable W3C compositing
+//! model with the item's alpha as coverage. Filter passes of a
+//! `RenderPlan` run into `RenderTargets` kept between frames, so cached
+//! outputs stay valid.
+
+use crate::filter::{ChromaKey, ColorCorrection, CropPad, Filter, FilterPipeline, PassInput};
+use crate::filter::{FilterPass, RenderPlan};
+use crate::render::{render_scene, RenderCommand};
+use crate::scene::Scene;
+use crate::transition::{LumaWipe, PixelMix, SceneTransition, TransitionFrame};
+use crate::types::{BlendMode, ScaleFilter};
+use glam::{Mat4, Vec2, Vec3};
+use obs_video::{VideoFormat, VideoFrame};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Owned RGBA image that can be viewed as a `VideoFrame`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed rows of RGBA pixels
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Image filled with one color
+    pub fn new(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * self.width + x) * 4) as usize;
+        self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        let offset = ((y * self.width + x) * 4) as usize;
+        self.pixels[offset..offset + 4].copy_from_slice(&color);
+    }
+
+    /// Frame pointing into this image; valid while the image is not
+    /// reallocated or dropped
+    pub fn as_frame(&mut self) -> VideoFrame {
+        let mut frame = VideoFrame::new(self.width, self.height, VideoFormat::RGBA);
+        frame.data[0] = self.pixels.as_mut_ptr();
+        frame.linesize[0] = self.width * 4;
+        frame
+    }
+}
+
+/// Filter outputs of a `RenderPlan`, kept between frames
+#[derive(Debug, Default)]
+pub struct RenderTargets {
+    images: HashMap<u64, RgbaImage>,
+}
+
+impl RenderTargets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&RgbaImage> {
+        self.images.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+}
+
+/// Software renderer for scenes and render commands
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoftwareRenderer {
+    /// Color the target is cleared to before drawing (transparent black
+    /// by default)
+    pub clear_color: [u8; 4],
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the scene's transforms from the source sizes and render it
+    ///
+    /// Returns the number of items drawn.
+    ///
+    /// # Safety
+    /// `target` and every frame in `sources` must be RGBA frames whose
+    /// `data[0]` and `linesize[0]` describe their full size.
+    pub unsafe fn render(
+        &self,
+        scene: &Scene,
+        sources: &HashMap<u64, VideoFrame>,
+        target: &mut VideoFrame,
+    ) -> usize {
+        let dimensions: Vec<(u64, u32, u32)> = sources
+            .iter()
+            .map(|(&id, frame)| (id, frame.width, frame.height))
+            .collect();
+        scene.update_transforms(&dimensions);
+        self.execute(&render_scene(scene), sources, target)
+    }
+
+    /// Clear the target and draw `commands` back to front
+    ///
+    /// Commands whose source is missing or not RGBA are skipped. Returns
+    /// the number of commands drawn.
+    ///
+    /// # Safety
+    /// Same requirements as [`SoftwareRenderer::render`].
+    pub unsafe fn execute(
+        &self,
+        commands: &[RenderCommand],
+        sources: &HashMap<u64, VideoFrame>,
+        target: &mut VideoFrame,
+    ) -> usize {
+        self.draw_commands(commands, sources, None, target)
+    }
+
+    /// Update the scene's transforms, run its filter passes and render it
+    ///
+    /// Frame timestamps identify source frames: a source whose timestamp
+    /// is unchanged keeps its cached filter output.
+    ///
+    /// # Safety
+    /// Same requirements as [`SoftwareRenderer::render`].
+    pub unsafe fn render_filtered(
+        &self,
+        scene: &Scene,
+        pipeline: &mut FilterPipeline,
+        sources: &HashMap<u64, VideoFrame>,
+        targets: &mut RenderTargets,
+        target: &mut VideoFrame,
+    ) -> usize {
+        let dimensions: Vec<(u64, u32, u32)> = sources
+            .iter()
+            .map(|(&id, frame)| (id, frame.width, frame.height))
+            .collect();
+        let versions: HashMap<u64, u64> = sources
+            .iter()
+            .map(|(&id, frame)| (id, frame.timestamp))
+            .collect();
+        let plan = pipeline.plan(scene, &dimensions, &versions);
+        self.execute_plan(&plan, sources, targets, target)
+    }
+
+    /// Free released targets, run the filter passes, then draw the
+    /// commands
+    ///
+    /// # Safety
+    /// Same requirements as [`SoftwareRenderer::render`].
+    pub unsafe fn execute_plan(
+        &self,
+        plan: &RenderPlan,
+        sources: &HashMap<u64, VideoFrame>,
+        targets: &mut RenderTargets,
+        target: &mut VideoFrame,
+    ) -> usize {
+        for id in &plan.released {
+            targets.images.remove(id);
+        }
+        for pass in &plan.passes {
+            let input = match pass.input {
+                PassInput::Source(id) => sources.get(&id).and_then(|f| Texture::new(f)),
+                PassInput::Target(id) => targets.images.get(&id).and_then(Texture::from_image),
+            };
+            let output = match input {
+                Some(input) => run_pass(pass, input),
+                None => RgbaImage::new(pass.width, pass.height, [0; 4]),
+            };
+            targets.images.insert(pass.output, output);
+        }
+        self.draw_commands(&plan.commands, sources, Some(targets), target)
+    }
+
+    unsafe fn draw_commands(
+        &self,
+        commands: &[RenderCommand],
+        sources: &HashMap<u64, VideoFrame>,
+        targets: Option<&RenderTargets>,
+        target: &mut VideoFrame,
+    ) -> usize {
+        if target.format != VideoFormat::RGBA || target.data[0].is_null() {
+            return 0;
+        }
+        let stride = target.linesize[0] as usize;
+        let pixels =
+            std::slice::from_raw_parts_mut(target.data[0], stride * target.height as usize);
+        let width = target.width as usize;
+
+        for row in pixels.chunks_mut(stride) {
+            for pixel in row[..width * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&self.clear_color);
+            }
+        }
+
+        let mut drawn = 0;
+        for command in commands {
+            let texture = match command.filtered {
+                Some(id) => targets
+                    .and_then(|t| t.images.get(&id))
+                    .and_then(Texture::from_image),
+                None => sources
+                    .get(&command.source_id)
+                    .and_then(|f| Texture::new(f)),
+            };
+            let Some(texture) = texture else {
+                continue;
+            };
+            if draw(
+                command,
+                &texture,
+                pixels,
+                stride,
+                target.width,
+                target.height,
+            ) {
+                drawn += 1;
+            }
+        }
+        drawn
+    }
+
+    /// Build the transition's frame at `timestamp` from the source sizes
+    /// and render it
+    ///
+    /// # Safety
+    /// Same requirements as [`SoftwareRenderer::render`].
+    pub unsafe fn render_transition(
+        &self,
+        transition: &mut SceneTransition,
+        timestamp: u64,
+        sources: &HashMap<u64, VideoFrame>,
+        target: &mut VideoFrame,
+    ) -> usize {
+        let dimensions: Vec<(u64, u32, u32)> = sources
+            .iter()
+            .map(|(&id, frame)| (id, frame.width, frame.height))
+            .collect();
+        let frame = transition.render(timestamp, &dimensions);
+        self.execute_transition(&frame, sources, target)
+    }
+
+    /// Draw a transition frame, rendering both scenes of a mix and
+    /// combining them per pixel
+    ///
+    /// # Safety
+    /// Same requirements as [`SoftwareRenderer::render`].
+    pub unsafe fn execute_transition(
+        &self,
+        frame: &TransitionFrame,
+        sources: &HashMap<u64, VideoFrame>,
+        target: &mut VideoFrame,
+    ) -> usize {
+        let (a, b, mix) = match frame {
+            TransitionFrame::Commands(commands) => return self.execute(commands, sources, target),
+            TransitionFrame::Mix { a, b, mix } => (a, b, mix),
+        };
+        if target.format != VideoFormat::RGBA || target.data[0].is_null() {
+            return 0;
+        }
+        let mut scene_b = RgbaImage::new(target.width, target.height, [0; 4]);
+        let drawn =
+            self.execute(a, sources, target) + self.execute(b, sources, &mut scene_b.as_frame());
+
+        let stride = target.linesize[0] as usize;
+        let pixels =
+            std::slice::from_raw_parts_mut(target.data[0], stride * target.height as usize);
+        match *mix {
+            PixelMix::Fade { progress } => fade(pixels, stride, &scene_b, progress),
+            PixelMix::FadeToColor {
+                color,
+                switch_point,
+                progress,
+            } => fade_to_color(pixels, stride, &scene_b, color, switch_point, progress),
+            PixelMix::Luma { wipe, progress } => {
+                let image = sources.get(&wipe.image).and_then(|f| Texture::new(f));
+                luma_wipe(pixels, stride, &scene_b, image, wipe, progress);
+            }
+        }
+        drawn
+    }
+}
+
+/// Crossfade the straight-alpha `pixels` holding scene A to `b`, following
+/// `fade_transition.effect`
+fn fade(pixels: &mut [u8], stride: usize, b: &RgbaImage, progress: f32) {
+    pixels
+        .par_chunks_mut(stride)
+        .take(b.height as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..b.width as usize {
+                let src = b.pixel(x as u32, y as u32);
+                mix_pixel(&mut row[x * 4..x * 4 + 4], src, progress);
+            }
+        });
+}
+
+/// Fade the straight-alpha `pixels` holding scene A to `color` until the
+/// switch point, then from `color` to `b`, following
+/// `fade_to_color_transition.effect`
+fn fade_to_color(
+    pixels: &mut [u8],
+    stride: usize,
+    b: &RgbaImage,
+    color: [u8; 4],
+    switch_point: f32,
+    progress: f32,
+) {
+    let point = switch_point.clamp(0.001, 0.999);
+    let (show_b, weight) = if progress < point {
+        (false, progress / point)
+    } else {
+        (true, 1.0 - (progress - point) / (1.0 - point))
+    };
+
+    pixels
+        .par_chunks_mut(stride)
+        .take(b.height as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..b.width as usize {
+                let dst = &mut row[x * 4..x * 4 + 4];
+                if show_b {
+                    dst.copy_from_slice(&b.pixel(x as u32, y as u32));
+                }
+                mix_pixel(dst, color, weight);
+            }
+        });
+}
+
+/// Move the straight-alpha `dst` towards `src` by `weight`, mixing
+/// premultiplied values as the transition shaders do with their textures
+fn mix_pixel(dst: &mut [u8], src: [u8; 4], weight: f32) {
+    if weight <= 0.0 {
+        return;
+    }
+    if weight >= 1.0 {
+        dst.copy_from_slice(&src);
+        return;
+    }
+    let (da, sa) = (dst[3] as f32 / 255.0, src[3] as f32 / 255.0);
+    let alpha = da + (sa - da) * weight;
+    for c in 0..3 {
+        let d = dst[c] as f32 / 255.0 * da;
+        let s = src[c] as f32 / 255.0 * sa;
+        let mixed = d + (s - d) * weight;
+        dst[c] = if alpha > 0.0 { to_u8(mixed / alpha) } else { 0 };
+    }
+    dst[3] = to_u8(alpha);
+}
+
+/// Mix `b` into the straight-alpha `pixels` holding scene A, following
+/// `luma_wipe_transition.effect`
+///
+/// The luma image is stretched over the canvas; without one, every pixel
+/// reads as mid gray.
+fn luma_wipe(
+    pixels: &mut [u8],
+    stride: usize,
+    b: &RgbaImage,
+    image: Option<Texture>,
+    wipe: LumaWipe,
+    progress: f32,
+) {
+    let (width, height) = (b.width as f32, b.height as f32);
+    let softness = wipe.softness.max(0.0);
+    let time = progress * (1.0 + softness);
+
+    pixels
+        .par_chunks_mut(stride)
+        .take(b.height as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..b.width as usize {
+                let mut luma = image.map_or(0.5, |image| {
+                    let u = (x as f32 + 0.5) / width * image.width as f32;
+                    let v = (y as f32 + 0.5) / height * image.height as f32;
+                    image.sample(ScaleFilter::Bilinear, u, v, (1.0, 1.0))[0]
+                });
+                if wipe.invert {
+                    luma = 1.0 - luma;
+                }
+
+                let weight = if luma <= time - softness {
+                    1.0
+                } else if luma >= time {
+                    0.0
+                } else {
+                    (time - luma) / softness
+                };
+                if weight > 0.0 {
+                    let src = b.pixel(x as u32, y as u32);
+                    mix_pixel(&mut row[x * 4..x * 4 + 4], src, weight);
+                }
+            }
+        });
+}
+
+/// Run one filter pass into a new image
+fn run_pass(pass: &FilterPass, input: Texture) -> RgbaImage {
+    let mut output = RgbaImage::new(pass.width, pass.height, [0; 4]);
+    let width = pass.width as usize;
+    if width == 0 {
+        return output;
+    }
+    output
+        .pixels
+        .par_chunks_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..width {
+                let (x, y) = (x as i64, y as i64);
+                let color = match &pass.filter {
+                    Filter::ColorCorrection(c) => color_correct(c, input.fetch(x, y)),
+                    Filter::CropPad(c) => crop_pad(c, &input, x, y),
+                    Filter::ChromaKey(k) => chroma_key(k, &input, x, y),
+                    Filter::Sharpen(s) => sharpen(s.amount, &input, x, y),
+                };
+                row[x as usize * 4..x as usize * 4 + 4].copy_from_slice(&store(color));
+            }
+        });
+    output
+}
+
+/// Straight color of a premultiplied one
+fn unpremultiply(color: [f32; 4]) -> [f32; 4] {
+    let a = color[3];
+    if a <= 0.0 {
+        return [0.0; 4];
+    }
+    [color[0] / a, color[1] / a, color[2] / a, a]
+}
+
+fn premultiply(rgb: [f32; 3], a: f32) -> [f32; 4] {
+    let a = a.clamp(0.0, 1.0);
+    [
+        rgb[0].clamp(0.0, 1.0) * a,
+        rgb[1].clamp(0.0, 1.0) * a,
+        rgb[2].clamp(0.0, 1.0) * a,
+        a,
+    ]
+}
+
+/// Straight-alpha pixel of a premultiplied color
+fn store(color: [f32; 4]) -> [u8; 4] {
+    let [r, g, b, a] = unpremultiply(color);
+    [to_u8(r), to_u8(g), to_u8(b), to_u8(a)]
+}
+
+fn color_correct(c: &ColorCorrection, color: [f32; 4]) -> [f32; 4] {
+    let [r, g, b, a] = unpremultiply(color);
+    if a <= 0.0 {
+        return [0.0; 4];
+    }
+    let gamma = if c.gamma < 0.0 {
+        -c.gamma + 1.0
+    } else {
+        1.0 / (c.gamma + 1.0)
+    };
+    let contrast = if c.contrast < 0.0 {
+        1.0 / (-c.contrast + 1.0)
+    } else {
+        c.contrast + 1.0
+    };
+    let mut rgb = [r, g, b].map(|v| v.max(0.0).powf(gamma) * contrast + c.brightness);
+
+    let saturation = c.saturation + 1.0;
+    let luma = 0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2];
+    rgb = rgb.map(|v| luma + (v - luma) * saturation);
+
+    if c.hue_shift != 0.0 {
+        // Rotate around the gray axis
+        let (sin, cos) = c.hue_shift.to_radians().sin_cos();
+        let k = 1.0 / 3f32.sqrt();
+        let dot = (rgb[0] + rgb[1] + rgb[2]) * k;
+        let cross = [
+            k * (rgb[2] - rgb[1]),
+            k * (rgb[0] - rgb[2]),
+            k * (rgb[1] - rgb[0]),
+        ];
+        rgb = [0, 1, 2].map(|i| rgb[i] * cos + cross[i] * sin + k * dot * (1.0 - cos));
+    }
+
+    let rgb = [0, 1, 2].map(|i| rgb[i] * c.multiply[i] + c.add[i]);
+    premultiply(rgb, a * c.opacity)
+}
+
+fn crop_pad(c: &CropPad, input: &Texture, x: i64, y: i64) -> [f32; 4] {
+    let (sx, sy) = (x + c.left as i64, y + c.top as i64);
+    if sx < 0 || sy < 0 || sx >= input.width || sy >= input.height {
+        return [0.0; 4];
+    }
+    input.fetch(sx, sy)
+}
+
+fn chroma_key(k: &ChromaKey, input: &Texture, x: i64, y: i64) -> [f32; 4] {
+    let chroma = |[r, g, b]: [f32; 3]| {
+        (
+            -0.100644 * r - 0.338572 * g + 0.439216 * b + 0.501961,
+            0.439216 * r - 0.398942 * g - 0.040274 * b + 0.501961,
+        )
+    };
+    let key = chroma(k.key_color.map(|c| c as f32 / 255.0));
+    let distance = |u: f32, v: f32| {
+        let [r, g, b, _] = unpremultiply(input.sample(ScaleFilter::Bilinear, u, v, (1.0, 1.0)));
+        let (cb, cr) = chroma([r, g, b]);
+        ((cb - key.0).powi(2) + (cr - key.1).powi(2)).sqrt()
+    };
+
+    // Box filtered distance, weighted like the shader
+    let (u, v) = (x as f32 + 0.5, y as f32 + 0.5);
+    let taps = distance(u - 1.0, v - 0.5)
+        + distance(u + 1.0, v + 0.5)
+        + distance(u - 0.5, v + 1.0)
+        + distance(u + 0.5, v - 1.0);
+    let distance = (taps * 2.0 + distance(u, v)) / 9.0;
+
+    let base = distance - k.similarity;
+    let mask = (base / k.smoothness.max(1e-6)).clamp(0.0, 1.0).powf(1.5);
+    let spill = (base / k.spill.max(1e-6)).clamp(0.0, 1.0).powf(1.5);
+
+    let [r, g, b, a] = unpremultiply(input.fetch(x, y));
+    let desaturated = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let rgb = [r, g, b].map(|c| desaturated + (c - desaturated) * spill);
+    premultiply(rgb, a * mask)
+}
+
+/// Unsharp 3x3 kernel, leaving flat areas alone
+fn sharpen(amount: f32, input: &Texture, x: i64, y: i64) -> [f32; 4] {
+    let e = input.fetch(x, y);
+    let (b, d) = (input.fetch(x, y - 1), input.fetch(x - 1, y));
+    let (f, h) = (input.fetch(x + 1, y), input.fetch(x, y + 1));
+    let differs = |other: [f32; 4]| other != e;
+    if !((differs(f) && differs(d)) || (differs(b) && differs(h))) {
+        return e;
+    }
+
+    let mut laplacian = e.map(|c| c * 8.0);
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if dx != 0 || dy != 0 {
+                let n = input.fetch(x + dx, y + dy);
+                for c in 0..4 {
+                    laplacian[c] -= n[c];
+                }
+            }
+        }
+    }
+    let a = (e[3] + laplacian[3] * amount).clamp(0.0, 1.0);
+    [0, 1, 2, 3].map(|c| {
+        let value = (e[c] + laplacian[c] * amount).clamp(0.0, 1.0);
+        if c == 3 {
+            a
+        } else {
+            value.min(a)
+        }
+    })
+}
+
+/// Read-only view of an RGBA source, limited to its cropped region
+#[derive(Clone, Copy)]
+struct Texture<'a> {
+    data: &'a [u8],
+    stride: usize,
+    // Cropped region in texels
+    x0: i64,
+    y0: i64,
+    width: i64,
+    height: i64,
+}
+
+impl<'a> Texture<'a> {
+    unsafe fn new(frame: &'a VideoFrame) -> Option<Self> {
+        if frame.format != VideoFormat::RGBA || frame.data[0].is_null() || frame.width == 0 {
+            return None;
+        }
+        let stride = frame.linesize[0] as usize;
+        Some(Self {
+            data: std::slice::from_raw_parts(frame.data[0], stride * frame.height as usize),
+            stride,
+            x0: 0,
+            y0: 0,
+            width: frame.width as i64,
+            height: frame.height as i64,
+        })
+    }
+
+    fn from_image(image: &'a RgbaImage) -> Option<Self> {
+        if image.width == 0 || image.height == 0 {
+            return None;
+        }
+        Some(Self {
+            data: &image.pixels,
+            stride: image.width as usize * 4,
+            x0: 0,
+            y0: 0,
+            width: image.width as i64,
+            height: image.height as i64,
+        })
+    }
+
+    /// Restrict to the region left after cropping, matching the size the
+    /// transform was computed for
+    fn crop(&mut self, command: &RenderCommand) -> (f32, f32) {
+        let crop = command.crop;
+        let (full_w, full_h) = (self.width as u32, self.height as u32);
+        let cx = crop.calc_width(full_w);
+        let cy = crop.calc_height(full_h);
+
+        self.x0 = (crop.left.max(0) as i64).min(self.width - 1);
+        self.y0 = (crop.top.max(0) as i64).min(self.height - 1);
+        self.width = (cx as i64).min(self.width - self.x0);
+        self.height = (cy as i64).min(self.height - self.y0);
+        (cx as f32, cy as f32)
+    }
+
+    /// Premultiplied texel, clamped to the cropped region
+    fn fetch(&self, x: i64, y: i64) -> [f32; 4] {
+        let x = (self.x0 + x.clamp(0, self.width - 1)) as usize;
+        let y = (self.y0 + y.clamp(0, self.height - 1)) as usize;
+        let offset = y * self.stride + x * 4;
+        let px = &self.data[offset..offset + 4];
+        let a = px[3] as f32 / 255.0;
+        [
+            px[0] as f32 / 255.0 * a,
+            px[1] as f32 / 255.0 * a,
+            px[2] as f32 / 255.0 * a,
+            a,
+        ]
+    }
+
+    /// Sample at (u, v) in texels from the cropped region's top-left
+    fn sample(&self, filter: ScaleFilter, u: f32, v: f32, footprint: (f32, f32)) -> [f32; 4] {
+        match filter {
+            ScaleFilter::Point => self.fetch(u.floor() as i64, v.floor() as i64),
+            // Drawing the source directly samples it linearly
+            ScaleFilter::Disable | ScaleFilter::Bilinear => {
+                self.convolve(u, v, 1, |t| (1.0 - t.abs()).max(0.0))
+            }
+            ScaleFilter::Bicubic => self.convolve(u, v, 2, bicubic),
+            ScaleFilter::Lanczos => self.convolve(u, v, 3, lanczos3),
+            ScaleFilter::Area => self.area(u, v, footprint),
+        }
+    }
+
+    /// Separable filter with taps within `radius` texel centers of (u, v)
+    fn convolve(&self, u: f32, v: f32, radius: i64, kernel: fn(f32) -> f32) -> [f32; 4] {
+        let (cu, cv) = (u - 0.5, v - 0.5);
+        let (bu, bv) = (cu.floor() as i64, cv.floor() as i64);
+
+        let mut sum = [0.0f32; 4];
+        let mut total = 0.0;
+        for y in bv - radius + 1..=bv + radius {
+            let wy = kernel(cv - y as f32);
+            if wy == 0.0 {
+                continue;
+            }
+            for x in bu - radius + 1..=bu + radius {
+                let w = kernel(cu - x as f32) * wy;
+                if w == 0.0 {
+                    continue;
+                }
+                let texel = self.fetch(x, y);
+                for c in 0..4 {
+                    sum[c] += texel[c] * w;
+                }
+                total += w;
+            }
+        }
+        normalize(sum, total)
+    }
+
+    /// Box filter over the destination pixel's footprint in texels
+    fn area(&self, u: f32, v: f32, footprint: (f32, f32)) -> [f32; 4] {
+        let (half_u, half_v) = (footprint.0.max(1.0) / 2.0, footprint.1.max(1.0) / 2.0);
+        let (u0, u1) = (u - half_u, u + half_u);
+        let (v0, v1) = (v - half_v, v + half_v);
+
+        let mut sum = [0.0f32; 4];
+        let mut total = 0.0;
+        for y in v0.floor() as i64..v1.ceil() as i64 {
+            let wy = overlap(y as f32, v0, v1);
+            for x in u0.floor() as i64..u1.ceil() as i64 {
+                let w = overlap(x as f32, u0, u1) * wy;
+                if w <= 0.0 {
+                    continue;
+                }
+                let texel = self.fetch(x, y);
+                for c in 0..4 {
+                    sum[c] += texel[c] * w;
+                }
+                total += w;
+            }
+        }
+        normalize(sum, total)
+    }
+}
+
+/// Length of texel `[i, i + 1)` covered by `[lo, hi)`
+fn overlap(i: f32, lo: f32, hi: f32) -> f32 {
+    ((i + 1.0).min(hi) - i.max(lo)).max(0.0)
+}
+
+fn normalize(sum: [f32; 4], total: f32) -> [f32; 4] {
+    if total <= 0.0 {
+        return [0.0; 4];
+    }
+    // Bicubic and Lanczos ring; keep the result a valid premultiplied color
+    let a = (sum[3] / total).clamp(0.0, 1.0);
+    [
+        (sum[0] / total).clamp(0.0, a),
+        (sum[1] / total).clamp(0.0, a),
+        (sum[2] / total).clamp(0.0, a),
+        a,
+    ]
+}
+
+/// Keys cubic convolution (a = -0.5)
+fn bicubic(t: f32) -> f32 {
+    let t = t.abs();
+    if t < 1.0 {
+        (1.5 * t - 2.5) * t * t + 1.0
+    } else if t < 2.0 {
+        ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0
+    } else {
+        0.0
+    }
+}
+
+fn lanczos3(t: f32) -> f32 {
+    let t = t.abs();
+    if t < 1e-6 {
+        1.0
+    } else if t < 3.0 {
+        let x = std::f32::consts::PI * t;
+        3.0 * x.sin() * (x / 3.0).sin() / (x * x)
+    } else {
+        0.0
+    }
+}
+
+/// Separable blend function on straight colors
+fn blend_channel(mode: BlendMode, s: f32, d: f32) -> f32 {
+    match mode {
+        BlendMode::Multiply => s * d,
+        BlendMode::Screen => s + d - s * d,
+        BlendMode::Lighten => s.max(d),
+        BlendMode::Darken => s.min(d),
+        _ => s,
+    }
+}
+
+/// Composite a premultiplied source color over a straight-alpha pixel
+fn blend(mode: BlendMode, src: [f32; 4], dst: &mut [u8]) {
+    let sa = src[3];
+    if sa <= 0.0 {
+        return;
+    }
+    let da = dst[3] as f32 / 255.0;
+    let out_a = sa + da * (1.0 - sa);
+
+    for c in 0..3 {
+        let d = dst[c] as f32 / 255.0;
+        let premultiplied = match mode {
+            BlendMode::Additive => (src[c] + d * da).min(out_a),
+            BlendMode::Subtract => (d * da - src[c]).max(0.0),
+            _ => {
+                let s = src[c] / sa;
+                let mixed = (1.0 - da) * s + da * blend_channel(mode, s, d);
+                sa * mixed + d * da * (1.0 - sa)
+            }
+        };
+        dst[c] = to_u8(premultiplied / out_a);
+    }
+    dst[3] = to_u8(out_a);
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+/// Draw one command; false if it covers nothing
+fn draw(
+    command: &RenderCommand,
+    texture: &Texture,
+    pixels: &mut [u8],
+    stride: usize,
+    width: u32,
+    height: u32,
+) -> bool {
+    if command.opacity <= 0.0 {
+        return false;
+    }
+    let mut texture = *texture;
+    let (cx, cy) = texture.crop(command);
+
+    let inverse = command.transform.inverse();
+    if !inverse.is_finite() {
+        return false;
+    }
+
+    // Canvas-space bounding box of the item quad
+    let corners = [(0.0, 0.0), (cx, 0.0), (0.0, cy), (cx, cy)]
+        .map(|(x, y)| command.transform.transform_point3(Vec3::new(x, y, 0.0)));
+    let min_x = corners.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+    let max_x = corners.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+    let min_y = corners.iter().map(|p| p.y).fold(f32::MAX, f32::min);
+    let max_y = corners.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+
+    let (mut min, mut max) = (Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+
+    // Clip rectangles as canvas-to-clip transforms, narrowing the box
+    let mut clips = Vec::with_capacity(command.clips.len());
+    for clip in &command.clips {
+        let inverse = clip.transform.inverse();
+        if !inverse.is_finite() {
+            return false;
+        }
+        let (clip_min, clip_max) = clip.bounds();
+        min = min.max(clip_min);
+        max = max.min(clip_max);
+        clips.push((inverse, clip.min, clip.max));
+    }
+
+    let x_start = min.x.floor().max(0.0) as usize;
+    let x_end = (max.x.ceil().max(0.0) as usize).min(width as usize);
+    let y_start = min.y.floor().max(0.0) as usize;
+    let y_end = (max.y.ceil().max(0.0) as usize).min(height as usize);
+    if x_start >= x_end || y_start >= y_end {
+        return false;
+    }
+
+    let footprint = texel_footprint(&inverse);
+    pixels
+        .par_chunks_mut(stride)
+        .enumerate()
+        .skip(y_start)
+        .take(y_end - y_start)
+        .for_each(|(y, row)| {
+            for x in x_start..x_end {
+                let center = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                let local = inverse.transform_point3(center);
+                if local.x < 0.0 || local.y < 0.0 || local.x >= cx || local.y >= cy {
+                    continue;
+                }
+                let clipped = clips.iter().any(|(inverse, min, max)| {
+                    let p = inverse.transform_point3(center).truncate();
+                    p.x < min.x || p.y < min.y || p.x >= max.x || p.y >= max.y
+                });
+                if clipped {
+                    continue;
+                }
+                let mut color = texture.sample(command.scale_filter, local.x, local.y, footprint);
+                if command.opacity < 1.0 {
+                    // Samples are premultiplied, so every channel scales
+                    color = color.map(|c| c * command.opacity);
+                }
+                blend(command.blend_mode, color, &mut row[x * 4..x * 4 + 4]);
+            }
+        });
+    true
+}
+
+/// Texels covered by one canvas pixel along each source axis
+fn texel_footprint(inverse: &Mat4) -> (f32, f32) {
+    (
+        inverse.x_axis.x.abs() + inverse.y_axis.x.abs(),
+        inverse.x_axis.y.abs() + inverse.y_axis.y.abs(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Alignment, BoundsType, SceneItem};
+    use glam::Vec2;
+
+    const GRAY: [u8; 4] = [128, 128, 128, 255];
+
+    /// Render `scene` over a cleared canvas
+    fn render(scene: &Scene, sources: &mut [(u64, &mut RgbaImage)], clear: [u8; 4]) -> RgbaImage {
+        let (width, height) = scene.dimensions();
+        let mut canvas = RgbaImage::new(width, height, [0; 4]);
+        let frames: HashMap<u64, VideoFrame> = sources
+            .iter_mut()
+            .map(|(id, image)| (*id, image.as_frame()))
+            .collect();
+        let renderer = SoftwareRenderer { clear_color: clear };
+        unsafe { renderer.render(scene, &frames, &mut canvas.as_frame()) };
+        canvas
+    }
+
+    fn top_left(source_id: u64, x: f32, y: f32) -> SceneItem {
+        let mut item = SceneItem::new(0, source_id);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        item.pos = Vec2::new(x, y);
+        item
+    }
+
+    /// 4x4 image whose pixel (x, y) is (x * 60, y * 60, 0, 255)
+    fn gradient() -> RgbaImage {
+        let mut image = RgbaImage::new(4, 4, [0; 4]);
+        for y in 0..4 {
+            for x in 0..4 {
+                image.set_pixel(x, y, [x as u8 * 60, y as u8 * 60, 0, 255]);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_translate_and_point_upscale() {
+        let mut source = gradient();
+        let scene = Scene::new(16, 16);
+        let mut item = top_left(1, 3.0, 2.0);
+        item.scale = Vec2::splat(2.0);
+        item.scale_filter = ScaleFilter::Point;
+        scene.add_item(item).unwrap();
+
+        let out = render(&scene, &mut [(1, &mut source)], [0; 4]);
+        assert_eq!(out.pixel(2, 2), [0; 4]);
+        assert_eq!(out.pixel(3, 2), [0, 0, 0, 255]);
+        assert_eq!(out.pixel(4, 3), [0, 0, 0, 255]);
+        assert_eq!(out.pixel(5, 2), [60, 0, 0, 255]);
+        assert_eq!(out.pixel(10, 9), [180, 180, 0, 255]);
+        assert_eq!(out.pixel(11, 10), [0; 4]);
+    }
+
+    #[test]
+    fn test_crop_and_flip() {
+        let mut source = gradient();
+        let scene = Scene::new(8, 8);
+        let mut item = top_left(1, 0.0, 0.0);
+        item.crop.left = 2;
+        item.scale_filter = ScaleFilter::Point;
+        scene.add_item(item).unwrap();
+
+        let out = render(&scene, &mut [(1, &mut source)], [0; 4]);
+        assert_eq!(out.pixel(0, 0), [120, 0, 0, 255]);
+        assert_eq!(out.pixel(1, 3), [180, 180, 0, 255]);
+        assert_eq!(out.pixel(2, 0), [0; 4]);
+
+        // Horizontal flip around the item's left edge
+        let scene = Scene::new(8, 8);
+        let mut item = top_left(1, 4.0, 0.0);
+        item.scale = Vec2::new(-1.0, 1.0);
+        item.scale_filter = ScaleFilter::Point;
+        scene.add_item(item).unwrap();
+
+        let out = render(&scene, &mut [(1, &mut source)], [0; 4]);
+        assert_eq!(out.pixel(3, 0), [0, 0, 0, 255]);
+        assert_eq!(out.pixel(0, 1), [180, 60, 0, 255]);
+        assert_eq!(out.pixel(4, 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_bounds_crop() {
+        // 4x4 source scaled to cover a 4x2 box: the middle rows survive
+        let mut source = gradient();
+        let scene = Scene::new(8, 8);
+        let mut item = top_left(1, 0.0, 0.0);
+        item.bounds_type = BoundsType::ScaleOuter;
+        item.bounds = Vec2::new(4.0, 2.0);
+        item.crop_to_bounds = true;
+        item.scale_filter = ScaleFilter::Point;
+        scene.add_item(item).unwrap();
+
+        let out = render(&scene, &mut [(1, &mut source)], [0; 4]);
+        assert_eq!(out.pixel(0, 0), [0, 60, 0, 255]);
+        assert_eq!(out.pixel(3, 1), [180, 120, 0, 255]);
+        assert_eq!(out.pixel(0, 2), [0; 4]);
+        assert_eq!(out.pixel(4, 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let expect = [
+            (BlendMode::Normal, [114, 114, 114]),
+            (BlendMode::Additive, [178, 178, 178]),
+            (BlendMode::Subtract, [78, 78, 78]),
+            (BlendMode::Screen, [153, 153, 153]),
+            (BlendMode::Multiply, [89, 89, 89]),
+            (BlendMode::Lighten, [128, 128, 128]),
+            (BlendMode::Darken, [114, 114, 114]),
+        ];
+        for (mode, rgb) in expect {
+            let mut background = RgbaImage::new(2, 2, GRAY);
+            let mut overlay = RgbaImage::new(2, 2, [100, 100, 100, 128]);
+            let scene = Scene::new(2, 2);
+            scene.add_item(top_left(1, 0.0, 0.0)).unwrap();
+            let mut item = top_left(2, 0.0, 0.0);
+            item.blend_mode = mode;
+            item.scale_filter = ScaleFilter::Point;
+            scene.add_item(item).unwrap();
+
+            let out = render(
+                &scene,
+                &mut [(1, &mut background), (2, &mut overlay)],
+                [0; 4],
+            );
+            let [r, g, b, a] = out.pixel(1, 1);
+            assert_eq!(([r, g, b], a), (rgb, 255), "{mode:?}");
+        }
+
+        // Over a transparent canvas the source is drawn unchanged
+        let mut overlay = RgbaImage::new(2, 2, [100, 100, 100, 128]);
+        let scene = Scene::new(2, 2);
+        scene.add_item(top_left(1, 0.0, 0.0)).unwrap();
+        let out = render(&scene, &mut [(1, &mut overlay)], [0; 4]);
+        assert_eq!(out.pixel(0, 0), [100, 100, 100, 128]);
+    }
+
+    #[test]
+    fn test_transitions() {
+        use crate::transition::{LumaWipe, TransitionKind};
+
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        let mut red = RgbaImage::new(4, 1, RED);
+        let mut blue = RgbaImage::new(4, 1, BLUE);
+        // Left to right wipe
+        let mut luma = RgbaImage::new(4, 1, [0; 4]);
+        for x in 0..4 {
+            let v = x as u8 * 85;
+            luma.set_pixel(x, 0, [v, v, v, 255]);
+        }
+        let mut frames: HashMap<u64, VideoFrame> = HashMap::new();
+        frames.insert(1, red.as_frame());
+        frames.insert(2, blue.as_frame());
+        frames.insert(3, luma.as_frame());
+
+        let scene = |source| {
+            let scene = Scene::new(4, 1);
+            scene.add_item(top_left(source, 0.0, 0.0)).unwrap();
+            scene
+        };
+        let renderer = SoftwareRenderer::new();
+        let mut canvas = RgbaImage::new(4, 1, [0; 4]);
+        let mut wipe = LumaWipe::new(3);
+        wipe.softness = 0.0;
+        let mut transition = SceneTransition::new(TransitionKind::LumaWipe(wipe), 100);
+        transition.start_at(scene(1), scene(2), 0);
+
+        let drawn = unsafe {
+            renderer.render_transition(&mut transition, 50, &frames, &mut canvas.as_frame())
+        };
+        assert_eq!(drawn, 2);
+        let row: Vec<_> = (0..4).map(|x| canvas.pixel(x, 0)).collect();
+        assert_eq!(row, [BLUE, BLUE, RED, RED]);
+
+        wipe.invert = true;
+        transition.kind = TransitionKind::LumaWipe(wipe);
+        unsafe { renderer.render_transition(&mut transition, 50, &frames, &mut canvas.as_frame()) };
+        let row: Vec<_> = (0..4).map(|x| canvas.pixel(x, 0)).collect();
+        assert_eq!(row, [RED, RED, BLUE, BLUE]);
+
+        // Crossfades mix the two scenes, so where scene B is transparent
+        // scene A fades out instead of staying under it
+        let mut clear = RgbaImage::new(4, 1, [0, 0, 255, 0]);
+        frames.insert(4, clear.as_frame());
+        transition.kind = TransitionKind::Fade;
+        let mut fade = |a, b, timestamp| {
+            transition.start_at(scene(a), scene(b), 0);
+            unsafe {
+                renderer.render_transition(
+                    &mut transition,
+                    timestamp,
+                    &frames,
+                    &mut canvas.as_frame(),
+                )
+            };
+            canvas.pixel(0, 0)
+        };
+        assert_eq!(fade(1, 2, 50), [128, 0, 128, 255]);
+        assert_eq!(fade(1, 2, 25), [191, 0, 64, 255]);
+        assert_eq!(fade(1, 4, 50), [255, 0, 0, 128]);
+        assert_eq!(fade(1, 2, 100), BLUE);
+    }
+
+    #[test]
+    fn test_fade_to_color() {
+        use crate::transition::TransitionKind;
+
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        const WHITE: [u8; 4] = [255; 4];
+        let mut red = RgbaImage::new(2, 1, RED);
+        let mut blue = RgbaImage::new(2, 1, BLUE);
+        let mut frames: HashMap<u64, VideoFrame> = HashMap::new();
+        frames.insert(1, red.as_frame());
+        frames.insert(2, blue.as_frame());
+        let scene = |source| {
+            let scene = Scene::new(2, 1);
+            scene.add_item(top_left(source, 0.0, 0.0)).unwrap();
+            scene
+        };
+
+        let renderer = SoftwareRenderer::new();
+        let mut canvas = RgbaImage::new(2, 1, [0; 4]);
+        let kind = TransitionKind::FadeToColor {
+            color: WHITE,
+            switch_point: 0.4,
+        };
+        let mut transition = SceneTransition::new(kind, 100);
+        transition.start_at(scene(1), scene(2), 0);
+        let mut at = |timestamp| {
+            unsafe {
+                renderer.render_transition(
+                    &mut transition,
+                    timestamp,
+                    &frames,
+                    &mut canvas.as_frame(),
+                )
+            };
+            canvas.pixel(1, 0)
+        };
+
+        // Halfway to the switch point, then fully covered at it
+        assert_eq!(at(20), [255, 128, 128, 255]);
+        assert_eq!(at(40), WHITE);
+        // Scene B shows through from there
+        assert_eq!(at(70), [128, 128, 255, 255]);
+        assert_eq!(at(100), BLUE);
+    }
+
+    #[test]
+    fn test_filter_passes() {
+        use crate::filter::{ChromaKey, ColorCorrection, CropPad, Filter, FilterPipeline, Sharpen};
+
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        let mut source = RgbaImage::new(4, 1, GREEN);
+        source.set_pixel(2, 0, RED);
+        source.set_pixel(3, 0, RED);
+
+        let scene = Scene::new(8, 1);
+        let mut item = top_left(1, 0.0, 0.0);
+        item.scale_filter = ScaleFilter::Point;
+        item.filters.add(
+            "pad",
+            Filter::CropPad(CropPad {
+                left: -1,
+                ..Default::default()
+            }),
+        );
+        scene.add_item(item).unwrap();
+
+        let mut pipeline = FilterPipeline::new();
+        let chain = pipeline.source_filters_mut(1);
+        chain.add("key", Filter::ChromaKey(ChromaKey::default()));
+        chain.add(
+            "tint",
+            Filter::ColorCorrection(ColorCorrection {
+                multiply: [0.5, 1.0, 1.0],
+                ..Default::default()
+            }),
+        );
+        chain.add("sharpen", Filter::Sharpen(Sharpen::default()));
+        chain.set_enabled("sharpen", false);
+
+        let renderer = SoftwareRenderer::new();
+        let mut targets = RenderTargets::new();
+        let mut canvas = RgbaImage::new(8, 1, [0; 4]);
+        let mut frames = HashMap::from([(1, source.as_frame())]);
+        let mut render = |frames: &HashMap<u64, VideoFrame>, canvas: &mut RgbaImage| unsafe {
+            renderer.render_filtered(
+                &scene,
+                &mut pipeline,
+                frames,
+                &mut targets,
+                &mut canvas.as_frame(),
+            )
+        };
+
+        // Padded by one pixel, green keyed out, red halved
+        assert_eq!(render(&frames, &mut canvas), 1);
+        let row: Vec<_> = (0..6).map(|x| canvas.pixel(x, 0)).collect();
+        let half_red = [128, 0, 0, 255];
+        assert_eq!(row, [[0; 4], [0; 4], [0; 4], half_red, half_red, [0; 4]]);
+
+        // The same frame keeps its filtered output
+        source.set_pixel(0, 0, RED);
+        render(&frames, &mut canvas);
+        assert_eq!(canvas.pixel(1, 0), [0; 4]);
+
+        frames.get_mut(&1).unwrap().timestamp = 1;
+        render(&frames, &mut canvas);
+        assert_eq!(canvas.pixel(1, 0), half_red);
+        assert_eq!(targets.len(), 3);
+    }
+
+    #[test]
+    fn test_item_opacity() {
+        let mut white = RgbaImage::new(2, 2, [255; 4]);
+        let scene = Scene::new(2, 2);
+        let mut item = top_left(1, 0.0, 0.0);
+        item.opacity = 0.5;
+        let id = scene.add_item(item).unwrap();
+
+        let out = render(&scene, &mut [(1, &mut white)], [0, 0, 0, 255]);
+        assert_eq!(out.pixel(0, 0), [128, 128, 128, 255]);
+
+        scene.update_item(id, |item| item.opacity = 0.0);
+        let out = render(&scene, &mut [(1, &mut white)], [0, 0, 0, 255]);
+        assert_eq!(out.pixel(0, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_scale_filters() {
+        // Every filter reproduces a flat image exactly
+        for filter in [
+            ScaleFilter::Disable,
+            ScaleFilter::Point,
+            ScaleFilter::Bilinear,
+            ScaleFilter::Bicubic,
+            ScaleFilter::Lanczos,
+            ScaleFilter::Area,
+        ] {
+            let mut flat = RgbaImage::new(5, 3, [40, 80, 120, 255]);
+            let scene = Scene::new(12, 12);
+            let mut item = top_left(1, 0.0, 0.0);
+            item.scale = Vec2::new(2.3, 3.1);
+            item.scale_filter = filter;
+            scene.add_item(item).unwrap();
+
+            let out = render(&scene, &mut [(1, &mut flat)], [0; 4]);
+            assert_eq!(out.pixel(5, 5), [40, 80, 120, 255], "{filter:?}");
+        }
+
+        // Upscaling a hard edge: point keeps it, the others smooth it
+        let mut edge = RgbaImage::new(2, 1, [0, 0, 0, 255]);
+        edge.set_pixel(1, 0, [255, 255, 255, 255]);
+        let sample = |filter| {
+            let scene = Scene::new(8, 1);
+            let mut item = top_left(1, 0.0, 0.0);
+            item.scale = Vec2::new(4.0, 1.0);
+            item.scale_filter = filter;
+            scene.add_item(item).unwrap();
+            render(&scene, &mut [(1, &mut edge.clone())], [0; 4]).pixel(3, 0)[0]
+        };
+        assert_eq!(sample(ScaleFilter::Point), 0);
+        assert!(sample(ScaleFilter::Bilinear) > 0);
+        assert!(sample(ScaleFilter::Bicubic) < sample(ScaleFilter::Bilinear));
+
+        // Area averages the texels under each output pixel
+        let mut checker = RgbaImage::new(4, 4, [0, 0, 0, 255]);
+        for y in 0..4 {
+            for x in 0..4 {
+                if (x + y) % 2 == 0 {
+                    checker.set_pixel(x, y, [255, 255, 255, 255]);
+                }
+            }
+        }
+        let scene = Scene::new(2, 2);
+        let mut item = top_left(1, 0.0, 0.0);
+        item.scale = Vec2::splat(0.5);
+        item.scale_filter = ScaleFilter::Area;
+        scene.add_item(item).unwrap();
+        let out = render(&scene, &mut [(1, &mut checker)], [0; 4]);
+        assert_eq!(out.pixel(1, 0), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn test_missing_sources_are_skipped() {
+        let scene = Scene::new(4, 4);
+        scene.add_item(top_left(9, 0.0, 0.0)).unwrap();
+
+        let out = render(&scene, &mut [], GRAY);
+        assert_eq!(out.pixel(2, 2), GRAY);
+    }
+}
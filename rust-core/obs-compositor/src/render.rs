@@ -3,8 +3,12 @@
 //! This module provides the rendering logic that converts scene items
 //! into render commands for the graphics backend.
 
-use crate::types::{BlendMode, SceneItem};
-use glam::Mat4;
+use crate::cull::render_scene_culled;
+use crate::scene::ItemRef;
+use crate::transform::ItemTransform;
+use crate::types::{BlendMode, ItemInputs, ScaleFilter, SceneItem, SceneItemCrop};
+use glam::{Mat4, Vec2};
+use std::collections::HashSet;
 
 /// A render command for a single scene item
 #[derive(Debug, Clone)]
@@ -18,20 +22,89 @@ pub struct RenderCommand {
     /// Blend mode for compositing
     pub blend_mode: BlendMode,
 
+    /// Source pixels cut from each edge (item crop plus bounds crop)
+    pub crop: SceneItemCrop,
+
+    /// Filter used when sampling the source
+    pub scale_filter: ScaleFilter,
+
     /// Whether to use the item's texture render
     pub use_item_texture: bool,
+
+    /// Opacity multiplied into every sampled pixel
+    pub opacity: f32,
+
+    /// Render target holding the source after its filters, drawn in place
+    /// of the source
+    pub filtered: Option<u64>,
+
+    /// Rectangles the command is drawn inside of; empty draws anywhere
+    pub clips: Vec<ClipRect>,
+}
+
+/// Rectangle from `min` to `max` in the space `transform` maps to the
+/// canvas, e.g. a nested scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipRect {
+    pub transform: Mat4,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl ClipRect {
+    /// Rectangle of the canvas itself
+    pub fn canvas(min: Vec2, max: Vec2) -> Self {
+        Self {
+            transform: Mat4::IDENTITY,
+            min,
+            max,
+        }
+    }
+
+    /// Canvas-space bounding box of the rectangle
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let corners = [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            Vec2::new(self.min.x, self.max.y),
+            self.max,
+        ]
+        .map(|p| self.transform.transform_point3(p.extend(0.0)).truncate());
+        corners[1..]
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), &p| {
+                (min.min(p), max.max(p))
+            })
+    }
 }
 
 impl RenderCommand {
     /// Create a new render command from a scene item
     pub fn from_item(item: &SceneItem) -> Self {
+        Self::from_parts(&item.inputs(), &ItemTransform::of(item))
+    }
+
+    /// Create a render command from an item's inputs and its cached
+    /// transform, as a scene stores them
+    pub(crate) fn from_parts(item: &ItemInputs, cached: &ItemTransform) -> Self {
+        let bounds_crop = cached.bounds_crop;
         Self {
             source_id: item.source_id,
-            transform: item.draw_transform,
+            transform: cached.draw_transform,
             blend_mode: item.blend_mode,
+            crop: SceneItemCrop {
+                left: item.crop.left + bounds_crop.left,
+                right: item.crop.right + bounds_crop.right,
+                top: item.crop.top + bounds_crop.top,
+                bottom: item.crop.bottom + bounds_crop.bottom,
+            },
+            scale_filter: item.scale_filter,
             use_item_texture: item.crop.is_enabled()
-                || item.bounds_crop.is_enabled()
-                || item.scale_filter != crate::types::ScaleFilter::Disable,
+                || bounds_crop.is_enabled()
+                || item.scale_filter != ScaleFilter::Disable,
+            opacity: item.opacity.clamp(0.0, 1.0),
+            filtered: None,
+            clips: Vec::new(),
         }
     }
 }
@@ -39,15 +112,11 @@ impl RenderCommand {
 /// Render a scene and return a list of render commands
 ///
 /// This function iterates through all visible items in the scene
-/// and generates render commands in back-to-front order.
+/// and generates render commands in back-to-front order. Items outside the
+/// scene are culled; see `render_scene_culled` to also cull items hidden
+/// under opaque ones.
 pub fn render_scene(scene: &crate::scene::Scene) -> Vec<RenderCommand> {
-    let mut commands = Vec::new();
-
-    scene.render_items(|item| {
-        commands.push(RenderCommand::from_item(item));
-    });
-
-    commands
+    render_scene_culled(scene, &HashSet::new())
 }
 
 /// Render a scene with a custom filter predicate
@@ -55,13 +124,13 @@ pub fn render_scene(scene: &crate::scene::Scene) -> Vec<RenderCommand> {
 /// Only items that pass the filter will be rendered.
 pub fn render_scene_filtered<F>(scene: &crate::scene::Scene, mut filter: F) -> Vec<RenderCommand>
 where
-    F: FnMut(&SceneItem) -> bool,
+    F: FnMut(ItemRef<'_>) -> bool,
 {
     let mut commands = Vec::new();
 
     scene.render_items(|item| {
         if filter(item) {
-            commands.push(RenderCommand::from_item(item));
+            commands.push(RenderCommand::from_parts(item.inputs, item.transform));
         }
     });
 
@@ -86,8 +155,8 @@ mod tests {
     #[test]
     fn test_render_scene() {
         let scene = Scene::new(1920, 1080);
-        scene.add_item(SceneItem::new(0, 100));
-        scene.add_item(SceneItem::new(0, 200));
+        scene.add_item(SceneItem::new(0, 100)).unwrap();
+        scene.add_item(SceneItem::new(0, 200)).unwrap();
 
         let commands = render_scene(&scene);
         assert_eq!(commands.len(), 2);
@@ -96,8 +165,8 @@ mod tests {
     #[test]
     fn test_render_scene_filtered() {
         let scene = Scene::new(1920, 1080);
-        let id1 = scene.add_item(SceneItem::new(0, 100));
-        scene.add_item(SceneItem::new(0, 200));
+        let id1 = scene.add_item(SceneItem::new(0, 100)).unwrap();
+        scene.add_item(SceneItem::new(0, 200)).unwrap();
 
         // Only render items with specific ID
         let commands = render_scene_filtered(&scene, |item| item.id == id1);
@@ -2,13 +2,312 @@
 //!
 //! This module provides the core Scene type that manages scene items using
 //! Vec-based storage for cache-friendly iteration and RwLock for concurrent reads.
-
-use crate::transform::update_item_transform;
-use crate::types::SceneItem;
+//! Items are stored as parallel arrays of their transform inputs, the
+//! transforms cached from them and their filters, so a transform update
+//! reads one array and writes another, and rendering borrows them in place. An ID-to-index
+//! map kept alongside makes lookups by ID constant time, and large batches
+//! of dirty transforms are recalculated in parallel.
+
+use crate::filter::FilterChain;
+use crate::scene_tree::TreeLink;
+use crate::signals::{item_changes, SceneSignal, SignalHub};
+use crate::transform::ItemTransform;
+use crate::types::{ItemInputs, SceneItem};
 use parking_lot::RwLock;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 
+/// Dirty items needed before `update_transforms` spreads the work over threads
+pub const PARALLEL_TRANSFORM_THRESHOLD: usize = 128;
+
+/// Scene items in drawing order, with an ID-to-index map kept in step
+///
+/// Each item is stored as three parallel arrays: its inputs, the transform
+/// cached from them, and its filters. Reads borrow them side by side as
+/// [`ItemRef`]s or copy them together, and edits go through [`ItemMut`],
+/// which cannot change an item's ID or position.
+#[derive(Default)]
+pub(crate) struct SceneItems {
+    inputs: Vec<ItemInputs>,
+    transforms: Vec<ItemTransform>,
+    filters: Vec<FilterChain>,
+    index: HashMap<i64, usize>,
+    /// Source sizes of the last transform update, reused between calls
+    sizes: HashMap<u64, (u32, u32)>,
+    /// Per item source size to update the transform for, reused between
+    /// calls
+    pending: Vec<Option<(u32, u32)>>,
+}
+
+impl SceneItems {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inputs: Vec::with_capacity(capacity),
+            transforms: Vec::with_capacity(capacity),
+            filters: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+            sizes: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Position of an item in drawing order
+    pub(crate) fn position(&self, id: i64) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
+    pub(crate) fn get(&self, id: i64) -> Option<SceneItem> {
+        self.position(id).map(|index| self.item(index).to_item())
+    }
+
+    pub(crate) fn get_mut(&mut self, id: i64) -> Option<ItemMut<'_>> {
+        let index = self.position(id)?;
+        Some(ItemMut::new(
+            &mut self.inputs[index],
+            &mut self.transforms[index],
+            &mut self.filters[index],
+        ))
+    }
+
+    /// The items in drawing order, borrowed in place
+    pub(crate) fn refs(&self) -> impl DoubleEndedIterator<Item = ItemRef<'_>> {
+        (0..self.len()).map(|index| self.item(index))
+    }
+
+    /// Copies of the items in drawing order
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = SceneItem> + '_ {
+        self.refs().map(|item| item.to_item())
+    }
+
+    /// Edit the items in drawing order
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = ItemMut<'_>> {
+        self.inputs
+            .iter_mut()
+            .zip(self.transforms.iter_mut())
+            .zip(self.filters.iter_mut())
+            .map(|((inputs, transform), filters)| ItemMut::new(inputs, transform, filters))
+    }
+
+    /// Item IDs in drawing order
+    pub(crate) fn ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.inputs.iter().map(|item| item.id)
+    }
+
+    fn item(&self, index: usize) -> ItemRef<'_> {
+        ItemRef {
+            inputs: &self.inputs[index],
+            transform: &self.transforms[index],
+            filters: &self.filters[index],
+        }
+    }
+
+    /// Append an item; false if its ID is already taken
+    fn push(&mut self, item: SceneItem) -> bool {
+        self.insert(usize::MAX, item)
+    }
+
+    /// Insert at `index`, clamped to the end; false if the item's ID is
+    /// already taken
+    fn insert(&mut self, index: usize, mut item: SceneItem) -> bool {
+        if self.index.contains_key(&item.id) {
+            return false;
+        }
+        let index = index.min(self.len());
+        self.inputs.insert(index, item.inputs());
+        self.transforms.insert(index, ItemTransform::of(&item));
+        self.filters
+            .insert(index, std::mem::take(&mut item.filters));
+        self.reindex(index..self.len());
+        true
+    }
+
+    fn remove(&mut self, id: i64) -> Option<SceneItem> {
+        let index = self.index.remove(&id)?;
+        let inputs = self.inputs.remove(index);
+        let transform = self.transforms.remove(index);
+        let filters = self.filters.remove(index);
+        self.reindex(index..self.len());
+        Some(SceneItem::from_parts(&inputs, &transform, filters))
+    }
+
+    /// Move an item from one position to another
+    fn relocate(&mut self, from: usize, to: usize) {
+        let inputs = self.inputs.remove(from);
+        self.inputs.insert(to, inputs);
+        let transform = self.transforms.remove(from);
+        self.transforms.insert(to, transform);
+        let filters = self.filters.remove(from);
+        self.filters.insert(to, filters);
+        self.reindex(from.min(to)..from.max(to) + 1);
+    }
+
+    /// Put the items at `order`'s positions first, in that order, followed
+    /// by the rest in their current order
+    fn reorder(&mut self, order: &[usize]) {
+        let mut placed = vec![false; self.len()];
+        let mut permutation = Vec::with_capacity(self.len());
+        for &index in order {
+            if index < placed.len() && !placed[index] {
+                placed[index] = true;
+                permutation.push(index);
+            }
+        }
+        permutation.extend((0..placed.len()).filter(|&index| !placed[index]));
+
+        permute(&mut self.inputs, &permutation);
+        permute(&mut self.transforms, &permutation);
+        permute(&mut self.filters, &permutation);
+        self.index.clear();
+        self.reindex(0..self.len());
+    }
+
+    fn reindex(&mut self, range: std::ops::Range<usize>) {
+        for index in range {
+            self.index.insert(self.inputs[index].id, index);
+        }
+    }
+
+    fn update_transforms(&mut self, source_dimensions: &[(u64, u32, u32)]) {
+        let Self {
+            inputs,
+            transforms,
+            sizes,
+            pending,
+            ..
+        } = self;
+
+        // Reuse the lookup map's allocation between frames
+        sizes.clear();
+        sizes.extend(source_dimensions.iter().map(|&(id, w, h)| (id, (w, h))));
+
+        pending.clear();
+        pending.extend(
+            inputs
+                .iter()
+                .zip(transforms.iter())
+                .map(|(item, transform)| {
+                    let &(width, height) = sizes.get(&item.source_id)?;
+                    transform
+                        .needs_update(width, height)
+                        .then_some((width, height))
+                }),
+        );
+
+        let update = |((item, transform), size): (
+            (&ItemInputs, &mut ItemTransform),
+            &Option<(u32, u32)>,
+        )| {
+            if let Some((width, height)) = *size {
+                *transform = ItemTransform::update(item, width, height);
+            }
+        };
+        let dirty = pending.iter().flatten().count();
+        if dirty >= PARALLEL_TRANSFORM_THRESHOLD {
+            inputs
+                .par_iter()
+                .zip(transforms.par_iter_mut())
+                .zip(pending.par_iter())
+                .for_each(update);
+        } else {
+            inputs
+                .iter()
+                .zip(transforms.iter_mut())
+                .zip(pending.iter())
+                .for_each(update);
+        }
+    }
+}
+
+/// Rearrange `values` so the value at `permutation[i]` ends up at `i`
+fn permute<T>(values: &mut Vec<T>, permutation: &[usize]) {
+    let mut slots: Vec<Option<T>> = values.drain(..).map(Some).collect();
+    values.extend(permutation.iter().filter_map(|&index| slots[index].take()));
+}
+
+/// An item of a scene borrowed in place, without copying its parts
+/// together
+///
+/// Derefs to the item's inputs.
+#[derive(Clone, Copy)]
+pub struct ItemRef<'a> {
+    pub inputs: &'a ItemInputs,
+    pub transform: &'a ItemTransform,
+    pub filters: &'a FilterChain,
+}
+
+impl ItemRef<'_> {
+    /// A copy of the item with its parts joined
+    pub fn to_item(&self) -> SceneItem {
+        SceneItem::from_parts(self.inputs, self.transform, self.filters.clone())
+    }
+}
+
+impl Deref for ItemRef<'_> {
+    type Target = ItemInputs;
+
+    fn deref(&self) -> &ItemInputs {
+        self.inputs
+    }
+}
+
+/// An item of [`SceneItems`] being edited
+///
+/// Derefs to the item with its parts joined; they are split back into the
+/// scene's arrays when this is dropped. The item keeps its ID and position
+/// whatever it is changed to.
+pub(crate) struct ItemMut<'a> {
+    item: SceneItem,
+    inputs: &'a mut ItemInputs,
+    transform: &'a mut ItemTransform,
+    filters: &'a mut FilterChain,
+}
+
+impl<'a> ItemMut<'a> {
+    fn new(
+        inputs: &'a mut ItemInputs,
+        transform: &'a mut ItemTransform,
+        filters: &'a mut FilterChain,
+    ) -> Self {
+        Self {
+            item: SceneItem::from_parts(inputs, transform, std::mem::take(filters)),
+            inputs,
+            transform,
+            filters,
+        }
+    }
+}
+
+impl Deref for ItemMut<'_> {
+    type Target = SceneItem;
+
+    fn deref(&self) -> &SceneItem {
+        &self.item
+    }
+}
+
+impl DerefMut for ItemMut<'_> {
+    fn deref_mut(&mut self) -> &mut SceneItem {
+        &mut self.item
+    }
+}
+
+impl Drop for ItemMut<'_> {
+    fn drop(&mut self) {
+        // The ID keys the index map
+        self.item.id = self.inputs.id;
+        *self.inputs = self.item.inputs();
+        *self.transform = ItemTransform::of(&self.item);
+        *self.filters = std::mem::take(&mut self.item.filters);
+    }
+}
+
 /// A scene that contains multiple scene items
 ///
 /// Uses array-based storage (Vec) instead of linked lists for better cache locality.
@@ -16,7 +315,7 @@ use std::sync::Arc;
 /// safe writes (add/remove/reorder operations).
 pub struct Scene {
     /// Array-based storage for scene items (replaces linked list)
-    items: Arc<RwLock<Vec<SceneItem>>>,
+    pub(crate) items: Arc<RwLock<SceneItems>>,
 
     /// Scene dimensions
     width: u32,
@@ -25,23 +324,46 @@ pub struct Scene {
     /// Whether this is a group
     is_group: bool,
 
-    /// ID counter for generating unique item IDs
-    id_counter: AtomicI64,
+    /// ID counter for generating unique item IDs, shared by clones
+    id_counter: Arc<AtomicI64>,
+
+    /// Change subscribers, shared by clones
+    pub(crate) signals: Arc<SignalHub>,
+
+    /// Tree the scene is registered in, shared by clones
+    pub(crate) tree: Arc<RwLock<Option<TreeLink>>>,
 
     /// Statistics
     render_count: AtomicU64,
+    culled_offscreen: AtomicU64,
+    culled_covered: AtomicU64,
+}
+
+/// Render statistics of a scene handle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SceneStats {
+    /// Passes over the visible items
+    pub render_count: u64,
+    /// Items skipped for lying outside the scene
+    pub culled_offscreen: u64,
+    /// Items skipped for being hidden by opaque items above them
+    pub culled_covered: u64,
 }
 
 impl Scene {
     /// Create a new scene with the given dimensions
     pub fn new(width: u32, height: u32) -> Self {
         Self {
-            items: Arc::new(RwLock::new(Vec::with_capacity(16))), // Pre-allocate for typical scenes
+            items: Arc::new(RwLock::new(SceneItems::with_capacity(16))), // Pre-allocate for typical scenes
             width,
             height,
             is_group: false,
-            id_counter: AtomicI64::new(1),
+            id_counter: Arc::new(AtomicI64::new(1)),
+            signals: Arc::new(SignalHub::default()),
+            tree: Arc::new(RwLock::new(None)),
             render_count: AtomicU64::new(0),
+            culled_offscreen: AtomicU64::new(0),
+            culled_covered: AtomicU64::new(0),
         }
     }
 
@@ -70,24 +392,86 @@ impl Scene {
 
     /// Add a new item to the scene
     ///
-    /// Returns the ID of the newly added item.
-    pub fn add_item(&self, mut item: SceneItem) -> i64 {
-        let id = self.id_counter.fetch_add(1, Ordering::SeqCst);
-        item.id = id;
+    /// Returns the ID of the newly added item, or None if the scene is
+    /// registered in a `SceneTree` and the item would make it show itself.
+    pub fn add_item(&self, mut item: SceneItem) -> Option<i64> {
+        let id = TreeLink::edit(&self.tree, |cycles| {
+            if cycles.is_some_and(|cycles| cycles(item.source_id)) {
+                return None;
+            }
+            let mut items = self.items.write();
+            // Allocated under the lock, so a kept ID inserted meanwhile is
+            // already counted
+            let id = self.id_counter.fetch_add(1, Ordering::SeqCst);
+            item.id = id;
+            item.mark_transform_dirty();
+            items.push(item);
+            Some(id)
+        })?;
+        self.signals.emit(&[SceneSignal::ItemAdded { item: id }]);
+        Some(id)
+    }
+
+    /// Add an item keeping its ID, e.g. one loaded from a saved scene
+    ///
+    /// Later IDs from `add_item` are allocated past it. An item whose ID
+    /// is already taken gets a new one. Returns the item's ID, or None if
+    /// the item would make the scene show itself.
+    pub(crate) fn restore_item(&self, mut item: SceneItem) -> Option<i64> {
+        if self.items.read().position(item.id).is_some() {
+            return self.add_item(item);
+        }
+        let id = item.id;
+        if !self.insert_item(usize::MAX, item.clone()) {
+            // Taken since the check, or rejected
+            item.id = 0;
+            return self.add_item(item);
+        }
+        Some(id)
+    }
+
+    /// Insert an item at `index` (clamped to the end), keeping its ID
+    ///
+    /// Returns false, leaving the scene unchanged, if the ID is taken or
+    /// the item would make the scene show itself.
+    pub(crate) fn insert_item(&self, index: usize, mut item: SceneItem) -> bool {
         item.mark_transform_dirty();
+        let id = item.id;
+        let inserted = TreeLink::edit(&self.tree, |cycles| {
+            if cycles.is_some_and(|cycles| cycles(item.source_id)) {
+                return false;
+            }
+            let mut items = self.items.write();
+            if !items.insert(index, item) {
+                return false;
+            }
+            self.id_counter.fetch_max(id + 1, Ordering::SeqCst);
+            true
+        });
+        if !inserted {
+            return false;
+        }
+        self.signals.emit(&[SceneSignal::ItemAdded { item: id }]);
+        true
+    }
 
-        let mut items = self.items.write();
-        items.push(item);
-        id
+    /// Position of an item in drawing order
+    pub(crate) fn item_index(&self, id: i64) -> Option<usize> {
+        self.items.read().position(id)
+    }
+
+    /// Item IDs in drawing order
+    pub(crate) fn item_order(&self) -> Vec<i64> {
+        self.items.read().ids().collect()
     }
 
     /// Remove an item by ID
     ///
     /// Returns true if the item was found and removed.
     pub fn remove_item(&self, id: i64) -> bool {
-        let mut items = self.items.write();
-        if let Some(pos) = items.iter().position(|item| item.id == id) {
-            items.remove(pos);
+        let removed = self.items.write().remove(id);
+        if removed.is_some() {
+            self.signals.emit(&[SceneSignal::ItemRemoved { item: id }]);
             true
         } else {
             false
@@ -104,34 +488,35 @@ impl Scene {
     /// This is more efficient than individual moves for bulk reordering.
     pub fn reorder_items(&self, order: &[(i64, usize)]) {
         let mut items = self.items.write();
-        let mut new_items = Vec::with_capacity(items.len());
+        let before: Vec<i64> = items.ids().collect();
 
-        // Create a map of id -> item
-        let mut item_map: std::collections::HashMap<i64, SceneItem> =
-            items.drain(..).map(|item| (item.id, item)).collect();
+        // Items missing from the order list keep their relative order after
+        // the listed ones
+        let positions: Vec<usize> = order
+            .iter()
+            .filter_map(|&(id, _)| items.position(id))
+            .collect();
+        items.reorder(&positions);
 
-        // Build new order
-        for (id, _) in order {
-            if let Some(item) = item_map.remove(id) {
-                new_items.push(item);
-            }
+        let changed = items.ids().ne(before);
+        drop(items);
+        if changed {
+            self.signals.emit(&[SceneSignal::Reordered]);
         }
-
-        // Add any remaining items that weren't in the order list
-        new_items.extend(item_map.into_values());
-
-        *items = new_items;
     }
 
     /// Move an item to a new position
     pub fn move_item(&self, id: i64, new_index: usize) -> bool {
         let mut items = self.items.write();
-        if let Some(old_index) = items.iter().position(|item| item.id == id) {
+        if let Some(old_index) = items.position(id) {
             if new_index >= items.len() {
                 return false;
             }
-            let item = items.remove(old_index);
-            items.insert(new_index, item);
+            items.relocate(old_index, new_index);
+            drop(items);
+            if old_index != new_index {
+                self.signals.emit(&[SceneSignal::Reordered]);
+            }
             true
         } else {
             false
@@ -141,59 +526,77 @@ impl Scene {
     /// Update an item's properties
     ///
     /// The callback receives a mutable reference to the item if found.
+    /// Returns false, undoing the update, if the item is not found or the
+    /// scene is registered in a `SceneTree` and the new source would make
+    /// it show itself.
     pub fn update_item<F>(&self, id: i64, mut update_fn: F) -> bool
     where
         F: FnMut(&mut SceneItem),
     {
-        let mut items = self.items.write();
-        if let Some(item) = items.iter_mut().find(|item| item.id == id) {
-            update_fn(item);
+        let signals = TreeLink::edit(&self.tree, |cycles| {
+            let mut items = self.items.write();
+            let mut item = items.get_mut(id)?;
+            let before = (self.signals.is_active() || cycles.is_some()).then(|| item.clone());
+            update_fn(&mut item);
+            // ItemMut restores the ID
+            item.id = id;
+
+            if let (Some(cycles), Some(before)) = (cycles, &before) {
+                if item.source_id != before.source_id && cycles(item.source_id) {
+                    *item = before.clone();
+                    return None;
+                }
+            }
             item.mark_transform_dirty();
-            true
-        } else {
-            false
-        }
+
+            let mut signals = Vec::new();
+            if let Some(before) = before.filter(|_| self.signals.is_active()) {
+                item_changes(&before, &item, &mut signals);
+            }
+            Some(signals)
+        });
+        let Some(signals) = signals else {
+            return false;
+        };
+        self.signals.emit(&signals);
+        true
     }
 
     /// Render all visible items (lock-free read)
     ///
     /// This function acquires a read lock, allowing multiple threads to render
-    /// concurrently. The callback is invoked for each visible item.
+    /// concurrently. The callback is invoked for each visible item, borrowed
+    /// in place; [`ItemRef::to_item`] copies it out.
     pub fn render_items<F>(&self, mut callback: F)
     where
-        F: FnMut(&SceneItem),
+        F: FnMut(ItemRef<'_>),
     {
         self.render_count.fetch_add(1, Ordering::Relaxed);
 
         let items = self.items.read();
-        for item in items.iter() {
+        for item in items.refs() {
             if item.visible {
                 callback(item);
             }
         }
     }
 
+    /// Like [`Scene::render_items`], with the visible items passed together
+    pub(crate) fn render_visible<R>(&self, render: impl FnOnce(&[ItemRef<'_>]) -> R) -> R {
+        self.render_count.fetch_add(1, Ordering::Relaxed);
+
+        let items = self.items.read();
+        let visible: Vec<ItemRef<'_>> = items.refs().filter(|item| item.visible).collect();
+        render(&visible)
+    }
+
     /// Update transforms for all items that need it
     ///
     /// This should be called before rendering to ensure transforms are up-to-date.
-    /// Only items with dirty transforms are recalculated.
+    /// Only items with dirty transforms are recalculated, in parallel once
+    /// there are at least [`PARALLEL_TRANSFORM_THRESHOLD`] of them.
     pub fn update_transforms(&self, source_dimensions: &[(u64, u32, u32)]) {
-        let mut items = self.items.write();
-
-        // Create a map of source_id -> (width, height) for quick lookup
-        let dim_map: std::collections::HashMap<u64, (u32, u32)> = source_dimensions
-            .iter()
-            .map(|(id, w, h)| (*id, (*w, *h)))
-            .collect();
-
-        for item in items.iter_mut() {
-            if let Some(&(width, height)) = dim_map.get(&item.source_id) {
-                // Update transform if dirty or source size changed
-                if item.transform_dirty || item.source_size_changed(width, height) {
-                    update_item_transform(item, width, height);
-                }
-            }
-        }
+        self.items.write().update_transforms(source_dimensions);
     }
 
     /// Get statistics
@@ -201,14 +604,28 @@ impl Scene {
         self.render_count.load(Ordering::Relaxed)
     }
 
+    pub fn stats(&self) -> SceneStats {
+        SceneStats {
+            render_count: self.render_count.load(Ordering::Relaxed),
+            culled_offscreen: self.culled_offscreen.load(Ordering::Relaxed),
+            culled_covered: self.culled_covered.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn record_culled(&self, offscreen: u64, covered: u64) {
+        self.culled_offscreen
+            .fetch_add(offscreen, Ordering::Relaxed);
+        self.culled_covered.fetch_add(covered, Ordering::Relaxed);
+    }
+
     /// Get a snapshot of all items (for debugging/inspection)
     pub fn get_items_snapshot(&self) -> Vec<SceneItem> {
-        self.items.read().clone()
+        self.items.read().iter().collect()
     }
 
     /// Find an item by ID
     pub fn find_item(&self, id: i64) -> Option<SceneItem> {
-        self.items.read().iter().find(|item| item.id == id).cloned()
+        self.items.read().get(id)
     }
 }
 
@@ -220,8 +637,12 @@ impl Clone for Scene {
             width: self.width,
             height: self.height,
             is_group: self.is_group,
-            id_counter: AtomicI64::new(self.id_counter.load(Ordering::SeqCst)),
+            id_counter: Arc::clone(&self.id_counter),
+            signals: Arc::clone(&self.signals),
+            tree: Arc::clone(&self.tree),
             render_count: AtomicU64::new(0),
+            culled_offscreen: AtomicU64::new(0),
+            culled_covered: AtomicU64::new(0),
         }
     }
 }
@@ -229,6 +650,8 @@ impl Clone for Scene {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::filter::{Filter, Sharpen};
+    use crate::transform::update_item_transform;
 
     #[test]
     fn test_scene_creation() {
@@ -251,8 +674,8 @@ mod tests {
         let item1 = SceneItem::new(0, 100);
         let item2 = SceneItem::new(0, 200);
 
-        let id1 = scene.add_item(item1);
-        let id2 = scene.add_item(item2);
+        let id1 = scene.add_item(item1).unwrap();
+        let id2 = scene.add_item(item2).unwrap();
 
         assert_eq!(scene.item_count(), 2);
 
@@ -268,9 +691,9 @@ mod tests {
     fn test_move_item() {
         let scene = Scene::new(1920, 1080);
 
-        let id1 = scene.add_item(SceneItem::new(0, 100));
-        let id2 = scene.add_item(SceneItem::new(0, 200));
-        let id3 = scene.add_item(SceneItem::new(0, 300));
+        let id1 = scene.add_item(SceneItem::new(0, 100)).unwrap();
+        let id2 = scene.add_item(SceneItem::new(0, 200)).unwrap();
+        let id3 = scene.add_item(SceneItem::new(0, 300)).unwrap();
 
         // Move item 1 to position 2
         assert!(scene.move_item(id1, 2));
@@ -284,7 +707,7 @@ mod tests {
     #[test]
     fn test_update_item() {
         let scene = Scene::new(1920, 1080);
-        let id = scene.add_item(SceneItem::new(0, 100));
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
 
         assert!(scene.update_item(id, |item| {
             item.visible = false;
@@ -300,8 +723,8 @@ mod tests {
     fn test_render_items() {
         let scene = Scene::new(1920, 1080);
 
-        let id1 = scene.add_item(SceneItem::new(0, 100));
-        let id2 = scene.add_item(SceneItem::new(0, 200));
+        let id1 = scene.add_item(SceneItem::new(0, 100)).unwrap();
+        let id2 = scene.add_item(SceneItem::new(0, 200)).unwrap();
 
         // Hide one item
         scene.update_item(id2, |item| item.visible = false);
@@ -320,8 +743,8 @@ mod tests {
         use std::thread;
 
         let scene = Arc::new(Scene::new(1920, 1080));
-        scene.add_item(SceneItem::new(0, 100));
-        scene.add_item(SceneItem::new(0, 200));
+        scene.add_item(SceneItem::new(0, 100)).unwrap();
+        scene.add_item(SceneItem::new(0, 200)).unwrap();
 
         let mut handles = vec![];
 
@@ -343,10 +766,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_clones_share_items_and_ids() {
+        let scene = Scene::new(1920, 1080);
+        let handle = scene.clone();
+
+        let id1 = scene.add_item(SceneItem::new(0, 100)).unwrap();
+        let id2 = handle.add_item(SceneItem::new(0, 200)).unwrap();
+
+        assert_ne!(id1, id2);
+        assert_eq!(scene.item_count(), 2);
+    }
+
     #[test]
     fn test_update_transforms() {
         let scene = Scene::new(1920, 1080);
-        let id = scene.add_item(SceneItem::new(0, 100));
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
 
         // Provide source dimensions
         let dimensions = vec![(100, 1920, 1080)];
@@ -357,4 +792,129 @@ mod tests {
         assert_eq!(item.last_width, 1920);
         assert_eq!(item.last_height, 1080);
     }
+
+    #[test]
+    fn test_source_resize_updates_transform() {
+        let scene = Scene::new(1920, 1080);
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
+        scene.update_transforms(&[(100, 1920, 1080)]);
+
+        scene.update_transforms(&[(100, 1280, 720)]);
+        let item = scene.find_item(id).unwrap();
+        assert_eq!((item.last_width, item.last_height), (1280, 720));
+        assert_eq!(item.box_scale, glam::Vec2::new(1280.0, 720.0));
+    }
+
+    #[test]
+    fn test_index_follows_order() {
+        let scene = Scene::new(1920, 1080);
+        // Each item's filter is named after its source, to check the
+        // arrays stay in step
+        let item = |source_id: u64| {
+            let mut item = SceneItem::new(0, source_id);
+            let sharpen = Filter::Sharpen(Sharpen::default());
+            item.filters.add(&source_id.to_string(), sharpen);
+            item
+        };
+        let ids: Vec<i64> = (0..6).map(|i| scene.add_item(item(i)).unwrap()).collect();
+        let check = |scene: &Scene| {
+            let items = scene.items.read();
+            for (index, item) in items.refs().enumerate() {
+                assert_eq!(items.position(item.id), Some(index));
+                assert!(item.filters.get(&item.source_id.to_string()).is_some());
+            }
+            assert_eq!(items.index.len(), items.len());
+        };
+
+        scene.remove_item(ids[1]);
+        check(&scene);
+        scene.move_item(ids[5], 0);
+        check(&scene);
+        scene.move_item(ids[0], 3);
+        check(&scene);
+        scene.insert_item(
+            2,
+            SceneItem {
+                id: ids[1],
+                ..item(1)
+            },
+        );
+        check(&scene);
+        scene.reorder_items(&[(ids[3], 0), (ids[2], 1)]);
+        check(&scene);
+
+        assert_eq!(scene.item_index(ids[3]), Some(0));
+        assert_eq!(scene.find_item(ids[2]).unwrap().source_id, 2);
+        assert!(scene.find_item(ids[1]).is_some());
+        scene.remove_item(ids[1]);
+        assert!(scene.find_item(ids[1]).is_none());
+        assert!(!scene.update_item(ids[1], |_| {}));
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let scene = Scene::new(1920, 1080);
+        let id = scene.add_item(SceneItem::new(0, 100)).unwrap();
+
+        assert!(!scene.insert_item(0, SceneItem::new(id, 200)));
+        assert_eq!(scene.item_count(), 1);
+        assert_eq!(scene.find_item(id).unwrap().source_id, 100);
+
+        // Restored items with a taken ID get a new one
+        let restored = scene.restore_item(SceneItem::new(id, 300)).unwrap();
+        assert_ne!(restored, id);
+        assert_eq!(scene.item_order(), vec![id, restored]);
+        assert_eq!(scene.find_item(restored).unwrap().source_id, 300);
+        assert_eq!(scene.restore_item(SceneItem::new(10, 400)), Some(10));
+        assert_eq!(scene.add_item(SceneItem::new(0, 500)).unwrap(), 11);
+    }
+
+    #[test]
+    fn test_item_edits_keep_id_and_order() {
+        let scene = Scene::new(1920, 1080);
+        let ids: Vec<i64> = (0..3)
+            .map(|i| scene.add_item(SceneItem::new(0, i)).unwrap())
+            .collect();
+        scene.update_transforms(&[(0, 100, 100), (1, 100, 100), (2, 100, 100)]);
+
+        for mut item in scene.items.write().iter_mut() {
+            item.id = 42;
+            item.pos.x = 10.0;
+            item.mark_transform_dirty();
+        }
+        assert_eq!(scene.item_order(), ids);
+        assert_eq!(scene.item_index(ids[2]), Some(2));
+        assert!(scene.find_item(42).is_none());
+
+        // Edits reach the cached transforms on the next update
+        let item = scene.find_item(ids[1]).unwrap();
+        assert!(item.transform_dirty);
+        scene.update_transforms(&[(0, 100, 100), (1, 100, 100), (2, 100, 100)]);
+        let item = scene.find_item(ids[1]).unwrap();
+        assert!(!item.transform_dirty);
+        assert_eq!(item.draw_transform.w_axis.x, 10.0 - 50.0);
+    }
+
+    #[test]
+    fn test_parallel_transform_update() {
+        let count = PARALLEL_TRANSFORM_THRESHOLD as u64 * 2;
+        let scene = Scene::new(1920, 1080);
+        for i in 0..count {
+            let mut item = SceneItem::new(0, i % 8);
+            item.pos = glam::Vec2::new(i as f32, 0.0);
+            item.rotation = i as f32;
+            scene.add_item(item).unwrap();
+        }
+        let dimensions: Vec<_> = (0..8).map(|i| (i, 100 + i as u32, 50)).collect();
+        scene.update_transforms(&dimensions);
+
+        for item in scene.get_items_snapshot() {
+            let mut expected = item.clone();
+            expected.mark_transform_dirty();
+            update_item_transform(&mut expected, 100 + item.source_id as u32, 50);
+            assert!(!item.transform_dirty);
+            assert_eq!(item.draw_transform, expected.draw_transform);
+            assert_eq!(item.box_transform, expected.box_transform);
+        }
+    }
 }
@@ -0,0 +1,577 @@
+//! Nested scenes and groups
+//!
+//! A `SceneTree` registers scenes under source IDs. An item whose
+//! `source_id` names a registered scene shows that scene, transformed by
+//! the item. Groups are scenes created with `Scene::new_group`: their size
+//! is the bounding box of their children, recomputed on every transform
+//! update, so moving a child resizes the group.
+//!
+//! A registered scene checks every item added to it or changed to show
+//! another source, under one edit lock for the whole tree, and rejects the
+//! item if the scene would end up showing itself.
+//!
+//! Rendering flattens the tree into one list of `RenderCommand`s with the
+//! parent transforms and opacities applied. Children of an item showing a
+//! scene are clipped to the scene's size less the item's crop, and the
+//! crop's top-left corner becomes their origin. The scale filter and blend
+//! mode of that item are not applied to them.
+
+use crate::render::{ClipRect, RenderCommand};
+use crate::scene::Scene;
+use crate::types::SceneItem;
+use glam::{Mat4, Vec2, Vec3};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+/// Bounding box of a group's visible children, in group space
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GroupBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl GroupBounds {
+    pub fn size(&self) -> Vec2 {
+        (self.max - self.min).max(Vec2::ZERO)
+    }
+
+    /// Source size an item showing the group is transformed with
+    fn source_size(&self) -> (u32, u32) {
+        let size = self.size().ceil();
+        (size.x as u32, size.y as u32)
+    }
+}
+
+/// Scenes that can show one another
+#[derive(Default)]
+pub struct SceneTree {
+    shared: Arc<TreeShared>,
+}
+
+/// State of a tree its registered scenes point back to
+#[derive(Default)]
+pub(crate) struct TreeShared {
+    scenes: RwLock<HashMap<u64, Scene>>,
+    group_bounds: RwLock<HashMap<u64, GroupBounds>>,
+    // Held from a cycle check until the checked item is in its scene
+    edits: Mutex<()>,
+}
+
+/// Registration of a scene in a tree
+pub(crate) struct TreeLink {
+    tree: Weak<TreeShared>,
+    source_id: u64,
+}
+
+impl TreeLink {
+    /// Run `edit` under the tree's edit lock, with a check of whether
+    /// showing a source in the linked scene would form a cycle
+    ///
+    /// `edit` gets None if the tree is gone.
+    pub(crate) fn edit<R>(
+        link: &RwLock<Option<TreeLink>>,
+        edit: impl FnOnce(Option<&dyn Fn(u64) -> bool>) -> R,
+    ) -> R {
+        let linked = link
+            .read()
+            .as_ref()
+            .and_then(|link| Some((link.tree.upgrade()?, link.source_id)));
+        let Some((tree, own_id)) = linked else {
+            return edit(None);
+        };
+        let _edits = tree.edits.lock();
+        let scenes = tree.scenes.read();
+        // Unregistered since the link was read
+        if !scenes.contains_key(&own_id) {
+            return edit(None);
+        }
+        edit(Some(&|source_id| nests(&scenes, source_id, own_id)))
+    }
+}
+
+impl SceneTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `scene` as the source `source_id`
+    ///
+    /// Returns false if the ID is already taken or the scene is registered
+    /// in a tree already.
+    pub fn add_scene(&self, source_id: u64, scene: Scene) -> bool {
+        let _edits = self.shared.edits.lock();
+        let mut scenes = self.shared.scenes.write();
+        let mut link = scene.tree.write();
+        let linked = link
+            .as_ref()
+            .is_some_and(|link| link.tree.strong_count() > 0);
+        if linked || scenes.contains_key(&source_id) {
+            return false;
+        }
+        *link = Some(TreeLink {
+            tree: Arc::downgrade(&self.shared),
+            source_id,
+        });
+        drop(link);
+
+        // Items already in the scene must not show it
+        let items = scene.items.clone();
+        scenes.insert(source_id, scene);
+        let cycle = items
+            .read()
+            .refs()
+            .any(|item| nests(&scenes, item.source_id, source_id));
+        if cycle {
+            if let Some(scene) = scenes.remove(&source_id) {
+                *scene.tree.write() = None;
+            }
+            return false;
+        }
+        true
+    }
+
+    /// Unregister a scene and remove the items showing it
+    pub fn remove_scene(&self, source_id: u64) -> bool {
+        let _edits = self.shared.edits.lock();
+        let mut scenes = self.shared.scenes.write();
+        let Some(scene) = scenes.remove(&source_id) else {
+            return false;
+        };
+        *scene.tree.write() = None;
+        self.shared.group_bounds.write().remove(&source_id);
+
+        for scene in scenes.values() {
+            for item in scene.get_items_snapshot() {
+                if item.source_id == source_id {
+                    scene.remove_item(item.id);
+                }
+            }
+        }
+        true
+    }
+
+    /// Handle to a registered scene
+    pub fn scene(&self, source_id: u64) -> Option<Scene> {
+        self.shared.scenes.read().get(&source_id).cloned()
+    }
+
+    pub fn contains(&self, source_id: u64) -> bool {
+        self.shared.scenes.read().contains_key(&source_id)
+    }
+
+    pub fn scene_count(&self) -> usize {
+        self.shared.scenes.read().len()
+    }
+
+    /// Whether `descendant` is `ancestor` or shown somewhere inside it
+    pub fn is_nested(&self, ancestor: u64, descendant: u64) -> bool {
+        nests(&self.shared.scenes.read(), ancestor, descendant)
+    }
+
+    /// Add an item to the scene `scene_id`
+    ///
+    /// Returns None if the scene is not registered or the item would make
+    /// the scene contain itself. Items showing a group are marked as
+    /// group items.
+    pub fn add_item(&self, scene_id: u64, mut item: SceneItem) -> Option<i64> {
+        let scene = self.scene(scene_id)?;
+        if let Some(child) = self.scene(item.source_id) {
+            item.is_group = child.is_group();
+        }
+        scene.add_item(item)
+    }
+
+    /// Bounds of a group from the last transform update
+    pub fn group_bounds(&self, source_id: u64) -> Option<GroupBounds> {
+        self.shared.group_bounds.read().get(&source_id).copied()
+    }
+
+    /// Update transforms in every scene, children before parents
+    ///
+    /// `source_dimensions` holds the sizes of plain sources; the sizes of
+    /// nested scenes and groups are filled in by the tree.
+    pub fn update_transforms(&self, source_dimensions: &[(u64, u32, u32)]) {
+        let scenes = self.shared.scenes.read();
+        let mut update = TransformUpdate {
+            scenes: &scenes,
+            base: source_dimensions,
+            sizes: HashMap::new(),
+            bounds: HashMap::new(),
+            visiting: Vec::new(),
+        };
+        for &id in scenes.keys() {
+            update.scene_size(id);
+        }
+        *self.shared.group_bounds.write() = update.bounds;
+    }
+
+    /// Render commands for `root` with nested scenes flattened, back to
+    /// front
+    pub fn render(&self, root: u64) -> Vec<RenderCommand> {
+        let scenes = self.shared.scenes.read();
+        let bounds = self.shared.group_bounds.read();
+        let mut flatten = Flatten {
+            scenes: &scenes,
+            bounds: &bounds,
+            path: Vec::new(),
+            commands: Vec::new(),
+        };
+        flatten.scene(root, Mat4::IDENTITY, 1.0, &[]);
+        flatten.commands
+    }
+}
+
+/// Whether `descendant` is `ancestor` or shown somewhere inside it
+fn nests(scenes: &HashMap<u64, Scene>, ancestor: u64, descendant: u64) -> bool {
+    let mut pending = vec![ancestor];
+    let mut seen = Vec::new();
+
+    while let Some(id) = pending.pop() {
+        if id == descendant {
+            return true;
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        if let Some(scene) = scenes.get(&id) {
+            let items = scene.items.read();
+            pending.extend(
+                items
+                    .refs()
+                    .map(|item| item.source_id)
+                    .filter(|source_id| scenes.contains_key(source_id)),
+            );
+        }
+    }
+    false
+}
+
+/// State of one bottom-up transform pass
+struct TransformUpdate<'a> {
+    scenes: &'a HashMap<u64, Scene>,
+    base: &'a [(u64, u32, u32)],
+    sizes: HashMap<u64, (u32, u32)>,
+    bounds: HashMap<u64, GroupBounds>,
+    // Scenes on the current path; a repeat means a cycle
+    visiting: Vec<u64>,
+}
+
+impl TransformUpdate<'_> {
+    /// Update a scene after its children and return its source size
+    fn scene_size(&mut self, id: u64) -> Option<(u32, u32)> {
+        if let Some(&size) = self.sizes.get(&id) {
+            return Some(size);
+        }
+        if self.visiting.contains(&id) {
+            return None;
+        }
+        let scene = self.scenes.get(&id)?;
+
+        self.visiting.push(id);
+        let mut nested = Vec::new();
+        for item in scene.get_items_snapshot() {
+            if let Some((width, height)) = self.scene_size(item.source_id) {
+                nested.push((item.source_id, width, height));
+            }
+        }
+        self.visiting.pop();
+
+        let dimensions: Vec<(u64, u32, u32)> = self.base.iter().copied().chain(nested).collect();
+        scene.update_transforms(&dimensions);
+
+        let size = if scene.is_group() {
+            let bounds = children_bounds(scene);
+            self.bounds.insert(id, bounds);
+            bounds.source_size()
+        } else {
+            scene.dimensions()
+        };
+        self.sizes.insert(id, size);
+        Some(size)
+    }
+}
+
+/// Bounding box of the visible items' boxes
+fn children_bounds(scene: &Scene) -> GroupBounds {
+    let mut bounds: Option<GroupBounds> = None;
+    for item in scene.get_items_snapshot() {
+        if !item.visible {
+            continue;
+        }
+        for corner in [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE] {
+            let point = item
+                .box_transform
+                .transform_point3(corner.extend(0.0))
+                .truncate();
+            let b = bounds.get_or_insert(GroupBounds {
+                min: point,
+                max: point,
+            });
+            b.min = b.min.min(point);
+            b.max = b.max.max(point);
+        }
+    }
+    bounds.unwrap_or_default()
+}
+
+/// State of one top-down render pass
+struct Flatten<'a> {
+    scenes: &'a HashMap<u64, Scene>,
+    bounds: &'a HashMap<u64, GroupBounds>,
+    // Scenes on the current path
+    path: Vec<u64>,
+    commands: Vec<RenderCommand>,
+}
+
+impl Flatten<'_> {
+    /// Push the commands of a scene drawn with `parent`, `opacity` and
+    /// `clips`
+    fn scene(&mut self, id: u64, parent: Mat4, opacity: f32, clips: &[ClipRect]) {
+        let Some(scene) = self.scenes.get(&id) else {
+            return;
+        };
+        // Edits are checked for cycles, so this only guards the renderer
+        if self.path.contains(&id) {
+            return;
+        }
+        self.path.push(id);
+
+        // Nested scenes are read while this one's items stay locked; the
+        // path check keeps a scene from being locked twice
+        scene.render_items(|item| {
+            let mut command = RenderCommand::from_parts(item.inputs, item.transform);
+            command.transform = parent * command.transform;
+            if self.scenes.contains_key(&item.source_id) {
+                // Children are drawn relative to the group's top-left and the
+                // crop's, inside the cropped scene
+                let crop = command.crop;
+                let mut offset = self
+                    .bounds
+                    .get(&item.source_id)
+                    .map_or(Vec2::ZERO, |b| b.min);
+                offset += Vec2::new(crop.left as f32, crop.top as f32);
+                let size = Vec2::new(
+                    crop.calc_width(item.transform.last_width) as f32,
+                    crop.calc_height(item.transform.last_height) as f32,
+                );
+                let mut clips = clips.to_vec();
+                clips.push(ClipRect {
+                    transform: command.transform,
+                    min: Vec2::ZERO,
+                    max: size,
+                });
+                let transform = command.transform
+                    * Mat4::from_translation(Vec3::new(-offset.x, -offset.y, 0.0));
+                self.scene(item.source_id, transform, opacity * command.opacity, &clips);
+            } else {
+                command.opacity *= opacity;
+                command.clips = clips.to_vec();
+                self.commands.push(command);
+            }
+        });
+
+        self.path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rasterizer::{RgbaImage, SoftwareRenderer};
+    use crate::types::Alignment;
+
+    const MAIN: u64 = 1000;
+    const NESTED: u64 = 1001;
+    const GROUP: u64 = 1002;
+
+    fn top_left(source_id: u64, x: f32, y: f32) -> SceneItem {
+        let mut item = SceneItem::new(0, source_id);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        item.pos = Vec2::new(x, y);
+        item
+    }
+
+    fn origin(command: &RenderCommand) -> Vec2 {
+        command.transform.transform_point3(Vec3::ZERO).truncate()
+    }
+
+    fn tree() -> SceneTree {
+        let tree = SceneTree::new();
+        assert!(tree.add_scene(MAIN, Scene::new(1920, 1080)));
+        assert!(tree.add_scene(NESTED, Scene::new(640, 360)));
+        assert!(tree.add_scene(GROUP, Scene::new_group()));
+        assert!(!tree.add_scene(MAIN, Scene::new(1, 1)));
+        tree
+    }
+
+    #[test]
+    fn test_cycles_rejected() {
+        let tree = tree();
+        assert!(tree.add_item(MAIN, top_left(NESTED, 0.0, 0.0)).is_some());
+        assert!(tree.add_item(NESTED, top_left(GROUP, 0.0, 0.0)).is_some());
+
+        assert!(tree.add_item(MAIN, top_left(MAIN, 0.0, 0.0)).is_none());
+        assert!(tree.add_item(GROUP, top_left(MAIN, 0.0, 0.0)).is_none());
+        assert!(tree.add_item(GROUP, top_left(NESTED, 0.0, 0.0)).is_none());
+
+        // A scene may be shown more than once
+        assert!(tree.add_item(MAIN, top_left(GROUP, 0.0, 0.0)).is_some());
+        assert!(tree.is_nested(MAIN, GROUP));
+        assert!(!tree.is_nested(GROUP, MAIN));
+        assert!(tree.add_item(99, top_left(MAIN, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_nested_scene_inherits_transform() {
+        let tree = tree();
+        let mut item = top_left(NESTED, 100.0, 50.0);
+        item.scale = Vec2::splat(2.0);
+        tree.add_item(MAIN, item).unwrap();
+        tree.add_item(NESTED, top_left(7, 10.0, 10.0)).unwrap();
+
+        tree.update_transforms(&[(7, 20, 20)]);
+        let commands = tree.render(MAIN);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].source_id, 7);
+        assert_eq!(origin(&commands[0]), Vec2::new(120.0, 70.0));
+
+        // The nested scene is sized by its own dimensions
+        let main_item = &tree.scene(MAIN).unwrap().get_items_snapshot()[0];
+        assert_eq!((main_item.last_width, main_item.last_height), (640, 360));
+    }
+
+    #[test]
+    fn test_group_bounds_follow_children() {
+        let tree = tree();
+        let group_item = tree.add_item(MAIN, top_left(GROUP, 200.0, 100.0)).unwrap();
+        tree.add_item(GROUP, top_left(7, 10.0, 20.0)).unwrap();
+        let moving = tree.add_item(GROUP, top_left(8, 50.0, 40.0)).unwrap();
+
+        let dimensions = [(7, 20, 20), (8, 10, 10)];
+        tree.update_transforms(&dimensions);
+        let bounds = tree.group_bounds(GROUP).unwrap();
+        assert_eq!(bounds.min, Vec2::new(10.0, 20.0));
+        assert_eq!(bounds.size(), Vec2::new(50.0, 30.0));
+
+        // Children are drawn relative to the group's top-left
+        let commands = tree.render(MAIN);
+        assert_eq!(origin(&commands[0]), Vec2::new(200.0, 100.0));
+        assert_eq!(origin(&commands[1]), Vec2::new(240.0, 120.0));
+
+        let main = tree.scene(MAIN).unwrap();
+        assert!(main.find_item(group_item).unwrap().is_group);
+
+        tree.scene(GROUP)
+            .unwrap()
+            .update_item(moving, |item| item.pos.x = 100.0);
+        tree.update_transforms(&dimensions);
+        assert_eq!(
+            tree.group_bounds(GROUP).unwrap().size(),
+            Vec2::new(100.0, 30.0)
+        );
+        assert_eq!(main.find_item(group_item).unwrap().last_width, 100);
+    }
+
+    #[test]
+    fn test_hidden_parent_hides_children() {
+        let tree = tree();
+        let item = tree.add_item(MAIN, top_left(NESTED, 0.0, 0.0)).unwrap();
+        tree.add_item(NESTED, top_left(7, 0.0, 0.0)).unwrap();
+        tree.add_item(MAIN, top_left(8, 0.0, 0.0)).unwrap();
+        tree.update_transforms(&[(7, 20, 20), (8, 20, 20)]);
+        assert_eq!(tree.render(MAIN).len(), 2);
+
+        tree.scene(MAIN)
+            .unwrap()
+            .update_item(item, |item| item.visible = false);
+        let commands = tree.render(MAIN);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].source_id, 8);
+
+        assert!(tree.remove_scene(NESTED));
+        assert_eq!(tree.scene(MAIN).unwrap().item_count(), 1);
+    }
+
+    #[test]
+    fn test_scene_edits_checked_for_cycles() {
+        let tree = tree();
+        tree.add_item(MAIN, top_left(NESTED, 0.0, 0.0)).unwrap();
+        let nested = tree.scene(NESTED).unwrap();
+        let item = nested.add_item(top_left(7, 0.0, 0.0)).unwrap();
+
+        // Handles of a registered scene go through the check
+        assert!(nested.add_item(top_left(MAIN, 0.0, 0.0)).is_none());
+        assert!(!nested.update_item(item, |item| {
+            item.source_id = MAIN;
+            item.pos.x = 10.0;
+        }));
+        let unchanged = nested.find_item(item).unwrap();
+        assert_eq!((unchanged.source_id, unchanged.pos.x), (7, 0.0));
+        assert!(nested.update_item(item, |item| item.source_id = GROUP));
+        assert!(!tree.is_nested(NESTED, MAIN));
+
+        // A scene belongs to one tree, and cannot bring a cycle into it
+        assert!(!SceneTree::new().add_scene(1, nested.clone()));
+        let looping = Scene::new(100, 100);
+        looping.add_item(top_left(MAIN, 0.0, 0.0)).unwrap();
+        tree.scene(MAIN)
+            .unwrap()
+            .add_item(top_left(5, 0.0, 0.0))
+            .unwrap();
+        assert!(!tree.add_scene(5, looping));
+        assert!(!tree.contains(5));
+
+        // Unregistered scenes are not checked
+        assert!(tree.remove_scene(NESTED));
+        assert!(nested.add_item(top_left(MAIN, 0.0, 0.0)).is_some());
+    }
+
+    #[test]
+    fn test_concurrent_edits_cannot_form_cycle() {
+        for _ in 0..50 {
+            let tree = Arc::new(tree());
+            let threads: Vec<_> = [(MAIN, NESTED), (NESTED, MAIN)]
+                .into_iter()
+                .map(|(parent, child)| {
+                    let tree = Arc::clone(&tree);
+                    std::thread::spawn(move || {
+                        tree.add_item(parent, top_left(child, 0.0, 0.0)).is_some()
+                    })
+                })
+                .collect();
+            let added: Vec<bool> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+            assert_eq!(added.iter().filter(|&&added| added).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_nested_scene_clipped_to_cropped_size() {
+        let tree = tree();
+        let mut item = top_left(NESTED, 100.0, 50.0);
+        item.crop.left = 40;
+        item.crop.right = 100;
+        tree.add_item(MAIN, item).unwrap();
+        // Straddles the cropped scene's right edge
+        tree.add_item(NESTED, top_left(7, 500.0, 0.0)).unwrap();
+
+        tree.update_transforms(&[(7, 100, 100)]);
+        let commands = tree.render(MAIN);
+        assert_eq!(origin(&commands[0]), Vec2::new(560.0, 50.0));
+        assert_eq!(
+            commands[0].clips[0].bounds(),
+            (Vec2::new(100.0, 50.0), Vec2::new(600.0, 410.0))
+        );
+
+        let mut canvas = RgbaImage::new(700, 200, [0; 4]);
+        let mut source = RgbaImage::new(100, 100, [255; 4]);
+        let sources = HashMap::from([(7, source.as_frame())]);
+        let drawn =
+            unsafe { SoftwareRenderer::new().execute(&commands, &sources, &mut canvas.as_frame()) };
+        assert_eq!(drawn, 1);
+        assert_eq!(canvas.pixel(599, 60), [255; 4]);
+        assert_eq!(canvas.pixel(600, 60), [0; 4]);
+        assert_eq!(canvas.pixel(650, 60), [0; 4]);
+    }
+}
@@ -0,0 +1,297 @@
+//! Hit testing and selection for editors
+//!
+//! Queries run against each item's `box_transform` from the last transform
+//! update, so rotated, flipped and bounded items are hit where they are
+//! drawn. Hidden and locked items are never hit. Results are topmost first;
+//! items later in the scene are drawn on top.
+
+use crate::scene::Scene;
+use crate::signals::SceneSignal;
+use crate::types::SceneItem;
+use glam::{Vec2, Vec3};
+
+/// How a selection query combines with the current selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionMode {
+    /// Select only the hit items
+    #[default]
+    Replace,
+    /// Add the hit items to the selection
+    Add,
+    /// Flip the selection state of the hit items
+    Toggle,
+}
+
+/// Corners of the item's box in scene space, in winding order
+fn box_corners(item: &SceneItem) -> [Vec2; 4] {
+    [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y].map(|corner| {
+        item.box_transform
+            .transform_point3(corner.extend(0.0))
+            .truncate()
+    })
+}
+
+fn is_hittable(item: &SceneItem) -> bool {
+    item.visible && !item.locked
+}
+
+fn contains_point(item: &SceneItem, point: Vec2) -> bool {
+    let inverse = item.box_transform.inverse();
+    if !inverse.is_finite() {
+        return false;
+    }
+    let local = inverse.transform_point3(Vec3::new(point.x, point.y, 0.0));
+    (0.0..=1.0).contains(&local.x) && (0.0..=1.0).contains(&local.y)
+}
+
+/// Separating axis test between the item's box and an axis-aligned rect
+fn intersects_rect(item: &SceneItem, min: Vec2, max: Vec2) -> bool {
+    let corners = box_corners(item);
+    let rect = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+
+    let axes = [
+        Vec2::X,
+        Vec2::Y,
+        (corners[1] - corners[0]).perp(),
+        (corners[3] - corners[0]).perp(),
+    ];
+    axes.iter().all(|&axis| {
+        let project = |points: &[Vec2; 4]| {
+            points.iter().fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+                let d = p.dot(axis);
+                (lo.min(d), hi.max(d))
+            })
+        };
+        let (a_lo, a_hi) = project(&corners);
+        let (b_lo, b_hi) = project(&rect);
+        a_lo <= b_hi && b_lo <= a_hi
+    })
+}
+
+impl Scene {
+    /// Topmost item under a scene point
+    pub fn item_at(&self, point: Vec2) -> Option<i64> {
+        let items = self.items.read();
+        let found = items
+            .iter()
+            .rev()
+            .find(|item| is_hittable(item) && contains_point(item, point));
+        found.map(|item| item.id)
+    }
+
+    /// All items under a scene point, topmost first
+    pub fn items_at(&self, point: Vec2) -> Vec<i64> {
+        let items = self.items.read();
+        items
+            .iter()
+            .rev()
+            .filter(|item| is_hittable(item) && contains_point(item, point))
+            .map(|item| item.id)
+            .collect()
+    }
+
+    /// Items overlapping the rectangle spanned by two corners, topmost
+    /// first
+    pub fn items_in_rect(&self, a: Vec2, b: Vec2) -> Vec<i64> {
+        let (min, max) = (a.min(b), a.max(b));
+        let items = self.items.read();
+        items
+            .iter()
+            .rev()
+            .filter(|item| is_hittable(item) && intersects_rect(item, min, max))
+            .map(|item| item.id)
+            .collect()
+    }
+
+    /// IDs of the selected items, in scene order
+    pub fn selected_items(&self) -> Vec<i64> {
+        let items = self.items.read();
+        items
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| item.id)
+            .collect()
+    }
+
+    /// Set the selection state of one item
+    pub fn set_selected(&self, id: i64, selected: bool) -> bool {
+        let mut found = false;
+        self.update_selection(|item| {
+            if item.id == id {
+                found = true;
+                selected
+            } else {
+                item.selected
+            }
+        });
+        found
+    }
+
+    pub fn clear_selection(&self) {
+        self.update_selection(|_| false);
+    }
+
+    /// Apply `mode` with `ids` as the hit items
+    pub fn select(&self, ids: &[i64], mode: SelectionMode) {
+        self.update_selection(|item| {
+            let hit = ids.contains(&item.id);
+            match mode {
+                SelectionMode::Replace => hit,
+                SelectionMode::Add => item.selected || hit,
+                SelectionMode::Toggle => item.selected != hit,
+            }
+        });
+    }
+
+    /// Set each item's selection state, signaling the ones that change
+    fn update_selection(&self, mut selected: impl FnMut(&SceneItem) -> bool) {
+        let mut signals = Vec::new();
+        for mut item in self.items.write().iter_mut() {
+            let value = selected(&item);
+            if value != item.selected {
+                item.selected = value;
+                signals.push(SceneSignal::SelectionChanged {
+                    item: item.id,
+                    selected: value,
+                });
+            }
+        }
+        self.signals.emit(&signals);
+    }
+
+    /// Click selection: the topmost item under the point
+    ///
+    /// Clicking empty space with `Replace` clears the selection.
+    pub fn select_at(&self, point: Vec2, mode: SelectionMode) -> Option<i64> {
+        let hit = self.item_at(point);
+        self.select(hit.as_slice(), mode);
+        hit
+    }
+
+    /// Rubber-band selection of every item overlapping the rectangle
+    pub fn select_in_rect(&self, a: Vec2, b: Vec2, mode: SelectionMode) -> Vec<i64> {
+        let hits = self.items_in_rect(a, b);
+        self.select(&hits, mode);
+        hits
+    }
+
+    /// Cycle through the items under the point
+    ///
+    /// Selects the item below the topmost selected one under the point,
+    /// wrapping to the top, or the topmost item if none is selected.
+    pub fn select_next_at(&self, point: Vec2) -> Option<i64> {
+        let stack = self.items_at(point);
+        let selected = self.selected_items();
+        let next = match stack.iter().position(|id| selected.contains(id)) {
+            Some(index) => stack[(index + 1) % stack.len()],
+            None => *stack.first()?,
+        };
+        self.select(&[next], SelectionMode::Replace);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Alignment;
+
+    /// Scene with 100x100 sources at the given top-left positions
+    fn scene_with(positions: &[(f32, f32)]) -> (Scene, Vec<i64>) {
+        let scene = Scene::new(1920, 1080);
+        let ids = positions
+            .iter()
+            .map(|&(x, y)| {
+                let mut item = SceneItem::new(0, 1);
+                item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+                item.pos = Vec2::new(x, y);
+                scene.add_item(item).unwrap()
+            })
+            .collect();
+        scene.update_transforms(&[(1, 100, 100)]);
+        (scene, ids)
+    }
+
+    #[test]
+    fn test_item_at_respects_z_order() {
+        let (scene, ids) = scene_with(&[(0.0, 0.0), (50.0, 50.0)]);
+
+        assert_eq!(scene.item_at(Vec2::new(10.0, 10.0)), Some(ids[0]));
+        assert_eq!(scene.item_at(Vec2::new(75.0, 75.0)), Some(ids[1]));
+        assert_eq!(scene.items_at(Vec2::new(75.0, 75.0)), vec![ids[1], ids[0]]);
+        assert_eq!(scene.item_at(Vec2::new(500.0, 500.0)), None);
+    }
+
+    #[test]
+    fn test_hidden_and_locked_items_are_skipped() {
+        let (scene, ids) = scene_with(&[(0.0, 0.0), (0.0, 0.0), (0.0, 0.0)]);
+        scene.update_item(ids[2], |item| item.visible = false);
+        scene.update_item(ids[1], |item| item.locked = true);
+
+        assert_eq!(scene.items_at(Vec2::new(50.0, 50.0)), vec![ids[0]]);
+    }
+
+    #[test]
+    fn test_rotated_and_flipped_items() {
+        let (scene, ids) = scene_with(&[(200.0, 200.0), (500.0, 200.0)]);
+        // 45 degrees around the top-left corner: the box leans left
+        scene.update_item(ids[0], |item| item.rotation = 45.0);
+        // Mirrored to the left of its position
+        scene.update_item(ids[1], |item| item.scale = Vec2::new(-1.0, 1.0));
+        scene.update_transforms(&[(1, 100, 100)]);
+
+        assert_eq!(scene.item_at(Vec2::new(200.0, 250.0)), Some(ids[0]));
+        assert_eq!(scene.item_at(Vec2::new(290.0, 210.0)), None);
+        assert_eq!(scene.item_at(Vec2::new(450.0, 250.0)), Some(ids[1]));
+        assert_eq!(scene.item_at(Vec2::new(550.0, 250.0)), None);
+    }
+
+    #[test]
+    fn test_rect_selection() {
+        let (scene, ids) = scene_with(&[(0.0, 0.0), (300.0, 0.0), (600.0, 0.0)]);
+
+        // Corners in either order; touching an item selects it
+        let hits = scene.select_in_rect(
+            Vec2::new(350.0, 50.0),
+            Vec2::new(50.0, 20.0),
+            SelectionMode::Replace,
+        );
+        assert_eq!(hits, vec![ids[1], ids[0]]);
+        assert_eq!(scene.selected_items(), vec![ids[0], ids[1]]);
+
+        scene.select_in_rect(
+            Vec2::new(650.0, 50.0),
+            Vec2::new(320.0, 60.0),
+            SelectionMode::Toggle,
+        );
+        assert_eq!(scene.selected_items(), vec![ids[0], ids[2]]);
+
+        // A rotated box is tested by its real outline, not its bounding box
+        scene.update_item(ids[0], |item| item.rotation = 45.0);
+        scene.update_transforms(&[(1, 100, 100)]);
+        let near_corner = scene.items_in_rect(Vec2::new(60.0, 5.0), Vec2::new(70.0, 10.0));
+        assert!(near_corner.is_empty());
+    }
+
+    #[test]
+    fn test_click_and_cycle_selection() {
+        let (scene, ids) = scene_with(&[(0.0, 0.0), (0.0, 0.0), (0.0, 0.0)]);
+        let point = Vec2::new(50.0, 50.0);
+
+        assert_eq!(scene.select_at(point, SelectionMode::Replace), Some(ids[2]));
+        assert!(scene.find_item(ids[2]).unwrap().selected);
+
+        assert_eq!(scene.select_next_at(point), Some(ids[1]));
+        assert_eq!(scene.select_next_at(point), Some(ids[0]));
+        assert_eq!(scene.select_next_at(point), Some(ids[2]));
+        assert_eq!(scene.selected_items(), vec![ids[2]]);
+
+        assert_eq!(
+            scene.select_at(Vec2::new(900.0, 900.0), SelectionMode::Add),
+            None
+        );
+        assert_eq!(scene.selected_items(), vec![ids[2]]);
+        scene.select_at(Vec2::new(900.0, 900.0), SelectionMode::Replace);
+        assert!(scene.selected_items().is_empty());
+    }
+}
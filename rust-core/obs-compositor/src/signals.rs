@@ -0,0 +1,316 @@
+//! Change notifications for scenes
+//!
+//! Modeled on the libobs scene signals (`item_add`, `item_remove`,
+//! `reorder`, `item_transform`, `item_visible`, `item_select`,
+//! `item_locked`). Subscribers either get a callback on the thread making
+//! the change, or a crossbeam channel to drain at their own pace. Signals
+//! are sent after the scene's lock is released, so callbacks may query the
+//! scene.
+//!
+//! Every handle to a scene shares its subscribers. Transform updates from
+//! source resizes are not signaled; `update_transforms` runs every frame.
+
+use crate::scene::Scene;
+use crate::types::SceneItem;
+use crossbeam::channel::{self, Receiver, Sender};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A change to a scene, naming the item it affects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneSignal {
+    ItemAdded {
+        item: i64,
+    },
+    ItemRemoved {
+        item: i64,
+    },
+    /// Drawing order changed
+    Reordered,
+    /// Position, scale, rotation, alignment, bounds or crop changed
+    TransformChanged {
+        item: i64,
+    },
+    VisibilityChanged {
+        item: i64,
+        visible: bool,
+    },
+    SelectionChanged {
+        item: i64,
+        selected: bool,
+    },
+    LockChanged {
+        item: i64,
+        locked: bool,
+    },
+}
+
+impl SceneSignal {
+    /// Item the signal is about, if any
+    pub fn item(&self) -> Option<i64> {
+        match *self {
+            SceneSignal::Reordered => None,
+            SceneSignal::ItemAdded { item }
+            | SceneSignal::ItemRemoved { item }
+            | SceneSignal::TransformChanged { item }
+            | SceneSignal::VisibilityChanged { item, .. }
+            | SceneSignal::SelectionChanged { item, .. }
+            | SceneSignal::LockChanged { item, .. } => Some(item),
+        }
+    }
+}
+
+type Callback = Arc<dyn Fn(&SceneSignal) + Send + Sync>;
+
+enum Subscriber {
+    Callback(Callback),
+    Channel(Sender<SceneSignal>),
+}
+
+/// Subscribers of one scene, shared by its handles
+#[derive(Default)]
+pub(crate) struct SignalHub {
+    subscribers: RwLock<Vec<(u64, Subscriber)>>,
+    next_id: AtomicU64,
+}
+
+impl SignalHub {
+    pub(crate) fn is_active(&self) -> bool {
+        !self.subscribers.read().is_empty()
+    }
+
+    fn add(&self, subscriber: Subscriber) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribers.write().push((id, subscriber));
+        id
+    }
+
+    pub(crate) fn emit(&self, signals: &[SceneSignal]) {
+        if signals.is_empty() {
+            return;
+        }
+        // Callbacks run without the lock so they can subscribe or
+        // disconnect
+        let mut callbacks: Vec<Callback> = Vec::new();
+        let mut closed = Vec::new();
+        for (id, subscriber) in self.subscribers.read().iter() {
+            match subscriber {
+                Subscriber::Callback(callback) => callbacks.push(callback.clone()),
+                Subscriber::Channel(sender) => {
+                    if signals.iter().any(|&signal| sender.send(signal).is_err()) {
+                        closed.push(*id);
+                    }
+                }
+            }
+        }
+        if !closed.is_empty() {
+            self.subscribers
+                .write()
+                .retain(|(id, _)| !closed.contains(id));
+        }
+        for signal in signals {
+            for callback in &callbacks {
+                callback(signal);
+            }
+        }
+    }
+}
+
+/// Signals for the differences between two states of an item
+pub(crate) fn item_changes(before: &SceneItem, after: &SceneItem, out: &mut Vec<SceneSignal>) {
+    let item = after.id;
+    let transform_changed = before.pos != after.pos
+        || before.scale != after.scale
+        || before.rotation != after.rotation
+        || before.alignment != after.alignment
+        || before.bounds_type != after.bounds_type
+        || before.bounds_align != after.bounds_align
+        || before.bounds != after.bounds
+        || before.crop != after.crop
+        || before.crop_to_bounds != after.crop_to_bounds;
+    if transform_changed {
+        out.push(SceneSignal::TransformChanged { item });
+    }
+    if before.visible != after.visible {
+        out.push(SceneSignal::VisibilityChanged {
+            item,
+            visible: after.visible,
+        });
+    }
+    if before.selected != after.selected {
+        out.push(SceneSignal::SelectionChanged {
+            item,
+            selected: after.selected,
+        });
+    }
+    if before.locked != after.locked {
+        out.push(SceneSignal::LockChanged {
+            item,
+            locked: after.locked,
+        });
+    }
+}
+
+impl Scene {
+    /// Call `callback` with every change, on the thread making it
+    ///
+    /// Returns an ID for `disconnect`.
+    pub fn connect<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&SceneSignal) + Send + Sync + 'static,
+    {
+        self.signals.add(Subscriber::Callback(Arc::new(callback)))
+    }
+
+    /// Receive every change over an unbounded channel
+    ///
+    /// The subscription ends when the receiver is dropped.
+    pub fn subscribe(&self) -> Receiver<SceneSignal> {
+        let (sender, receiver) = channel::unbounded();
+        self.signals.add(Subscriber::Channel(sender));
+        receiver
+    }
+
+    pub fn disconnect(&self, id: u64) -> bool {
+        let mut subscribers = self.signals.subscribers.write();
+        let before = subscribers.len();
+        subscribers.retain(|(subscriber, _)| *subscriber != id);
+        subscribers.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::SceneHistory;
+    use crate::selection::SelectionMode;
+    use glam::Vec2;
+    use parking_lot::Mutex;
+
+    fn drain(receiver: &Receiver<SceneSignal>) -> Vec<SceneSignal> {
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn test_item_lifecycle_signals() {
+        let scene = Scene::new(1920, 1080);
+        let receiver = scene.subscribe();
+
+        let a = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let b = scene.add_item(SceneItem::new(0, 2)).unwrap();
+        scene.move_item(b, 0);
+        scene.remove_item(a);
+        assert!(!scene.remove_item(a));
+
+        assert_eq!(
+            drain(&receiver),
+            vec![
+                SceneSignal::ItemAdded { item: a },
+                SceneSignal::ItemAdded { item: b },
+                SceneSignal::Reordered,
+                SceneSignal::ItemRemoved { item: a },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_reports_what_changed() {
+        let scene = Scene::new(1920, 1080);
+        let id = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        let receiver = scene.subscribe();
+
+        scene.update_item(id, |item| {
+            item.pos = Vec2::new(10.0, 0.0);
+            item.visible = false;
+        });
+        scene.update_item(id, |item| item.locked = true);
+        // Unchanged values signal nothing
+        scene.update_item(id, |item| item.locked = true);
+
+        assert_eq!(
+            drain(&receiver),
+            vec![
+                SceneSignal::TransformChanged { item: id },
+                SceneSignal::VisibilityChanged {
+                    item: id,
+                    visible: false
+                },
+                SceneSignal::LockChanged {
+                    item: id,
+                    locked: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_and_commands_signal() {
+        let scene = Scene::new(1920, 1080);
+        let ids: Vec<i64> = (0..3)
+            .map(|_| scene.add_item(SceneItem::new(0, 1)).unwrap())
+            .collect();
+        scene.update_transforms(&[(1, 100, 100)]);
+        let receiver = scene.subscribe();
+
+        scene.select(&ids[..2], SelectionMode::Replace);
+        scene.select(&ids[1..], SelectionMode::Replace);
+        scene.clear_selection();
+        scene.flip_horizontal(&ids[2..]);
+
+        let selected = |item, selected| SceneSignal::SelectionChanged { item, selected };
+        assert_eq!(
+            drain(&receiver),
+            vec![
+                selected(ids[0], true),
+                selected(ids[1], true),
+                selected(ids[0], false),
+                selected(ids[2], true),
+                selected(ids[1], false),
+                selected(ids[2], false),
+                SceneSignal::TransformChanged { item: ids[2] },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callbacks_and_disconnect() {
+        let scene = Scene::new(1920, 1080);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        // Callbacks can read the scene they are notified about
+        let handle = scene.clone();
+        let log = seen.clone();
+        let connection = scene.connect(move |signal| {
+            let count = handle.item_count();
+            log.lock().push((*signal, count));
+        });
+
+        let id = scene.add_item(SceneItem::new(0, 1)).unwrap();
+        assert_eq!(*seen.lock(), vec![(SceneSignal::ItemAdded { item: id }, 1)]);
+
+        assert!(scene.disconnect(connection));
+        assert!(!scene.disconnect(connection));
+        scene.remove_item(id);
+        assert_eq!(seen.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_undo_signals_and_dropped_receivers() {
+        let scene = Scene::new(1920, 1080);
+        let mut history = SceneHistory::new(scene.clone());
+        let receiver = scene.subscribe();
+        drop(scene.subscribe());
+
+        let id = history.add_item(SceneItem::new(0, 1)).unwrap();
+        history.undo();
+        assert_eq!(
+            drain(&receiver),
+            vec![
+                SceneSignal::ItemAdded { item: id },
+                SceneSignal::ItemRemoved { item: id },
+            ]
+        );
+        assert_eq!(scene.signals.subscribers.read().len(), 1);
+    }
+}
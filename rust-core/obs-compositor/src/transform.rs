@@ -3,7 +3,7 @@
 //! This module provides SIMD-optimized transform calculations using glam,
 //! with a dirty flag system to prevent redundant recalculations.
 
-use crate::types::{Alignment, BoundsType, SceneItem, SceneItemCrop};
+use crate::types::{Alignment, BoundsType, ItemInputs, SceneItem, SceneItemCrop};
 use glam::{Mat4, Vec2};
 
 /// Add alignment offset to a position
@@ -23,7 +23,8 @@ fn add_alignment(pos: &mut Vec2, align: Alignment, cx: i32, cy: i32) {
 
 /// Calculate bounds data for a scene item
 fn calculate_bounds_data(
-    item: &mut SceneItem,
+    item: &ItemInputs,
+    bounds_crop: &mut SceneItemCrop,
     origin: &mut Vec2,
     scale: &mut Vec2,
     cx: &mut u32,
@@ -72,17 +73,14 @@ fn calculate_bounds_data(
     let new_width = (*cx as f32) * scale.x;
     let new_height = (*cy as f32) * scale.y;
 
-    let width_diff = bounds.x - new_width.abs();
-    let height_diff = bounds.y - new_height.abs();
+    let mut width_diff = bounds.x - new_width.abs();
+    let mut height_diff = bounds.y - new_height.abs();
     *cx = bounds.x as u32;
     *cy = bounds.y as u32;
 
-    add_alignment(
-        origin,
-        item.bounds_align,
-        -width_diff as i32,
-        -height_diff as i32,
-    );
+    // Size actually drawn, after cropping to the bounds
+    let mut drawn_width = new_width;
+    let mut drawn_height = new_height;
 
     // Set cropping if enabled and large enough size difference exists
     if item.crop_to_bounds && (width_diff < -0.1 || height_diff < -0.1) {
@@ -121,24 +119,40 @@ fn calculate_bounds_data(
             (overdraw_br.round() as i32, overdraw_tl.round() as i32)
         };
 
+        // The cropped item fills the box along that axis
+        let cropped = (crop_tl + crop_br) as f32 * crop_scale;
         if crop_width {
-            item.bounds_crop.right = crop_br;
-            item.bounds_crop.left = crop_tl;
+            bounds_crop.right = crop_br;
+            bounds_crop.left = crop_tl;
+            drawn_width -= cropped;
+            width_diff = bounds.x - drawn_width.abs();
         } else {
-            item.bounds_crop.bottom = crop_br;
-            item.bounds_crop.top = crop_tl;
+            bounds_crop.bottom = crop_br;
+            bounds_crop.top = crop_tl;
+            drawn_height -= cropped;
+            height_diff = bounds.y - drawn_height.abs();
         }
     }
 
+    add_alignment(
+        origin,
+        item.bounds_align,
+        -width_diff as i32,
+        -height_diff as i32,
+    );
+
     // Makes the item stay in-place in the box if flipped
-    origin.x += if new_width < 0.0 { new_width } else { 0.0 };
-    origin.y += if new_height < 0.0 { new_height } else { 0.0 };
+    origin.x += if drawn_width < 0.0 { drawn_width } else { 0.0 };
+    origin.y += if drawn_height < 0.0 {
+        drawn_height
+    } else {
+        0.0
+    };
 }
 
 /// Calculate the cropped width
-fn calc_cx(item: &SceneItem, width: u32) -> u32 {
-    let crop_cx =
-        (item.crop.left + item.crop.right + item.bounds_crop.left + item.bounds_crop.right) as u32;
+fn calc_cx(crop: &SceneItemCrop, width: u32) -> u32 {
+    let crop_cx = (crop.left + crop.right) as u32;
     if crop_cx > width {
         2
     } else {
@@ -147,9 +161,8 @@ fn calc_cx(item: &SceneItem, width: u32) -> u32 {
 }
 
 /// Calculate the cropped height
-fn calc_cy(item: &SceneItem, height: u32) -> u32 {
-    let crop_cy =
-        (item.crop.top + item.crop.bottom + item.bounds_crop.top + item.bounds_crop.bottom) as u32;
+fn calc_cy(crop: &SceneItemCrop, height: u32) -> u32 {
+    let crop_cy = (crop.top + crop.bottom) as u32;
     if crop_cy > height {
         2
     } else {
@@ -157,6 +170,124 @@ fn calc_cy(item: &SceneItem, height: u32) -> u32 {
     }
 }
 
+/// Cached results of a transform update, stored apart from the item's
+/// transform inputs in a scene
+///
+/// The fields match the cached fields of `SceneItem`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemTransform {
+    pub draw_transform: Mat4,
+    pub box_transform: Mat4,
+    pub output_scale: Vec2,
+    pub box_scale: Vec2,
+    pub bounds_crop: SceneItemCrop,
+    pub last_width: u32,
+    pub last_height: u32,
+    pub dirty: bool,
+}
+
+impl ItemTransform {
+    /// The cached fields of `item`
+    pub fn of(item: &SceneItem) -> Self {
+        Self {
+            draw_transform: item.draw_transform,
+            box_transform: item.box_transform,
+            output_scale: item.output_scale,
+            box_scale: item.box_scale,
+            bounds_crop: item.bounds_crop,
+            last_width: item.last_width,
+            last_height: item.last_height,
+            dirty: item.transform_dirty,
+        }
+    }
+
+    /// Copy into the cached fields of `item`
+    pub fn apply(&self, item: &mut SceneItem) {
+        item.draw_transform = self.draw_transform;
+        item.box_transform = self.box_transform;
+        item.output_scale = self.output_scale;
+        item.box_scale = self.box_scale;
+        item.bounds_crop = self.bounds_crop;
+        item.last_width = self.last_width;
+        item.last_height = self.last_height;
+        item.transform_dirty = self.dirty;
+    }
+
+    /// Whether `update` would recalculate it for a source of this size
+    pub(crate) fn needs_update(&self, width: u32, height: u32) -> bool {
+        self.dirty || self.last_width != width || self.last_height != height
+    }
+
+    /// Recalculate from the transform inputs of an item
+    pub(crate) fn update(item: &ItemInputs, source_width: u32, source_height: u32) -> Self {
+        let mut bounds_crop = SceneItemCrop::default();
+        let mut cx = calc_cx(&item.crop, source_width);
+        let mut cy = calc_cy(&item.crop, source_height);
+
+        let width = cx;
+        let height = cy;
+
+        let mut base_origin = Vec2::ZERO;
+        let mut origin = Vec2::ZERO;
+        let mut scale = item.scale;
+        let position = item.pos;
+
+        // Calculate bounds if enabled
+        if item.bounds_type != BoundsType::None {
+            calculate_bounds_data(
+                item,
+                &mut bounds_crop,
+                &mut origin,
+                &mut scale,
+                &mut cx,
+                &mut cy,
+            );
+        } else {
+            cx = (width as f32 * scale.x) as u32;
+            cy = (height as f32 * scale.y) as u32;
+        }
+
+        add_alignment(&mut origin, item.alignment, cx as i32, cy as i32);
+
+        // Build draw transform using SIMD-optimized glam operations
+        // Order: translate(position) * rotate(rotation) * translate(-origin) * scale(scale)
+        let draw_transform = Mat4::from_translation(position.extend(0.0))
+            * Mat4::from_rotation_z(item.rotation.to_radians())
+            * Mat4::from_translation((-origin).extend(0.0))
+            * Mat4::from_scale(scale.extend(1.0));
+
+        // Calculate box transform
+        let box_scale = if item.bounds_type != BoundsType::None {
+            item.bounds
+        } else {
+            Vec2::new(scale.x * width as f32, scale.y * height as f32)
+        };
+
+        add_alignment(
+            &mut base_origin,
+            item.alignment,
+            box_scale.x as i32,
+            box_scale.y as i32,
+        );
+
+        let box_transform = Mat4::from_translation(position.extend(0.0))
+            * Mat4::from_rotation_z(item.rotation.to_radians())
+            * Mat4::from_translation((-base_origin).extend(0.0))
+            * Mat4::from_scale(box_scale.extend(1.0));
+
+        Self {
+            draw_transform,
+            box_transform,
+            output_scale: scale,
+            box_scale,
+            bounds_crop,
+            last_width: source_width,
+            last_height: source_height,
+            dirty: false,
+        }
+    }
+}
+
 /// Update the transform matrices for a scene item
 ///
 /// This function recalculates the draw_transform and box_transform matrices
@@ -172,67 +303,7 @@ pub fn update_item_transform(item: &mut SceneItem, source_width: u32, source_hei
     if !item.transform_dirty {
         return;
     }
-
-    // Reset bounds crop
-    item.bounds_crop = SceneItemCrop::default();
-
-    let width = source_width;
-    let height = source_height;
-
-    let mut cx = calc_cx(item, width);
-    let mut cy = calc_cy(item, height);
-    item.last_width = width;
-    item.last_height = height;
-
-    let width = cx;
-    let height = cy;
-
-    let mut base_origin = Vec2::ZERO;
-    let mut origin = Vec2::ZERO;
-    let mut scale = item.scale;
-    let position = item.pos;
-
-    // Calculate bounds if enabled
-    if item.bounds_type != BoundsType::None {
-        calculate_bounds_data(item, &mut origin, &mut scale, &mut cx, &mut cy);
-    } else {
-        cx = (width as f32 * scale.x) as u32;
-        cy = (height as f32 * scale.y) as u32;
-    }
-
-    add_alignment(&mut origin, item.alignment, cx as i32, cy as i32);
-
-    // Build draw transform using SIMD-optimized glam operations
-    // Order: translate(position) * rotate(rotation) * translate(-origin) * scale(scale)
-    item.draw_transform = Mat4::from_translation(position.extend(0.0))
-        * Mat4::from_rotation_z(item.rotation.to_radians())
-        * Mat4::from_translation((-origin).extend(0.0))
-        * Mat4::from_scale(scale.extend(1.0));
-
-    item.output_scale = scale;
-
-    // Calculate box transform
-    let box_scale = if item.bounds_type != BoundsType::None {
-        item.bounds
-    } else {
-        Vec2::new(scale.x * width as f32, scale.y * height as f32)
-    };
-
-    item.box_scale = box_scale;
-
-    add_alignment(
-        &mut base_origin,
-        item.alignment,
-        box_scale.x as i32,
-        box_scale.y as i32,
-    );
-
-    item.box_transform = Mat4::from_translation(position.extend(0.0))
-        * Mat4::from_rotation_z(item.rotation.to_radians())
-        * Mat4::from_translation((-base_origin).extend(0.0))
-        * Mat4::from_scale(box_scale.extend(1.0));
-
-    item.transform_dirty = false;
+    ItemTransform::update(&item.inputs(), source_width, source_height).apply(item);
 }
 
 #[cfg(test)]
@@ -278,8 +349,8 @@ mod tests {
         item.crop.top = 5;
         item.crop.bottom = 15;
 
-        assert_eq!(calc_cx(&item, 100), 70); // 100 - 10 - 20
-        assert_eq!(calc_cy(&item, 100), 80); // 100 - 5 - 15
+        assert_eq!(calc_cx(&item.crop, 100), 70); // 100 - 10 - 20
+        assert_eq!(calc_cy(&item.crop, 100), 80); // 100 - 5 - 15
     }
 
     #[test]
@@ -311,6 +382,23 @@ mod tests {
         assert_eq!(item.draw_transform, old_transform);
     }
 
+    #[test]
+    fn test_bounds_crop_fills_box() {
+        // 100x100 covering a 100x50 box: 25 rows cropped off each side
+        let mut item = SceneItem::new(1, 42);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        item.bounds_type = BoundsType::ScaleOuter;
+        item.bounds = Vec2::new(100.0, 50.0);
+        item.crop_to_bounds = true;
+
+        update_item_transform(&mut item, 100, 100);
+
+        assert_eq!(item.bounds_crop.top, 25);
+        assert_eq!(item.bounds_crop.bottom, 25);
+        let top_left = item.draw_transform.transform_point3(glam::Vec3::ZERO);
+        assert_eq!(top_left, glam::Vec3::ZERO);
+    }
+
     #[test]
     fn test_bounds_stretch() {
         let mut item = SceneItem::new(1, 42);
@@ -0,0 +1,640 @@
+//! Interactive transform editing
+//!
+//! The math behind dragging an item or one of its eight resize handles in
+//! an editor preview. A `TransformDrag` captures the item and the snap
+//! targets when the drag starts; each mouse move passes the total delta
+//! since then and gets back the edited item.
+//!
+//! Handles are positioned on the item's box, so rotated, flipped and
+//! bounded items resize along their own axes. The point opposite the
+//! handle (or the center, when resizing from the center) stays where it
+//! is. Scaling changes `scale`, or `bounds` for items with a bounds type;
+//! crop mode changes `crop` instead.
+
+use crate::scene::Scene;
+use crate::transform::update_item_transform;
+use crate::types::{BoundsType, SceneItem};
+use glam::{Vec2, Vec3};
+
+/// Part of an item being dragged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// The item body: moves the item
+    Move,
+}
+
+impl Handle {
+    /// The eight resize handles
+    pub const RESIZE: [Handle; 8] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Left,
+        Handle::Right,
+        Handle::BottomLeft,
+        Handle::Bottom,
+        Handle::BottomRight,
+    ];
+
+    /// Position on the item's box, (0, 0) being the box's top-left
+    pub fn unit_position(self) -> Vec2 {
+        match self {
+            Handle::TopLeft => Vec2::new(0.0, 0.0),
+            Handle::Top => Vec2::new(0.5, 0.0),
+            Handle::TopRight => Vec2::new(1.0, 0.0),
+            Handle::Left => Vec2::new(0.0, 0.5),
+            Handle::Right => Vec2::new(1.0, 0.5),
+            Handle::BottomLeft => Vec2::new(0.0, 1.0),
+            Handle::Bottom => Vec2::new(0.5, 1.0),
+            Handle::BottomRight => Vec2::new(1.0, 1.0),
+            Handle::Move => Vec2::new(0.5, 0.5),
+        }
+    }
+
+    fn moves_x(self) -> bool {
+        self.unit_position().x != 0.5
+    }
+
+    fn moves_y(self) -> bool {
+        self.unit_position().y != 0.5
+    }
+
+    fn is_corner(self) -> bool {
+        self.moves_x() && self.moves_y()
+    }
+}
+
+/// Keyboard modifiers held during a drag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EditModifiers {
+    /// Keep the item's aspect ratio
+    pub keep_aspect: bool,
+    /// Crop the source instead of scaling it
+    pub crop: bool,
+    /// Resize around the item's center
+    pub from_center: bool,
+}
+
+/// Snapping configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapSettings {
+    pub enabled: bool,
+    /// Snap when within this many scene pixels of a target
+    pub distance: f32,
+    pub canvas_edges: bool,
+    pub canvas_center: bool,
+    pub item_edges: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            distance: 10.0,
+            canvas_edges: true,
+            canvas_center: true,
+            item_edges: true,
+        }
+    }
+}
+
+/// Scene coordinates an edge can snap to
+#[derive(Debug, Clone, Default)]
+struct SnapTargets {
+    x: Vec<f32>,
+    y: Vec<f32>,
+    distance: f32,
+}
+
+impl SnapTargets {
+    fn new(canvas: Vec2, others: &[SceneItem], settings: SnapSettings) -> Self {
+        let mut targets = Self {
+            distance: settings.distance,
+            ..Default::default()
+        };
+        if !settings.enabled {
+            return targets;
+        }
+        if settings.canvas_edges {
+            targets.x.extend([0.0, canvas.x]);
+            targets.y.extend([0.0, canvas.y]);
+        }
+        if settings.canvas_center {
+            targets.x.push(canvas.x / 2.0);
+            targets.y.push(canvas.y / 2.0);
+        }
+        if settings.item_edges {
+            for item in others.iter().filter(|item| item.visible) {
+                let (min, max) = box_extent(item);
+                targets.x.extend([min.x, max.x]);
+                targets.y.extend([min.y, max.y]);
+            }
+        }
+        targets
+    }
+
+    /// Offset moving the closest of `values` onto a target, if in range
+    fn offset(targets: &[f32], values: &[f32], distance: f32) -> f32 {
+        let mut best: Option<f32> = None;
+        for &value in values {
+            for &target in targets {
+                let offset = target - value;
+                if offset.abs() <= distance && best.is_none_or(|b| offset.abs() < b.abs()) {
+                    best = Some(offset);
+                }
+            }
+        }
+        best.unwrap_or(0.0)
+    }
+
+    fn snap_x(&self, values: &[f32]) -> f32 {
+        Self::offset(&self.x, values, self.distance)
+    }
+
+    fn snap_y(&self, values: &[f32]) -> f32 {
+        Self::offset(&self.y, values, self.distance)
+    }
+}
+
+/// Scene-space bounding box of an item's box
+pub(crate) fn box_extent(item: &SceneItem) -> (Vec2, Vec2) {
+    [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE]
+        .map(|corner| box_point(item, corner))
+        .iter()
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), &p| {
+            (min.min(p), max.max(p))
+        })
+}
+
+/// Scene position of a point on the item's box
+fn box_point(item: &SceneItem, unit: Vec2) -> Vec2 {
+    item.box_transform
+        .transform_point3(unit.extend(0.0))
+        .truncate()
+}
+
+/// Recompute transforms, then move the item so `unit` on its box lands on
+/// `target`
+fn reanchor(item: &mut SceneItem, unit: Vec2, target: Vec2) {
+    let (width, height) = (item.last_width, item.last_height);
+    item.mark_transform_dirty();
+    update_item_transform(item, width, height);
+
+    item.pos += target - box_point(item, unit);
+    item.mark_transform_dirty();
+    update_item_transform(item, width, height);
+}
+
+/// An in-progress drag of one item
+#[derive(Debug, Clone)]
+pub struct TransformDrag {
+    original: SceneItem,
+    handle: Handle,
+    targets: SnapTargets,
+}
+
+impl TransformDrag {
+    /// Start dragging `item` in a scene of size `canvas`
+    ///
+    /// `item` must have up-to-date transforms; `others` are the items it
+    /// can snap to.
+    pub fn new(
+        item: SceneItem,
+        handle: Handle,
+        canvas: Vec2,
+        others: &[SceneItem],
+        snap: SnapSettings,
+    ) -> Self {
+        Self {
+            targets: SnapTargets::new(canvas, others, snap),
+            original: item,
+            handle,
+        }
+    }
+
+    /// Start dragging an item of `scene`, snapping to its other items
+    pub fn begin(scene: &Scene, id: i64, handle: Handle, snap: SnapSettings) -> Option<Self> {
+        let items = scene.get_items_snapshot();
+        let item = items.iter().find(|item| item.id == id)?.clone();
+        let others: Vec<SceneItem> = items.into_iter().filter(|item| item.id != id).collect();
+        let (width, height) = scene.dimensions();
+        Some(Self::new(
+            item,
+            handle,
+            Vec2::new(width as f32, height as f32),
+            &others,
+            snap,
+        ))
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    pub fn original(&self) -> &SceneItem {
+        &self.original
+    }
+
+    /// The item after dragging by `delta` scene pixels since the start
+    pub fn update(&self, delta: Vec2, modifiers: EditModifiers) -> SceneItem {
+        let mut item = self.original.clone();
+        if item.last_width == 0 || item.last_height == 0 {
+            return item;
+        }
+
+        if self.handle == Handle::Move {
+            self.drag_move(&mut item, delta);
+            return item;
+        }
+
+        let inverse = item.box_transform.inverse();
+        if !inverse.is_finite() {
+            return item;
+        }
+
+        // Where the handle would go, snapped along the axes it moves
+        let start = box_point(&item, self.handle.unit_position());
+        let mut target = start + delta;
+        if self.handle.moves_x() {
+            target.x += self.targets.snap_x(&[target.x]);
+        }
+        if self.handle.moves_y() {
+            target.y += self.targets.snap_y(&[target.y]);
+        }
+
+        let handle = self.handle.unit_position();
+        let moved = inverse
+            .transform_point3(Vec3::new(target.x, target.y, 0.0))
+            .truncate();
+        let mut anchor = if modifiers.from_center {
+            Vec2::splat(0.5)
+        } else {
+            Vec2::ONE - handle
+        };
+        let anchor_scene = box_point(&item, anchor);
+
+        if modifiers.crop {
+            self.crop(&mut item, moved - handle);
+            reanchor(&mut item, anchor, anchor_scene);
+            return item;
+        }
+
+        // Size factor along each box axis
+        let factor_along = |moves: bool, from: f32, to: f32, anchor: f32| {
+            if moves && from != anchor {
+                (to - anchor) / (from - anchor)
+            } else {
+                1.0
+            }
+        };
+        let mut factor = Vec2::new(
+            factor_along(self.handle.moves_x(), handle.x, moved.x, anchor.x),
+            factor_along(self.handle.moves_y(), handle.y, moved.y, anchor.y),
+        );
+
+        if modifiers.keep_aspect {
+            if self.handle.is_corner() {
+                let magnitude = factor.x.abs().max(factor.y.abs());
+                factor = Vec2::new(magnitude.copysign(factor.x), magnitude.copysign(factor.y));
+            } else if self.handle.moves_x() {
+                // Edge handles grow the other axis around its center
+                factor.y = factor.x.abs();
+                anchor.y = 0.5;
+            } else {
+                factor.x = factor.y.abs();
+                anchor.x = 0.5;
+            }
+        }
+        let anchor_scene = box_point(&item, anchor);
+
+        if item.bounds_type == BoundsType::None {
+            item.scale *= factor;
+        } else {
+            item.bounds *= factor.abs();
+            // Dragging past the anchor mirrors the item
+            if factor.x < 0.0 {
+                item.scale.x = -item.scale.x;
+            }
+            if factor.y < 0.0 {
+                item.scale.y = -item.scale.y;
+            }
+        }
+        reanchor(&mut item, anchor, anchor_scene);
+        item
+    }
+
+    /// Write the dragged item into `scene`
+    pub fn apply(&self, scene: &Scene, delta: Vec2, modifiers: EditModifiers) -> bool {
+        let edited = self.update(delta, modifiers);
+        scene.update_item(edited.id, |item| *item = edited.clone())
+    }
+
+    fn drag_move(&self, item: &mut SceneItem, delta: Vec2) {
+        let (min, max) = box_extent(item);
+        let (min, max) = (min + delta, max + delta);
+        let center = (min + max) / 2.0;
+        let snap = Vec2::new(
+            self.targets.snap_x(&[min.x, center.x, max.x]),
+            self.targets.snap_y(&[min.y, center.y, max.y]),
+        );
+        item.pos += delta + snap;
+        item.mark_transform_dirty();
+        update_item_transform(item, item.last_width, item.last_height);
+    }
+
+    /// Move the dragged edges by `unit_delta` (in box units) through crop
+    fn crop(&self, item: &mut SceneItem, unit_delta: Vec2) {
+        let (width, height) = (item.last_width as i32, item.last_height as i32);
+        // Source pixels per box unit
+        let per_unit = (item.box_scale / item.output_scale).abs();
+        let pixels = (unit_delta * per_unit).round();
+        let (dx, dy) = (pixels.x as i32, pixels.y as i32);
+        let handle = self.handle.unit_position();
+        let crop = &mut item.crop;
+
+        // A crop already larger than the source leaves no room to move the
+        // dragged edge; pin it at zero instead of inverting the clamp range
+        if self.handle.moves_x() {
+            if handle.x == 0.0 {
+                crop.left = (crop.left + dx).clamp(0, (width - 1 - crop.right).max(0));
+            } else {
+                crop.right = (crop.right - dx).clamp(0, (width - 1 - crop.left).max(0));
+            }
+        }
+        if self.handle.moves_y() {
+            if handle.y == 0.0 {
+                crop.top = (crop.top + dy).clamp(0, (height - 1 - crop.bottom).max(0));
+            } else {
+                crop.bottom = (crop.bottom - dy).clamp(0, (height - 1 - crop.top).max(0));
+            }
+        }
+
+        // Bounded items shrink their box with the crop so content stays put
+        if item.bounds_type != BoundsType::None {
+            let change = Vec2::new(
+                (self.original.crop.left + self.original.crop.right - crop.left - crop.right)
+                    as f32,
+                (self.original.crop.top + self.original.crop.bottom - crop.top - crop.bottom)
+                    as f32,
+            );
+            item.bounds = (item.bounds + change * item.output_scale.abs()).max(Vec2::ONE);
+        }
+    }
+}
+
+/// Resize handle of `item` within `radius` scene pixels of `point`
+pub fn handle_at(item: &SceneItem, point: Vec2, radius: f32) -> Option<Handle> {
+    Handle::RESIZE
+        .into_iter()
+        .map(|handle| {
+            (
+                handle,
+                box_point(item, handle.unit_position()).distance(point),
+            )
+        })
+        .filter(|&(_, distance)| distance <= radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(handle, _)| handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Alignment;
+
+    const CANVAS: Vec2 = Vec2::new(1920.0, 1080.0);
+
+    /// 100x100 source at `pos`, aligned top-left, transforms updated
+    fn item_at(x: f32, y: f32) -> SceneItem {
+        let mut item = SceneItem::new(1, 7);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        item.pos = Vec2::new(x, y);
+        update_item_transform(&mut item, 100, 100);
+        item
+    }
+
+    fn drag(item: &SceneItem, handle: Handle) -> TransformDrag {
+        let no_snap = SnapSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        TransformDrag::new(item.clone(), handle, CANVAS, &[], no_snap)
+    }
+
+    fn extent(item: &SceneItem) -> (Vec2, Vec2) {
+        box_extent(item)
+    }
+
+    #[test]
+    fn test_edge_handles_keep_opposite_edge() {
+        let item = item_at(100.0, 100.0);
+
+        let right =
+            drag(&item, Handle::Right).update(Vec2::new(50.0, 7.0), EditModifiers::default());
+        assert_eq!(right.scale, Vec2::new(1.5, 1.0));
+        assert_eq!(
+            extent(&right),
+            (Vec2::new(100.0, 100.0), Vec2::new(250.0, 200.0))
+        );
+
+        let left =
+            drag(&item, Handle::Left).update(Vec2::new(-50.0, 0.0), EditModifiers::default());
+        assert_eq!(left.scale, Vec2::new(1.5, 1.0));
+        assert_eq!(
+            extent(&left),
+            (Vec2::new(50.0, 100.0), Vec2::new(200.0, 200.0))
+        );
+    }
+
+    #[test]
+    fn test_aspect_lock_and_from_center() {
+        let item = item_at(100.0, 100.0);
+
+        let corner = drag(&item, Handle::BottomRight).update(
+            Vec2::new(50.0, 10.0),
+            EditModifiers {
+                keep_aspect: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(corner.scale, Vec2::splat(1.5));
+        assert_eq!(extent(&corner).0, Vec2::new(100.0, 100.0));
+
+        let centered = drag(&item, Handle::Right).update(
+            Vec2::new(50.0, 0.0),
+            EditModifiers {
+                from_center: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(centered.scale, Vec2::new(2.0, 1.0));
+        assert_eq!(
+            extent(&centered),
+            (Vec2::new(50.0, 100.0), Vec2::new(250.0, 200.0))
+        );
+
+        let edge = drag(&item, Handle::Bottom).update(
+            Vec2::new(0.0, 100.0),
+            EditModifiers {
+                keep_aspect: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(edge.scale, Vec2::splat(2.0));
+        assert_eq!(
+            extent(&edge),
+            (Vec2::new(50.0, 100.0), Vec2::new(250.0, 300.0))
+        );
+    }
+
+    #[test]
+    fn test_rotated_item_resizes_along_its_axes() {
+        let mut item = item_at(500.0, 100.0);
+        item.rotation = 90.0;
+        item.mark_transform_dirty();
+        update_item_transform(&mut item, 100, 100);
+
+        // Rotated 90 degrees, the item's right edge faces down
+        let edited =
+            drag(&item, Handle::Right).update(Vec2::new(0.0, 50.0), EditModifiers::default());
+        assert!((edited.scale - Vec2::new(1.5, 1.0)).abs().max_element() < 1e-4);
+        let (min, max) = extent(&edited);
+        assert!((min - Vec2::new(400.0, 100.0)).abs().max_element() < 1e-3);
+        assert!((max - Vec2::new(500.0, 250.0)).abs().max_element() < 1e-3);
+    }
+
+    #[test]
+    fn test_drag_past_anchor_flips() {
+        let item = item_at(100.0, 100.0);
+        let flipped =
+            drag(&item, Handle::Right).update(Vec2::new(-150.0, 0.0), EditModifiers::default());
+        assert_eq!(flipped.scale, Vec2::new(-0.5, 1.0));
+        assert_eq!(
+            extent(&flipped),
+            (Vec2::new(50.0, 100.0), Vec2::new(100.0, 200.0))
+        );
+    }
+
+    #[test]
+    fn test_crop_mode() {
+        let item = item_at(100.0, 100.0);
+        let crop = EditModifiers {
+            crop: true,
+            ..Default::default()
+        };
+
+        let cropped = drag(&item, Handle::Left).update(Vec2::new(20.0, 0.0), crop);
+        assert_eq!(cropped.crop.left, 20);
+        assert_eq!(cropped.scale, Vec2::ONE);
+        assert_eq!(
+            extent(&cropped),
+            (Vec2::new(120.0, 100.0), Vec2::new(200.0, 200.0))
+        );
+
+        // Crop never goes negative or past the opposite edge
+        let uncropped = drag(&cropped, Handle::Left).update(Vec2::new(-80.0, 0.0), crop);
+        assert_eq!(uncropped.crop.left, 0);
+        let corner = drag(&item, Handle::BottomRight).update(Vec2::new(-500.0, -30.0), crop);
+        assert_eq!((corner.crop.right, corner.crop.bottom), (99, 30));
+    }
+
+    #[test]
+    fn test_crop_larger_than_source() {
+        let mut item = item_at(100.0, 100.0);
+        item.crop.left = 150;
+        item.crop.bottom = 300;
+        let crop = EditModifiers {
+            crop: true,
+            ..Default::default()
+        };
+
+        for handle in [Handle::TopLeft, Handle::BottomRight] {
+            let edited = drag(&item, handle).update(Vec2::new(10.0, 10.0), crop);
+            let c = edited.crop;
+            assert!(c.left >= 0 && c.right >= 0 && c.top >= 0 && c.bottom >= 0);
+        }
+        let right = drag(&item, Handle::Right).update(Vec2::new(-20.0, 0.0), crop);
+        assert_eq!((right.crop.left, right.crop.right), (150, 0));
+        let top = drag(&item, Handle::Top).update(Vec2::new(0.0, 20.0), crop);
+        assert_eq!((top.crop.top, top.crop.bottom), (0, 300));
+    }
+
+    #[test]
+    fn test_bounds_items_change_bounds() {
+        let mut item = item_at(100.0, 100.0);
+        item.bounds_type = BoundsType::ScaleInner;
+        item.bounds = Vec2::new(100.0, 100.0);
+        item.mark_transform_dirty();
+        update_item_transform(&mut item, 100, 100);
+
+        let edited =
+            drag(&item, Handle::Right).update(Vec2::new(100.0, 0.0), EditModifiers::default());
+        assert_eq!(edited.bounds, Vec2::new(200.0, 100.0));
+        assert_eq!(edited.scale, Vec2::ONE);
+        assert_eq!(
+            extent(&edited),
+            (Vec2::new(100.0, 100.0), Vec2::new(300.0, 200.0))
+        );
+    }
+
+    #[test]
+    fn test_snapping() {
+        let item = item_at(0.0, 0.0);
+        let other = item_at(600.0, 300.0);
+        let snap = SnapSettings::default();
+
+        // Right edge lands 5px from the canvas edge
+        let moved = TransformDrag::new(item.clone(), Handle::Move, CANVAS, &[], snap)
+            .update(Vec2::new(1815.0, 0.0), EditModifiers::default());
+        assert_eq!(moved.pos, Vec2::new(1820.0, 0.0));
+
+        // Item center snaps to the canvas center
+        let centered = TransformDrag::new(item.clone(), Handle::Move, CANVAS, &[], snap)
+            .update(Vec2::new(906.0, 487.0), EditModifiers::default());
+        assert_eq!(centered.pos, Vec2::new(910.0, 490.0));
+
+        // Handle snaps to another item's left edge
+        let resized = TransformDrag::new(item.clone(), Handle::Right, CANVAS, &[other], snap)
+            .update(Vec2::new(493.0, 0.0), EditModifiers::default());
+        assert_eq!(resized.scale.x, 6.0);
+
+        // Out of range, or disabled: no snapping
+        let free = TransformDrag::new(item.clone(), Handle::Move, CANVAS, &[], snap)
+            .update(Vec2::new(1790.0, 0.0), EditModifiers::default());
+        assert_eq!(free.pos.x, 1790.0);
+        let disabled =
+            drag(&item, Handle::Move).update(Vec2::new(1815.0, 0.0), EditModifiers::default());
+        assert_eq!(disabled.pos.x, 1815.0);
+    }
+
+    #[test]
+    fn test_drag_in_scene() {
+        let scene = Scene::new(1920, 1080);
+        let mut item = item_at(100.0, 100.0);
+        item.id = 0;
+        let id = scene.add_item(item).unwrap();
+        scene.update_transforms(&[(7, 100, 100)]);
+
+        let item = scene.find_item(id).unwrap();
+        assert_eq!(
+            handle_at(&item, Vec2::new(198.0, 203.0), 8.0),
+            Some(Handle::BottomRight)
+        );
+        assert_eq!(handle_at(&item, Vec2::new(150.0, 150.0), 8.0), None);
+
+        let drag =
+            TransformDrag::begin(&scene, id, Handle::BottomRight, SnapSettings::default()).unwrap();
+        assert!(drag.apply(&scene, Vec2::new(100.0, 100.0), EditModifiers::default()));
+        scene.update_transforms(&[(7, 100, 100)]);
+        assert_eq!(scene.find_item(id).unwrap().scale, Vec2::splat(2.0));
+        assert!(TransformDrag::begin(&scene, 99, Handle::Move, SnapSettings::default()).is_none());
+    }
+}
@@ -0,0 +1,307 @@
+//! Transform commands from the editor's Transform menu
+//!
+//! Fit, stretch, center, flip, rotate and reset, applied to a set of items
+//! the way the OBS frontend applies them to the selection. Locked items are
+//! left alone. Flip and rotate keep the top-left of the item's bounding box
+//! in place, so they need transforms from a previous update.
+
+use crate::scene::Scene;
+use crate::signals::{item_changes, SceneSignal};
+use crate::transform::update_item_transform;
+use crate::transform_edit::box_extent;
+use crate::types::{Alignment, BoundsType, SceneItem, SceneItemCrop};
+use glam::Vec2;
+
+/// Axes to center items on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CenterAxis {
+    Both,
+    Horizontal,
+    Vertical,
+}
+
+/// Recompute the transform from the last known source size
+fn refresh(item: &mut SceneItem) {
+    item.mark_transform_dirty();
+    if item.last_width != 0 && item.last_height != 0 {
+        update_item_transform(item, item.last_width, item.last_height);
+    }
+}
+
+/// Apply `change`, then move the item back so its bounding box keeps its
+/// top-left corner
+fn keep_top_left(item: &mut SceneItem, change: impl FnOnce(&mut SceneItem)) {
+    let (before, _) = box_extent(item);
+    change(item);
+    refresh(item);
+    let (after, _) = box_extent(item);
+    item.pos += before - after;
+    refresh(item);
+}
+
+impl Scene {
+    /// Run `op` on each unlocked item in `ids`, returning how many changed
+    fn transform_items(&self, ids: &[i64], mut op: impl FnMut(&mut SceneItem)) -> usize {
+        let mut items = self.items.write();
+        let mut count = 0;
+        let mut signals = Vec::new();
+        for mut item in items.iter_mut() {
+            if ids.contains(&item.id) && !item.locked {
+                let before = self.signals.is_active().then(|| item.clone());
+                op(&mut item);
+                if let Some(before) = before {
+                    item_changes(&before, &item, &mut signals);
+                }
+                count += 1;
+            }
+        }
+        drop(items);
+        self.signals.emit(&signals);
+        count
+    }
+
+    /// Fill the scene with each item under `bounds_type`, centered
+    fn align_to_screen(&self, ids: &[i64], bounds_type: BoundsType) -> usize {
+        let (width, height) = self.dimensions();
+        self.transform_items(ids, |item| {
+            item.pos = Vec2::ZERO;
+            item.scale = Vec2::ONE;
+            item.rotation = 0.0;
+            item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+            item.bounds_type = bounds_type;
+            item.bounds_align = Alignment::new(Alignment::CENTER);
+            item.bounds = Vec2::new(width as f32, height as f32);
+            refresh(item);
+        })
+    }
+
+    /// Scale each item to fit inside the scene, keeping its aspect ratio
+    pub fn fit_to_screen(&self, ids: &[i64]) -> usize {
+        self.align_to_screen(ids, BoundsType::ScaleInner)
+    }
+
+    /// Stretch each item over the whole scene
+    pub fn stretch_to_screen(&self, ids: &[i64]) -> usize {
+        self.align_to_screen(ids, BoundsType::Stretch)
+    }
+
+    /// Center the items as one group, keeping their relative positions
+    pub fn center_items(&self, ids: &[i64], axis: CenterAxis) -> usize {
+        let mut items = self.items.write();
+        let targets = || {
+            items
+                .iter()
+                .filter(|item| ids.contains(&item.id) && !item.locked)
+        };
+        let Some((min, max)) = targets()
+            .map(|item| box_extent(&item))
+            .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)))
+        else {
+            return 0;
+        };
+
+        let (width, height) = self.dimensions();
+        let screen = Vec2::new(width as f32, height as f32);
+        let mut offset = (screen - (max - min)) / 2.0 - min;
+        match axis {
+            CenterAxis::Both => {}
+            CenterAxis::Horizontal => offset.y = 0.0,
+            CenterAxis::Vertical => offset.x = 0.0,
+        }
+
+        let mut count = 0;
+        let mut signals = Vec::new();
+        for mut item in items.iter_mut() {
+            if ids.contains(&item.id) && !item.locked {
+                item.pos += offset;
+                refresh(&mut item);
+                if offset != Vec2::ZERO {
+                    signals.push(SceneSignal::TransformChanged { item: item.id });
+                }
+                count += 1;
+            }
+        }
+        drop(items);
+        self.signals.emit(&signals);
+        count
+    }
+
+    /// Mirror each item left to right
+    pub fn flip_horizontal(&self, ids: &[i64]) -> usize {
+        self.transform_items(ids, |item| {
+            keep_top_left(item, |item| item.scale.x = -item.scale.x)
+        })
+    }
+
+    /// Mirror each item top to bottom
+    pub fn flip_vertical(&self, ids: &[i64]) -> usize {
+        self.transform_items(ids, |item| {
+            keep_top_left(item, |item| item.scale.y = -item.scale.y)
+        })
+    }
+
+    /// Rotate each item by `degrees` (90 for clockwise, -90 for counter
+    /// clockwise, 180)
+    pub fn rotate_items(&self, ids: &[i64], degrees: f32) -> usize {
+        self.transform_items(ids, |item| {
+            keep_top_left(item, |item| {
+                let mut rotation = item.rotation + degrees;
+                if rotation >= 360.0 {
+                    rotation -= 360.0;
+                } else if rotation <= -360.0 {
+                    rotation += 360.0;
+                }
+                item.rotation = rotation;
+            })
+        })
+    }
+
+    /// Put each item back at the scene origin at its source size
+    pub fn reset_transform(&self, ids: &[i64]) -> usize {
+        self.transform_items(ids, |item| {
+            item.pos = Vec2::ZERO;
+            item.scale = Vec2::ONE;
+            item.rotation = 0.0;
+            item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+            item.bounds_type = BoundsType::None;
+            item.bounds_align = Alignment::new(Alignment::CENTER);
+            item.bounds = Vec2::ZERO;
+            item.crop_to_bounds = false;
+            item.crop = SceneItemCrop::default();
+            refresh(item);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Mat4, Vec3};
+
+    fn scene_with(sources: &[(u64, u32, u32, f32, f32)]) -> (Scene, Vec<i64>) {
+        let scene = Scene::new(1920, 1080);
+        let ids = sources
+            .iter()
+            .map(|&(source, _, _, x, y)| {
+                let mut item = SceneItem::new(0, source);
+                item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+                item.pos = Vec2::new(x, y);
+                scene.add_item(item).unwrap()
+            })
+            .collect();
+        let dims: Vec<_> = sources.iter().map(|&(s, w, h, _, _)| (s, w, h)).collect();
+        scene.update_transforms(&dims);
+        (scene, ids)
+    }
+
+    fn draw(scene: &Scene, id: i64) -> Mat4 {
+        scene.find_item(id).unwrap().draw_transform
+    }
+
+    fn assert_mat(actual: Mat4, expected: Mat4) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-3),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_fit_and_stretch() {
+        let (scene, ids) = scene_with(&[(1, 1280, 720, 50.0, 50.0), (2, 720, 720, 0.0, 0.0)]);
+        scene.update_item(ids[0], |item| item.rotation = 30.0);
+
+        assert_eq!(scene.fit_to_screen(&ids), 2);
+        let fitted = scene.find_item(ids[0]).unwrap();
+        assert_eq!(fitted.bounds_type, BoundsType::ScaleInner);
+        assert_eq!(fitted.bounds, Vec2::new(1920.0, 1080.0));
+        assert_mat(
+            fitted.draw_transform,
+            Mat4::from_scale(Vec3::new(1.5, 1.5, 1.0)),
+        );
+        // Square source is pillarboxed in the middle
+        assert_mat(
+            draw(&scene, ids[1]),
+            Mat4::from_translation(Vec3::new(420.0, 0.0, 0.0))
+                * Mat4::from_scale(Vec3::new(1.5, 1.5, 1.0)),
+        );
+
+        scene.stretch_to_screen(&ids[1..]);
+        assert_mat(
+            draw(&scene, ids[1]),
+            Mat4::from_scale(Vec3::new(1920.0 / 720.0, 1.5, 1.0)),
+        );
+    }
+
+    #[test]
+    fn test_center_as_group() {
+        let (scene, ids) = scene_with(&[(1, 100, 100, 0.0, 0.0), (1, 100, 100, 200.0, 50.0)]);
+
+        scene.center_items(&ids, CenterAxis::Horizontal);
+        assert_eq!(scene.find_item(ids[0]).unwrap().pos, Vec2::new(810.0, 0.0));
+
+        scene.center_items(&ids, CenterAxis::Both);
+        assert_eq!(
+            scene.find_item(ids[0]).unwrap().pos,
+            Vec2::new(810.0, 465.0)
+        );
+        assert_mat(
+            draw(&scene, ids[1]),
+            Mat4::from_translation(Vec3::new(1010.0, 515.0, 0.0)),
+        );
+        assert_eq!(scene.center_items(&[99], CenterAxis::Both), 0);
+    }
+
+    #[test]
+    fn test_flip_and_rotate_keep_top_left() {
+        let (scene, ids) = scene_with(&[(1, 200, 100, 100.0, 100.0)]);
+
+        scene.flip_horizontal(&ids);
+        assert_mat(
+            draw(&scene, ids[0]),
+            Mat4::from_translation(Vec3::new(300.0, 100.0, 0.0))
+                * Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)),
+        );
+        scene.flip_horizontal(&ids);
+        scene.flip_vertical(&ids);
+        assert_mat(
+            draw(&scene, ids[0]),
+            Mat4::from_translation(Vec3::new(100.0, 200.0, 0.0))
+                * Mat4::from_scale(Vec3::new(1.0, -1.0, 1.0)),
+        );
+        scene.flip_vertical(&ids);
+
+        scene.rotate_items(&ids, 90.0);
+        assert_mat(
+            draw(&scene, ids[0]),
+            Mat4::from_translation(Vec3::new(200.0, 100.0, 0.0))
+                * Mat4::from_rotation_z(90f32.to_radians()),
+        );
+        let (min, _) = box_extent(&scene.find_item(ids[0]).unwrap());
+        assert!((min - Vec2::new(100.0, 100.0)).abs().max_element() < 1e-3);
+
+        for _ in 0..4 {
+            scene.rotate_items(&ids, 90.0);
+        }
+        assert_eq!(scene.find_item(ids[0]).unwrap().rotation, 90.0);
+    }
+
+    #[test]
+    fn test_reset_and_locked_items() {
+        let (scene, ids) = scene_with(&[(1, 200, 100, 100.0, 100.0), (1, 200, 100, 5.0, 5.0)]);
+        scene.update_item(ids[0], |item| {
+            item.scale = Vec2::new(2.0, 3.0);
+            item.rotation = 45.0;
+            item.crop.left = 10;
+            item.bounds_type = BoundsType::ScaleOuter;
+            item.bounds = Vec2::new(50.0, 50.0);
+        });
+        scene.update_item(ids[1], |item| item.locked = true);
+
+        assert_eq!(scene.reset_transform(&ids), 1);
+        let reset = scene.find_item(ids[0]).unwrap();
+        assert_eq!(reset.crop, SceneItemCrop::default());
+        assert_eq!(reset.bounds_type, BoundsType::None);
+        assert_mat(reset.draw_transform, Mat4::IDENTITY);
+        assert_eq!(scene.find_item(ids[1]).unwrap().pos, Vec2::new(5.0, 5.0));
+    }
+}
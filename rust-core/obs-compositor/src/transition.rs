@@ -0,0 +1,525 @@
+//! Transitions between two scenes
+//!
+//! The counterpart of `plugins/obs-transitions`: a `SceneTransition` takes
+//! the output from scene A to scene B over a duration, with the progress
+//! shaped by an [`Easing`] curve. Each frame it produces a
+//! [`TransitionFrame`]. Cut, slide, swipe and stinger are plain
+//! `RenderCommand` lists built from both scenes; fades and luma wipes need
+//! a per-pixel mix, so they carry both lists for the backend to render
+//! separately and combine. `SoftwareRenderer::execute_transition` is the
+//! CPU reference for both. Slides and swipes clip each scene to its moved
+//! canvas rectangle, as OBS draws them from canvas-sized textures, so items
+//! hanging over a scene's edge do not show.
+//!
+//! Once the duration has passed the transition releases scene A and keeps
+//! rendering scene B, like an OBS transition settling on its target.
+
+use crate::animation::Easing;
+use crate::render::{render_scene, ClipRect, RenderCommand};
+use crate::scene::Scene;
+use crate::transform::update_item_transform;
+use crate::types::{Alignment, BoundsType, SceneItem};
+use glam::{Mat4, Vec2, Vec3};
+
+/// Direction scene content moves in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlideDirection {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SlideDirection {
+    fn unit(self) -> Vec2 {
+        match self {
+            SlideDirection::Left => Vec2::new(-1.0, 0.0),
+            SlideDirection::Right => Vec2::new(1.0, 0.0),
+            SlideDirection::Up => Vec2::new(0.0, -1.0),
+            SlideDirection::Down => Vec2::new(0.0, 1.0),
+        }
+    }
+}
+
+/// Luma wipe settings, as in `transition-luma-wipe.c`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LumaWipe {
+    /// Grayscale image; darker pixels switch to scene B first
+    pub image: u64,
+    /// Width of the blended edge, in luma
+    pub softness: f32,
+    /// Switch lighter pixels first
+    pub invert: bool,
+}
+
+impl LumaWipe {
+    pub fn new(image: u64) -> Self {
+        Self {
+            image,
+            softness: 0.03,
+            invert: false,
+        }
+    }
+}
+
+/// Stinger settings, as in `transition-stinger.c`
+///
+/// The media source plays over the whole canvas for the length of the
+/// transition, which should match the media's duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stinger {
+    pub media: u64,
+    /// Nanoseconds from the start at which scene B replaces scene A
+    /// under the media
+    pub transition_point: u64,
+}
+
+/// How the two scenes are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionKind {
+    /// Switch to scene B at once
+    Cut,
+    /// Crossfade from scene A to scene B
+    Fade,
+    /// Fade scene A to `color`, then fade from it to scene B, as in
+    /// `transition-fade-to-color.c`
+    ///
+    /// `color` is straight-alpha RGBA; `switch_point` is the progress
+    /// (0..1) at which the color fully covers the output and scene B
+    /// replaces scene A.
+    FadeToColor {
+        color: [u8; 4],
+        switch_point: f32,
+    },
+    /// Push scene A out while scene B follows it in
+    Slide(SlideDirection),
+    /// Move scene B in over scene A, or with `swipe_in` false move scene
+    /// A out to uncover scene B
+    Swipe {
+        direction: SlideDirection,
+        swipe_in: bool,
+    },
+    LumaWipe(LumaWipe),
+    Stinger(Stinger),
+}
+
+/// Per-pixel combination of two rendered scenes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelMix {
+    /// Crossfade at `progress` (0 shows A, 1 shows B)
+    Fade { progress: f32 },
+    /// Fade through `color` at `progress`; see
+    /// [`TransitionKind::FadeToColor`]
+    FadeToColor {
+        color: [u8; 4],
+        switch_point: f32,
+        progress: f32,
+    },
+    /// Luma wipe at `progress` (0 shows A, 1 shows B)
+    Luma { wipe: LumaWipe, progress: f32 },
+}
+
+/// What to draw for one frame of a transition
+#[derive(Debug, Clone)]
+pub enum TransitionFrame {
+    /// Commands drawn back to front, like `render_scene`
+    Commands(Vec<RenderCommand>),
+    /// Scenes rendered into separate targets and mixed
+    Mix {
+        a: Vec<RenderCommand>,
+        b: Vec<RenderCommand>,
+        mix: PixelMix,
+    },
+}
+
+impl TransitionFrame {
+    /// Command list of a frame that needs no mixing
+    pub fn commands(&self) -> Option<&[RenderCommand]> {
+        match self {
+            TransitionFrame::Commands(commands) => Some(commands),
+            TransitionFrame::Mix { .. } => None,
+        }
+    }
+}
+
+/// Moves the output from one scene to another
+pub struct SceneTransition {
+    pub kind: TransitionKind,
+    /// Nanoseconds
+    pub duration: u64,
+    /// Shape of the progress; stinger timing uses linear time
+    pub curve: Easing,
+    from: Option<Scene>,
+    to: Option<Scene>,
+    // Set by the first render when not given
+    start: Option<u64>,
+}
+
+impl SceneTransition {
+    pub fn new(kind: TransitionKind, duration: u64) -> Self {
+        Self {
+            kind,
+            duration,
+            curve: Easing::Linear,
+            from: None,
+            to: None,
+            start: None,
+        }
+    }
+
+    /// Transition from `from` to `to`, starting at the next render
+    pub fn start(&mut self, from: Scene, to: Scene) {
+        self.from = Some(from);
+        self.to = Some(to);
+        self.start = None;
+    }
+
+    /// Transition from `from` to `to` as if it started at `timestamp`
+    pub fn start_at(&mut self, from: Scene, to: Scene, timestamp: u64) {
+        self.start(from, to);
+        self.start = Some(timestamp);
+    }
+
+    /// Show `scene` without a transition
+    pub fn set_scene(&mut self, scene: Scene) {
+        self.from = None;
+        self.to = Some(scene);
+    }
+
+    /// Scene being transitioned to, or shown
+    pub fn scene(&self) -> Option<&Scene> {
+        self.to.as_ref()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.from.is_some()
+    }
+
+    /// Linear time from 0 to 1 at `timestamp`
+    pub fn time(&self, timestamp: u64) -> f32 {
+        if self.from.is_none() {
+            return 1.0;
+        }
+        let Some(start) = self.start else {
+            return 0.0;
+        };
+        if self.duration == 0 {
+            return 1.0;
+        }
+        (timestamp.saturating_sub(start) as f64 / self.duration as f64).min(1.0) as f32
+    }
+
+    /// Progress at `timestamp`, shaped by the curve
+    pub fn progress(&self, timestamp: u64) -> f32 {
+        self.curve.apply(self.time(timestamp))
+    }
+
+    /// Update both scenes' transforms from the source sizes and build the
+    /// frame at `timestamp`
+    pub fn render(&mut self, timestamp: u64, dimensions: &[(u64, u32, u32)]) -> TransitionFrame {
+        if self.from.is_some() {
+            self.start.get_or_insert(timestamp);
+        }
+        let time = self.time(timestamp);
+        if time >= 1.0 || self.kind == TransitionKind::Cut {
+            self.from = None;
+        }
+
+        let Some(to) = &self.to else {
+            return TransitionFrame::Commands(Vec::new());
+        };
+        to.update_transforms(dimensions);
+        let b = render_scene(to);
+        let Some(from) = &self.from else {
+            return TransitionFrame::Commands(b);
+        };
+        from.update_transforms(dimensions);
+        let a = render_scene(from);
+
+        let (width, height) = to.dimensions();
+        let size = Vec2::new(width as f32, height as f32);
+        let t = self.curve.apply(time);
+        let commands = match self.kind {
+            TransitionKind::Cut => b,
+            TransitionKind::Fade => {
+                return TransitionFrame::Mix {
+                    a,
+                    b,
+                    mix: PixelMix::Fade { progress: t },
+                };
+            }
+            TransitionKind::FadeToColor {
+                color,
+                switch_point,
+            } => {
+                return TransitionFrame::Mix {
+                    a,
+                    b,
+                    mix: PixelMix::FadeToColor {
+                        color,
+                        switch_point,
+                        progress: t,
+                    },
+                };
+            }
+            TransitionKind::Slide(direction) => {
+                let shift = direction.unit() * size;
+                let mut commands = offset(a, shift * t, size);
+                commands.extend(offset(b, -shift * (1.0 - t), size));
+                commands
+            }
+            TransitionKind::Swipe {
+                direction,
+                swipe_in: true,
+            } => {
+                let mut commands = offset(a, Vec2::ZERO, size);
+                commands.extend(offset(b, -direction.unit() * size * (1.0 - t), size));
+                commands
+            }
+            TransitionKind::Swipe {
+                direction,
+                swipe_in: false,
+            } => {
+                let mut commands = offset(b, Vec2::ZERO, size);
+                commands.extend(offset(a, direction.unit() * size * t, size));
+                commands
+            }
+            TransitionKind::LumaWipe(wipe) => {
+                return TransitionFrame::Mix {
+                    a,
+                    b,
+                    mix: PixelMix::Luma { wipe, progress: t },
+                };
+            }
+            TransitionKind::Stinger(stinger) => {
+                let point = if self.duration == 0 {
+                    0.0
+                } else {
+                    stinger.transition_point as f32 / self.duration as f32
+                };
+                let mut commands = if time < point.clamp(0.001, 0.999) {
+                    a
+                } else {
+                    b
+                };
+                commands.extend(cover(stinger.media, size, dimensions));
+                commands
+            }
+        };
+        TransitionFrame::Commands(commands)
+    }
+}
+
+/// Translate every command by `by` canvas pixels and clip it to the
+/// canvas of `size` moved along
+fn offset(commands: Vec<RenderCommand>, by: Vec2, size: Vec2) -> Vec<RenderCommand> {
+    let translation = Mat4::from_translation(Vec3::new(by.x, by.y, 0.0));
+    let clip = ClipRect {
+        transform: translation,
+        min: Vec2::ZERO,
+        max: size,
+    };
+    commands
+        .into_iter()
+        .map(|mut command| {
+            command.transform = translation * command.transform;
+            command.clips.push(clip);
+            command
+        })
+        .collect()
+}
+
+/// Command stretching `source` over the canvas, if its size is known
+fn cover(source: u64, size: Vec2, dimensions: &[(u64, u32, u32)]) -> Option<RenderCommand> {
+    let &(_, width, height) = dimensions.iter().find(|(id, _, _)| *id == source)?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let mut item = SceneItem::new(0, source);
+    item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+    item.bounds_type = BoundsType::Stretch;
+    item.bounds = size;
+    update_item_transform(&mut item, width, height);
+    Some(RenderCommand::from_item(&item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rasterizer::{RgbaImage, SoftwareRenderer};
+    use std::collections::HashMap;
+
+    const MS: u64 = 1_000_000;
+    const DIMENSIONS: &[(u64, u32, u32)] = &[(1, 100, 100), (2, 100, 100), (9, 50, 25)];
+
+    fn scenes() -> (Scene, Scene) {
+        let a = Scene::new(200, 100);
+        let b = Scene::new(200, 100);
+        let mut item = SceneItem::new(0, 1);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        a.add_item(item.clone()).unwrap();
+        item.source_id = 2;
+        b.add_item(item).unwrap();
+        (a, b)
+    }
+
+    fn commands(frame: TransitionFrame) -> Vec<(u64, Vec2, f32)> {
+        frame
+            .commands()
+            .unwrap()
+            .iter()
+            .map(|c| {
+                let pos = c.transform.w_axis.truncate().truncate();
+                (c.source_id, pos, c.opacity)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fade_and_curve() {
+        let (a, b) = scenes();
+        let mut transition = SceneTransition::new(TransitionKind::Fade, 1000 * MS);
+        assert!(commands(transition.render(0, DIMENSIONS)).is_empty());
+
+        transition.start_at(a, b, 1000 * MS);
+        assert!(transition.is_active());
+        let TransitionFrame::Mix { a, b, mix } = transition.render(1250 * MS, DIMENSIONS) else {
+            panic!("fade needs a pixel mix");
+        };
+        assert_eq!((a[0].opacity, b[0].opacity), (1.0, 1.0));
+        assert_eq!(mix, PixelMix::Fade { progress: 0.25 });
+
+        transition.curve = Easing::EaseIn;
+        assert!(transition.progress(1250 * MS) < 0.25);
+        assert_eq!(transition.time(1250 * MS), 0.25);
+
+        // Scene A is released at the end
+        assert_eq!(
+            commands(transition.render(2000 * MS, DIMENSIONS)),
+            vec![(2, Vec2::ZERO, 1.0)]
+        );
+        assert!(!transition.is_active());
+    }
+
+    #[test]
+    fn test_slide_swipe_and_cut() {
+        let (a, b) = scenes();
+        let mut transition =
+            SceneTransition::new(TransitionKind::Slide(SlideDirection::Left), 1000 * MS);
+        transition.start_at(a.clone(), b.clone(), 0);
+        assert_eq!(
+            commands(transition.render(250 * MS, DIMENSIONS)),
+            vec![
+                (1, Vec2::new(-50.0, 0.0), 1.0),
+                (2, Vec2::new(150.0, 0.0), 1.0)
+            ]
+        );
+
+        transition.kind = TransitionKind::Swipe {
+            direction: SlideDirection::Down,
+            swipe_in: false,
+        };
+        assert_eq!(
+            commands(transition.render(500 * MS, DIMENSIONS)),
+            vec![(2, Vec2::ZERO, 1.0), (1, Vec2::new(0.0, 50.0), 1.0)]
+        );
+
+        transition.kind = TransitionKind::Cut;
+        transition.start(a, b);
+        assert_eq!(
+            commands(transition.render(0, DIMENSIONS)),
+            vec![(2, Vec2::ZERO, 1.0)]
+        );
+        assert!(!transition.is_active());
+    }
+
+    #[test]
+    fn test_slide_clips_scenes_to_canvas() {
+        let (a, b) = (Scene::new(200, 100), Scene::new(200, 100));
+        let mut item = SceneItem::new(0, 1);
+        item.alignment = Alignment::new(Alignment::LEFT | Alignment::TOP);
+        // Straddles the right edge of scene A
+        item.pos = Vec2::new(150.0, 0.0);
+        a.add_item(item.clone()).unwrap();
+        // Off the canvas until the slide ends
+        item.source_id = 2;
+        item.pos = Vec2::new(100.0, 0.0);
+        b.add_item(item).unwrap();
+
+        let mut transition =
+            SceneTransition::new(TransitionKind::Slide(SlideDirection::Left), 1000 * MS);
+        transition.start_at(a, b, 0);
+        let frame = transition.render(250 * MS, DIMENSIONS);
+        let slid = frame.commands().unwrap();
+        assert_eq!(
+            slid[0].clips[0].bounds(),
+            (Vec2::new(-50.0, 0.0), Vec2::new(150.0, 100.0))
+        );
+        assert_eq!(
+            slid[1].clips[0].bounds(),
+            (Vec2::new(150.0, 0.0), Vec2::new(350.0, 100.0))
+        );
+
+        let mut canvas = RgbaImage::new(200, 100, [0; 4]);
+        let mut white = RgbaImage::new(100, 100, [255; 4]);
+        let mut gray = RgbaImage::new(100, 100, [128, 128, 128, 255]);
+        let sources = HashMap::from([(1, white.as_frame()), (2, gray.as_frame())]);
+        unsafe {
+            SoftwareRenderer::new().execute_transition(&frame, &sources, &mut canvas.as_frame())
+        };
+        assert_eq!(canvas.pixel(149, 50), [255; 4]);
+        assert_eq!(canvas.pixel(150, 50), [0; 4]);
+        assert_eq!(canvas.pixel(199, 50), [0; 4]);
+    }
+
+    #[test]
+    fn test_stinger_transition_point() {
+        let (a, b) = scenes();
+        let stinger = Stinger {
+            media: 9,
+            transition_point: 400 * MS,
+        };
+        let mut transition = SceneTransition::new(TransitionKind::Stinger(stinger), 1000 * MS);
+        // An eased curve does not move the transition point
+        transition.curve = Easing::EaseOut;
+        transition.start(a, b);
+
+        let frame = transition.render(5000 * MS, DIMENSIONS);
+        let stretched = Mat4::from_scale(Vec3::new(4.0, 4.0, 1.0));
+        let overlay = &frame.commands().unwrap()[1];
+        assert_eq!(overlay.source_id, 9);
+        assert!(overlay.transform.abs_diff_eq(stretched, 1e-4));
+
+        let sources =
+            |frame: TransitionFrame| -> Vec<u64> { commands(frame).iter().map(|c| c.0).collect() };
+        assert_eq!(sources(transition.render(5399 * MS, DIMENSIONS)), [1, 9]);
+        assert_eq!(sources(transition.render(5400 * MS, DIMENSIONS)), [2, 9]);
+        // No overlay until the media has a size
+        assert_eq!(sources(transition.render(5500 * MS, &DIMENSIONS[..2])), [2]);
+    }
+
+    #[test]
+    fn test_luma_wipe_frame() {
+        let (a, b) = scenes();
+        let wipe = LumaWipe::new(9);
+        let mut transition = SceneTransition::new(TransitionKind::LumaWipe(wipe), 1000 * MS);
+        transition.start_at(a, b, 0);
+
+        let TransitionFrame::Mix { a, b, mix } = transition.render(300 * MS, DIMENSIONS) else {
+            panic!("luma wipe needs a pixel mix");
+        };
+        assert_eq!((a[0].source_id, b[0].source_id), (1, 2));
+        assert_eq!(
+            mix,
+            PixelMix::Luma {
+                wipe,
+                progress: 0.3
+            }
+        );
+        assert!(transition
+            .render(1000 * MS, DIMENSIONS)
+            .commands()
+            .is_some());
+    }
+}
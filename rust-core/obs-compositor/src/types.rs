@@ -3,6 +3,8 @@
 //! This module defines the data structures for scene items and their properties,
 //! designed to be C-compatible for FFI while optimized for Rust performance.
 
+use crate::filter::FilterChain;
+use crate::transform::ItemTransform;
 use glam::{Mat4, Vec2};
 
 /// Crop settings for a scene item
@@ -176,6 +178,10 @@ pub struct SceneItem {
     pub blend_mode: BlendMode,
     /// Scale filter
     pub scale_filter: ScaleFilter,
+    /// Opacity from 0 (transparent) to 1, multiplied into the source alpha
+    pub opacity: f32,
+    /// Filters applied after the source's own
+    pub filters: FilterChain,
 
     // State flags
     /// Item is visible
@@ -188,6 +194,33 @@ pub struct SceneItem {
     pub is_group: bool,
 }
 
+/// The fields of a scene item that are neither cached from a transform
+/// update nor filters
+///
+/// Scenes store these apart from the cached transforms and filter chains,
+/// so a transform update or a render pass reads only what it needs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemInputs {
+    pub id: i64,
+    pub source_id: u64,
+    pub pos: Vec2,
+    pub scale: Vec2,
+    pub rotation: f32,
+    pub alignment: Alignment,
+    pub bounds_type: BoundsType,
+    pub bounds_align: Alignment,
+    pub bounds: Vec2,
+    pub crop_to_bounds: bool,
+    pub crop: SceneItemCrop,
+    pub blend_mode: BlendMode,
+    pub scale_filter: ScaleFilter,
+    pub opacity: f32,
+    pub visible: bool,
+    pub locked: bool,
+    pub selected: bool,
+    pub is_group: bool,
+}
+
 impl SceneItem {
     /// Create a new scene item with default properties
     pub fn new(id: i64, source_id: u64) -> Self {
@@ -213,6 +246,8 @@ impl SceneItem {
             last_height: 0,
             blend_mode: BlendMode::Normal,
             scale_filter: ScaleFilter::Disable,
+            opacity: 1.0,
+            filters: FilterChain::new(),
             visible: true,
             locked: false,
             selected: false,
@@ -220,6 +255,58 @@ impl SceneItem {
         }
     }
 
+    /// An item from its inputs, cached transform and filters
+    pub fn from_parts(inputs: &ItemInputs, cached: &ItemTransform, filters: FilterChain) -> Self {
+        let mut item = Self {
+            id: inputs.id,
+            source_id: inputs.source_id,
+            pos: inputs.pos,
+            scale: inputs.scale,
+            rotation: inputs.rotation,
+            alignment: inputs.alignment,
+            bounds_type: inputs.bounds_type,
+            bounds_align: inputs.bounds_align,
+            bounds: inputs.bounds,
+            crop_to_bounds: inputs.crop_to_bounds,
+            crop: inputs.crop,
+            blend_mode: inputs.blend_mode,
+            scale_filter: inputs.scale_filter,
+            opacity: inputs.opacity,
+            filters,
+            visible: inputs.visible,
+            locked: inputs.locked,
+            selected: inputs.selected,
+            is_group: inputs.is_group,
+            ..Self::new(inputs.id, inputs.source_id)
+        };
+        cached.apply(&mut item);
+        item
+    }
+
+    /// The item's fields apart from its cached transform and filters
+    pub fn inputs(&self) -> ItemInputs {
+        ItemInputs {
+            id: self.id,
+            source_id: self.source_id,
+            pos: self.pos,
+            scale: self.scale,
+            rotation: self.rotation,
+            alignment: self.alignment,
+            bounds_type: self.bounds_type,
+            bounds_align: self.bounds_align,
+            bounds: self.bounds,
+            crop_to_bounds: self.crop_to_bounds,
+            crop: self.crop,
+            blend_mode: self.blend_mode,
+            scale_filter: self.scale_filter,
+            opacity: self.opacity,
+            visible: self.visible,
+            locked: self.locked,
+            selected: self.selected,
+            is_group: self.is_group,
+        }
+    }
+
     /// Mark transform as dirty (needs recalculation)
     pub fn mark_transform_dirty(&mut self) {
         self.transform_dirty = true;
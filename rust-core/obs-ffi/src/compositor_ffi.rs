@@ -2,14 +2,20 @@
 //!
 //! C-compatible API for the Rust scene compositor.
 
-use obs_compositor::{Scene, SceneItem};
-use std::os::raw::{c_int, c_void};
+use obs_compositor::{Scene, SceneHistory, SceneItem, DEFAULT_HISTORY_MEMORY};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
 
 /// Opaque handle to Scene (C-compatible)
 pub struct OBSScene {
     _private: [u8; 0],
 }
 
+/// Opaque handle to SceneHistory (C-compatible)
+pub struct OBSSceneHistory {
+    _private: [u8; 0],
+}
+
 /// C-compatible scene item structure
 #[repr(C)]
 pub struct CSceneItem {
@@ -24,6 +30,17 @@ pub struct CSceneItem {
     pub locked: bool,
 }
 
+impl CSceneItem {
+    /// Copy the transform and state flags onto an item
+    fn apply_to(&self, item: &mut SceneItem) {
+        item.pos = glam::Vec2::new(self.pos_x, self.pos_y);
+        item.scale = glam::Vec2::new(self.scale_x, self.scale_y);
+        item.rotation = self.rotation;
+        item.visible = self.visible;
+        item.locked = self.locked;
+    }
+}
+
 // ============================================================================
 // SCENE COMPOSITOR API
 // ============================================================================
@@ -51,6 +68,8 @@ pub unsafe extern "C" fn obs_rust_scene_destroy(ptr: *mut OBSScene) {
 
 /// Add an item to the scene
 ///
+/// Returns -1 if the item is rejected for making the scene show itself.
+///
 /// # Safety
 /// Caller must ensure ptr and item are valid.
 #[no_mangle]
@@ -65,13 +84,9 @@ pub unsafe extern "C" fn obs_rust_scene_add_item(
     let scene = &*(ptr as *const Scene);
 
     let mut scene_item = SceneItem::new(0, (*item).source_id);
-    scene_item.pos = glam::Vec2::new((*item).pos_x, (*item).pos_y);
-    scene_item.scale = glam::Vec2::new((*item).scale_x, (*item).scale_y);
-    scene_item.rotation = (*item).rotation;
-    scene_item.visible = (*item).visible;
-    scene_item.locked = (*item).locked;
+    (*item).apply_to(&mut scene_item);
 
-    scene.add_item(scene_item)
+    scene.add_item(scene_item).unwrap_or(-1)
 }
 
 /// Remove an item from the scene
@@ -142,11 +157,253 @@ pub unsafe extern "C" fn obs_rust_scene_render(
     let scene = &*(ptr as *const Scene);
     scene.render_items(|item| {
         // Pass transform matrix as flat array
-        let matrix = item.draw_transform.to_cols_array();
+        let matrix = item.transform.draw_transform.to_cols_array();
         callback(item.source_id, matrix.as_ptr(), item.blend_mode as u32);
     });
 }
 
+// ============================================================================
+// SCENE HISTORY API
+// ============================================================================
+
+/// Create an undo history recording edits to a scene
+///
+/// A `memory_limit` of 0 uses the default cap.
+///
+/// # Safety
+/// Caller must ensure scene is valid. The history keeps its own handle to
+/// the scene's items and may outlive the scene handle.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_create(
+    scene: *const OBSScene,
+    memory_limit: usize,
+) -> *mut OBSSceneHistory {
+    if scene.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let scene = (*(scene as *const Scene)).clone();
+    let limit = if memory_limit == 0 {
+        DEFAULT_HISTORY_MEMORY
+    } else {
+        memory_limit
+    };
+    let history = Box::new(SceneHistory::with_memory_limit(scene, limit));
+    Box::into_raw(history) as *mut OBSSceneHistory
+}
+
+/// Destroy a scene history
+///
+/// # Safety
+/// Caller must ensure ptr is valid and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_destroy(ptr: *mut OBSSceneHistory) {
+    if !ptr.is_null() {
+        let _ = Box::from_raw(ptr as *mut SceneHistory);
+    }
+}
+
+/// Add an item as an undoable step
+///
+/// # Safety
+/// Caller must ensure ptr and item are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_add_item(
+    ptr: *mut OBSSceneHistory,
+    item: *const CSceneItem,
+) -> i64 {
+    if ptr.is_null() || item.is_null() {
+        return -1;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    let mut scene_item = SceneItem::new(0, (*item).source_id);
+    (*item).apply_to(&mut scene_item);
+    history.add_item(scene_item).unwrap_or(-1)
+}
+
+/// Remove an item as an undoable step
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_remove_item(
+    ptr: *mut OBSSceneHistory,
+    id: i64,
+) -> c_int {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.remove_item(id) as c_int
+}
+
+/// Move an item to a new position as an undoable step
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_move_item(
+    ptr: *mut OBSSceneHistory,
+    id: i64,
+    new_index: usize,
+) -> c_int {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.move_item(id, new_index) as c_int
+}
+
+/// Set the transform and flags of the item `item->id`
+///
+/// With a non-null `merge_key`, consecutive calls with the same key merge
+/// into one undo step until `obs_rust_scene_history_seal`.
+///
+/// # Safety
+/// Caller must ensure ptr and item are valid and merge_key is null or a
+/// valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_set_item(
+    ptr: *mut OBSSceneHistory,
+    item: *const CSceneItem,
+    merge_key: *const c_char,
+) -> c_int {
+    if ptr.is_null() || item.is_null() {
+        return 0;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    let update = &*item;
+    let merge_key = if merge_key.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(merge_key).to_string_lossy().into_owned())
+    };
+    let updated = match merge_key {
+        Some(key) => history.update_item_merged(update.id, &key, |i| update.apply_to(i)),
+        None => history.update_item(update.id, |i| update.apply_to(i)),
+    };
+    updated as c_int
+}
+
+/// Start collecting edits into one undo step
+///
+/// # Safety
+/// Caller must ensure ptr is valid and name is a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_begin_group(
+    ptr: *mut OBSSceneHistory,
+    name: *const c_char,
+) {
+    if ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.begin_group(&CStr::from_ptr(name).to_string_lossy());
+}
+
+/// Finish the step started by `obs_rust_scene_history_begin_group`
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_end_group(ptr: *mut OBSSceneHistory) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.end_group();
+}
+
+/// Stop merging edits into the last step
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_seal(ptr: *mut OBSSceneHistory) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.seal();
+}
+
+/// Undo the last step; returns 1 if a step was undone
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_undo(ptr: *mut OBSSceneHistory) -> c_int {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.undo() as c_int
+}
+
+/// Redo the last undone step; returns 1 if a step was redone
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_redo(ptr: *mut OBSSceneHistory) -> c_int {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.redo() as c_int
+}
+
+/// Number of steps that can be undone
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_undo_count(ptr: *const OBSSceneHistory) -> usize {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &*(ptr as *const SceneHistory);
+    history.undo_count()
+}
+
+/// Number of steps that can be redone
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_redo_count(ptr: *const OBSSceneHistory) -> usize {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let history = &*(ptr as *const SceneHistory);
+    history.redo_count()
+}
+
+/// Drop all recorded steps
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_scene_history_clear(ptr: *mut OBSSceneHistory) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let history = &mut *(ptr as *mut SceneHistory);
+    history.clear();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +438,58 @@ mod tests {
             obs_rust_scene_destroy(scene);
         }
     }
+
+    #[test]
+    fn test_scene_history_ffi() {
+        unsafe {
+            let scene = obs_rust_scene_create(1920, 1080);
+            let history = obs_rust_scene_history_create(scene, 0);
+            assert!(!history.is_null());
+
+            let mut item = CSceneItem {
+                id: 0,
+                source_id: 100,
+                pos_x: 0.0,
+                pos_y: 0.0,
+                scale_x: 1.0,
+                scale_y: 1.0,
+                rotation: 0.0,
+                visible: true,
+                locked: false,
+            };
+            item.id = obs_rust_scene_history_add_item(history, &item);
+            assert!(item.id > 0);
+
+            // A drag merges into one step
+            let key = c"drag";
+            for x in 1..=5 {
+                item.pos_x = x as f32;
+                assert_eq!(
+                    obs_rust_scene_history_set_item(history, &item, key.as_ptr()),
+                    1
+                );
+            }
+            obs_rust_scene_history_seal(history);
+            assert_eq!(obs_rust_scene_history_undo_count(history), 2);
+
+            obs_rust_scene_history_begin_group(history, c"Remove".as_ptr());
+            obs_rust_scene_history_remove_item(history, item.id);
+            obs_rust_scene_history_end_group(history);
+            assert_eq!(obs_rust_scene_item_count(scene), 0);
+
+            assert_eq!(obs_rust_scene_history_undo(history), 1);
+            assert_eq!(obs_rust_scene_item_count(scene), 1);
+            assert_eq!(obs_rust_scene_history_undo(history), 1);
+            assert_eq!(obs_rust_scene_history_undo(history), 1);
+            assert_eq!(obs_rust_scene_item_count(scene), 0);
+            assert_eq!(obs_rust_scene_history_undo(history), 0);
+            assert_eq!(obs_rust_scene_history_redo_count(history), 3);
+
+            obs_rust_scene_history_clear(history);
+            assert_eq!(obs_rust_scene_history_redo(history), 0);
+
+            obs_rust_scene_history_destroy(history);
+            obs_rust_scene_destroy(scene);
+        }
+    }
 }
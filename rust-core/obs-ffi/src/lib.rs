@@ -2,14 +2,17 @@
 //!
 //! Provides C-compatible API for integration with existing OBS codebase.
 
-use std::ffi::CStr;
-use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::os::raw::{c_char, c_int};
 use std::ptr;
 
 // Re-export types from other crates
 use obs_audio_mix::{AudioConfig, AudioFormat, AudioMixer, SpeakerLayout};
-use obs_compositor::{Scene, SceneItem};
-use obs_video::{VideoFormat, VideoFrame, VideoOutput, VideoOutputInfo};
+use obs_video::{
+    build_global_worker_pool, rescale, set_thread_config, thread_reports, tune_current_thread,
+    ColorOverride, ColorRange, ColorSpace, ContentLightLevel, FrameRate, FrameSideData,
+    LatencySnapshot, MasteringDisplay, Rational, Rounding, ThreadConfig, ThreadPriority,
+    ThreadReport, ThreadRole, VideoFormat, VideoFrame, VideoOutput, VideoOutputInfo,
+};
 
 mod compositor_ffi;
 pub use compositor_ffi::*;
@@ -29,7 +32,21 @@ pub struct OBSScene {
     _private: [u8; 0],
 }
 
+/// Opaque handle to FrameSideData (C-compatible)
+pub struct OBSFrameSideData {
+    _private: [u8; 0],
+}
+
+/// Version of the C ABI, bumped whenever a `#[repr(C)]` struct changes layout
+///
+/// - 2: `CVideoFrame` gained the trailing `side_data` pointer (the struct
+///   grew by one pointer). Callers built against version 1 must be rebuilt;
+///   a zeroed `side_data` means no side data.
+pub const OBS_RUST_ABI_VERSION: u32 = 2;
+
 /// C-compatible video frame structure
+///
+/// Layout changed in ABI version 2, see `OBS_RUST_ABI_VERSION`.
 #[repr(C)]
 pub struct CVideoFrame {
     pub data: [*mut u8; 4],
@@ -38,6 +55,8 @@ pub struct CVideoFrame {
     pub height: u32,
     pub format: u32,
     pub timestamp: u64,
+    /// Optional side data; unlock_frame takes ownership (ABI version 2)
+    pub side_data: *mut OBSFrameSideData,
 }
 
 /// C-compatible video output info
@@ -48,6 +67,8 @@ pub struct CVideoOutputInfo {
     pub fps_num: u32,
     pub fps_den: u32,
     pub format: u32,
+    pub colorspace: u32,
+    pub range: u32,
 }
 
 /// C-compatible audio config
@@ -90,6 +111,36 @@ pub unsafe extern "C" fn obs_rust_video_output_destroy(ptr: *mut OBSVideoOutput)
     }
 }
 
+/// Change the output's resolution, frame rate or color settings
+///
+/// Encoder connections are kept. Must not be called concurrently with other
+/// calls on the same output. Returns 0 if the configuration is invalid.
+///
+/// # Safety
+/// Caller must ensure ptr and info are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_reconfigure(
+    ptr: *mut OBSVideoOutput,
+    info: *const CVideoOutputInfo,
+) -> c_int {
+    if ptr.is_null() || info.is_null() {
+        return 0;
+    }
+
+    let output = &mut *(ptr as *mut VideoOutput);
+    let info = &*info;
+    let reconfigured = output.reconfigure(VideoOutputInfo {
+        width: info.width,
+        height: info.height,
+        fps_num: info.fps_num,
+        fps_den: info.fps_den,
+        format: info.format,
+        colorspace: info.colorspace,
+        range: info.range,
+    });
+    reconfigured as c_int
+}
+
 /// Lock a frame for rendering
 ///
 /// # Safety
@@ -112,6 +163,7 @@ pub unsafe extern "C" fn obs_rust_video_output_lock_frame(
         (*frame_out).height = frame.height;
         (*frame_out).format = frame.format as u32;
         (*frame_out).timestamp = frame.timestamp;
+        (*frame_out).side_data = ptr::null_mut();
         1
     } else {
         0
@@ -121,27 +173,40 @@ pub unsafe extern "C" fn obs_rust_video_output_lock_frame(
 /// Unlock and submit frame for encoding
 ///
 /// # Safety
-/// Caller must ensure ptr and frame are valid.
+/// Caller must ensure ptr and frame are valid. A non-null `side_data` must
+/// come from `obs_rust_side_data_create` and is freed by this call, even
+/// when the frame is not submitted.
 #[no_mangle]
 pub unsafe extern "C" fn obs_rust_video_output_unlock_frame(
     ptr: *mut OBSVideoOutput,
     frame: *const CVideoFrame,
     timestamp: u64,
 ) -> c_int {
-    if ptr.is_null() || frame.is_null() {
+    if frame.is_null() {
+        return 0;
+    }
+
+    // Take ownership before any other early return so it is never leaked
+    let side_data = (*frame).side_data;
+    let side_data = (!side_data.is_null()).then(|| *Box::from_raw(side_data as *mut FrameSideData));
+
+    if ptr.is_null() {
         return 0;
     }
 
     let output = &mut *(ptr as *mut VideoOutput);
 
-    let rust_frame = VideoFrame {
-        data: (*frame).data,
-        linesize: (*frame).linesize,
-        width: (*frame).width,
-        height: (*frame).height,
-        format: std::mem::transmute((*frame).format),
-        timestamp: (*frame).timestamp,
-    };
+    let mut rust_frame = VideoFrame::new(
+        (*frame).width,
+        (*frame).height,
+        std::mem::transmute::<u32, VideoFormat>((*frame).format),
+    );
+    rust_frame.data = (*frame).data;
+    rust_frame.linesize = (*frame).linesize;
+    rust_frame.timestamp = (*frame).timestamp;
+    if let Some(side_data) = side_data {
+        rust_frame.set_side_data(side_data);
+    }
 
     if output.unlock_frame(rust_frame, timestamp) {
         1
@@ -180,6 +245,332 @@ pub unsafe extern "C" fn obs_rust_video_output_get_skipped_frames(
     output.stats().skipped_frames
 }
 
+/// Get lagged (duplicated) frames count
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_get_lagged_frames(
+    ptr: *const OBSVideoOutput,
+) -> u64 {
+    if ptr.is_null() {
+        return 0;
+    }
+
+    let output = &*(ptr as *const VideoOutput);
+    output.stats().lagged_frames
+}
+
+/// Latency window summary (all values in nanoseconds)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CLatencyStats {
+    pub recorded: u64,
+    pub window: u32,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    pub mean_ns: u64,
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub p99_ns: u64,
+}
+
+impl From<LatencySnapshot> for CLatencyStats {
+    fn from(snapshot: LatencySnapshot) -> Self {
+        Self {
+            recorded: snapshot.recorded,
+            window: snapshot.window as u32,
+            min_ns: snapshot.min_ns,
+            max_ns: snapshot.max_ns,
+            mean_ns: snapshot.mean_ns,
+            p50_ns: snapshot.p50_ns,
+            p95_ns: snapshot.p95_ns,
+            p99_ns: snapshot.p99_ns,
+        }
+    }
+}
+
+/// Enable or disable frame timing telemetry
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_set_timing_enabled(
+    ptr: *mut OBSVideoOutput,
+    enabled: c_int,
+) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let output = &*(ptr as *const VideoOutput);
+    output.set_timing_enabled(enabled != 0);
+}
+
+/// Clear recorded timing samples
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_reset_timing(ptr: *mut OBSVideoOutput) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let output = &*(ptr as *const VideoOutput);
+    output.reset_timing();
+}
+
+/// Get submit latency and frame jitter; returns the number of encoders
+/// with delivery latency available, or -1 on invalid arguments
+///
+/// # Safety
+/// Caller must ensure ptr is valid; the out pointers may be null.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_get_timing(
+    ptr: *const OBSVideoOutput,
+    submit_latency: *mut CLatencyStats,
+    frame_jitter: *mut CLatencyStats,
+) -> c_int {
+    if ptr.is_null() {
+        return -1;
+    }
+
+    let output = &*(ptr as *const VideoOutput);
+    let timing = output.timing();
+    if !submit_latency.is_null() {
+        *submit_latency = timing.submit_latency.into();
+    }
+    if !frame_jitter.is_null() {
+        *frame_jitter = timing.frame_jitter.into();
+    }
+    timing.encoders.len() as c_int
+}
+
+/// Get delivery latency of the encoder at `index`
+///
+/// # Safety
+/// Caller must ensure ptr and out are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_video_output_get_encoder_latency(
+    ptr: *const OBSVideoOutput,
+    index: u32,
+    out: *mut CLatencyStats,
+) -> c_int {
+    if ptr.is_null() || out.is_null() {
+        return 0;
+    }
+
+    let output = &*(ptr as *const VideoOutput);
+    match output.timing().encoders.get(index as usize) {
+        Some(encoder) => {
+            *out = encoder.delivery_latency.into();
+            1
+        }
+        None => 0,
+    }
+}
+
+// ============================================================================
+// FRAME SIDE DATA API
+// ============================================================================
+
+/// Create empty side data to attach to a frame through `CVideoFrame`
+#[no_mangle]
+pub extern "C" fn obs_rust_side_data_create() -> *mut OBSFrameSideData {
+    Box::into_raw(Box::new(FrameSideData::new())) as *mut OBSFrameSideData
+}
+
+/// Destroy side data that was not handed to unlock_frame
+///
+/// # Safety
+/// Caller must ensure ptr is valid and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_destroy(ptr: *mut OBSFrameSideData) {
+    if !ptr.is_null() {
+        let _ = Box::from_raw(ptr as *mut FrameSideData);
+    }
+}
+
+/// Set mastering display metadata
+///
+/// # Safety
+/// Caller must ensure ptr and value are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_set_mastering_display(
+    ptr: *mut OBSFrameSideData,
+    value: *const MasteringDisplay,
+) {
+    if ptr.is_null() || value.is_null() {
+        return;
+    }
+
+    let side_data = &mut *(ptr as *mut FrameSideData);
+    side_data.mastering_display = Some(*value);
+}
+
+/// Get mastering display metadata; returns 0 if not set
+///
+/// # Safety
+/// Caller must ensure ptr and out are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_get_mastering_display(
+    ptr: *const OBSFrameSideData,
+    out: *mut MasteringDisplay,
+) -> c_int {
+    if ptr.is_null() || out.is_null() {
+        return 0;
+    }
+
+    let side_data = &*(ptr as *const FrameSideData);
+    match side_data.mastering_display {
+        Some(value) => {
+            *out = value;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Set content light level metadata
+///
+/// # Safety
+/// Caller must ensure ptr and value are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_set_content_light(
+    ptr: *mut OBSFrameSideData,
+    value: *const ContentLightLevel,
+) {
+    if ptr.is_null() || value.is_null() {
+        return;
+    }
+
+    let side_data = &mut *(ptr as *mut FrameSideData);
+    side_data.content_light = Some(*value);
+}
+
+/// Get content light level metadata; returns 0 if not set
+///
+/// # Safety
+/// Caller must ensure ptr and out are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_get_content_light(
+    ptr: *const OBSFrameSideData,
+    out: *mut ContentLightLevel,
+) -> c_int {
+    if ptr.is_null() || out.is_null() {
+        return 0;
+    }
+
+    let side_data = &*(ptr as *const FrameSideData);
+    match side_data.content_light {
+        Some(value) => {
+            *out = value;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Override the color space and range for this frame
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_set_color(
+    ptr: *mut OBSFrameSideData,
+    colorspace: u32,
+    range: u32,
+) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let side_data = &mut *(ptr as *mut FrameSideData);
+    side_data.color = Some(ColorOverride {
+        colorspace: ColorSpace::from_u32(colorspace),
+        range: ColorRange::from_u32(range),
+    });
+}
+
+/// Append a CEA-608 byte pair
+///
+/// # Safety
+/// Caller must ensure ptr is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_add_caption(
+    ptr: *mut OBSFrameSideData,
+    byte1: u8,
+    byte2: u8,
+) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let side_data = &mut *(ptr as *mut FrameSideData);
+    side_data.captions.push([byte1, byte2]);
+}
+
+/// Set a keyed user data payload (copied)
+///
+/// # Safety
+/// Caller must ensure ptr and key are valid and data points to len bytes.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_set_user_data(
+    ptr: *mut OBSFrameSideData,
+    key: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    if ptr.is_null() || key.is_null() || (data.is_null() && len > 0) {
+        return 0;
+    }
+
+    let key = match std::ffi::CStr::from_ptr(key).to_str() {
+        Ok(key) => key,
+        Err(_) => return 0,
+    };
+    let data = if len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    };
+
+    let side_data = &mut *(ptr as *mut FrameSideData);
+    side_data.set_user_data(key, data);
+    1
+}
+
+/// Get a user data payload; the pointer stays valid until the side data is
+/// modified or destroyed
+///
+/// # Safety
+/// Caller must ensure ptr, key and len_out are valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_side_data_get_user_data(
+    ptr: *const OBSFrameSideData,
+    key: *const c_char,
+    len_out: *mut usize,
+) -> *const u8 {
+    if ptr.is_null() || key.is_null() || len_out.is_null() {
+        return ptr::null();
+    }
+
+    let key = match std::ffi::CStr::from_ptr(key).to_str() {
+        Ok(key) => key,
+        Err(_) => return ptr::null(),
+    };
+
+    let side_data = &*(ptr as *const FrameSideData);
+    match side_data.user_data(key) {
+        Some(data) => {
+            *len_out = data.len();
+            data.as_ptr()
+        }
+        None => ptr::null(),
+    }
+}
+
 // ============================================================================
 // AUDIO MIXER API
 // ============================================================================
@@ -200,8 +591,8 @@ pub unsafe extern "C" fn obs_rust_audio_mixer_create(
         sample_rate: (*config).sample_rate,
         channels: (*config).channels as usize,
         frames: (*config).frames as usize,
-        format: std::mem::transmute((*config).format),
-        layout: std::mem::transmute((*config).layout),
+        format: std::mem::transmute::<u32, AudioFormat>((*config).format),
+        layout: std::mem::transmute::<u32, SpeakerLayout>((*config).layout),
     };
 
     let mixer = Box::new(AudioMixer::new(rust_config));
@@ -249,6 +640,137 @@ pub unsafe extern "C" fn obs_rust_audio_mixer_get_frames_processed(
     mixer.stats().frames_processed
 }
 
+// ============================================================================
+// THREAD TUNING API
+// ============================================================================
+
+/// C-compatible report of a tuned thread's effective placement
+#[repr(C)]
+pub struct CThreadReport {
+    /// NUL-terminated thread name (truncated)
+    pub name: [c_char; 32],
+    pub role: u32,
+    /// Core requested by the profile, or -1
+    pub requested_core: i32,
+    /// Bit N set if the thread may run on core N (cores 0-63)
+    pub affinity_mask: u64,
+    /// 0 = default, 1 = nice, 2 = SCHED_FIFO
+    pub priority_kind: u32,
+    /// Nice value or SCHED_FIFO priority
+    pub priority: i32,
+}
+
+impl From<&ThreadReport> for CThreadReport {
+    fn from(report: &ThreadReport) -> Self {
+        let mut name = [0 as c_char; 32];
+        for (dst, src) in name.iter_mut().zip(report.name.bytes().take(31)) {
+            *dst = src as c_char;
+        }
+
+        let (priority_kind, priority) = match report.priority {
+            ThreadPriority::Default => (0, 0),
+            ThreadPriority::Nice(nice) => (1, nice),
+            ThreadPriority::Realtime(prio) => (2, prio),
+        };
+
+        CThreadReport {
+            name,
+            role: report.role as u32,
+            requested_core: report.requested_core.map_or(-1, |core| core as i32),
+            affinity_mask: report
+                .cores
+                .iter()
+                .filter(|&&core| core < 64)
+                .fold(0, |mask, &core| mask | (1u64 << core)),
+            priority_kind,
+            priority,
+        }
+    }
+}
+
+/// Load thread pinning and priority settings from a build profile
+///
+/// Also builds the global worker pool on the cores the profile leaves free.
+/// Call before creating outputs and mixers. Returns 0 if the profile can't
+/// be read or is not valid TOML with correctly typed thread keys.
+///
+/// # Safety
+/// Caller must ensure path is a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_thread_load_profile(path: *const c_char) -> c_int {
+    if path.is_null() {
+        return 0;
+    }
+
+    let path = match std::ffi::CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return 0,
+    };
+    let config = match ThreadConfig::from_profile_file(path) {
+        Ok(config) => config,
+        Err(err) => {
+            log::warn!("Thread profile {}: {}", path, err);
+            return 0;
+        }
+    };
+
+    if !build_global_worker_pool(&config) {
+        log::warn!("Global worker pool already running, worker threads not pinned");
+    }
+    set_thread_config(config);
+    1
+}
+
+/// Pin and prioritize the calling thread (e.g. the graphics thread) for a role
+///
+/// Returns 0 for an unknown role.
+///
+/// # Safety
+/// Safe to call from any thread.
+#[no_mangle]
+pub extern "C" fn obs_rust_thread_tune_current(role: u32) -> c_int {
+    match ThreadRole::from_u32(role) {
+        Some(role) => {
+            tune_current_thread(role);
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Number of tuned threads that can be reported
+///
+/// # Safety
+/// Safe to call from any thread.
+#[no_mangle]
+pub extern "C" fn obs_rust_thread_report_count() -> usize {
+    thread_reports().len()
+}
+
+/// Get the report for one tuned thread
+///
+/// Returns 0 if index is out of range.
+///
+/// # Safety
+/// Caller must ensure out is valid.
+#[no_mangle]
+pub unsafe extern "C" fn obs_rust_thread_report_get(
+    index: usize,
+    out: *mut CThreadReport,
+) -> c_int {
+    if out.is_null() {
+        return 0;
+    }
+
+    match thread_reports().get(index) {
+        Some(report) => {
+            *out = CThreadReport::from(report);
+            1
+        }
+        None => 0,
+    }
+}
+
 // ============================================================================
 // FORMAT CONVERSION API
 // ============================================================================
@@ -292,6 +814,41 @@ pub unsafe extern "C" fn obs_rust_convert_uyvy_to_nv12(
     1
 }
 
+// ============================================================================
+// TIMEBASE API
+// ============================================================================
+
+/// Convert a timestamp between timebases (e.g. ns to 90 kHz for muxers)
+///
+/// rounding: 0 = down, 1 = up, 2 = nearest, 3 = toward zero. Returns 0 for
+/// an invalid timebase or rounding mode.
+///
+/// # Safety
+/// Safe to call, pure computation.
+#[no_mangle]
+pub extern "C" fn obs_rust_rescale(value: i64, from: Rational, to: Rational, rounding: u32) -> i64 {
+    let rounding = match rounding {
+        0 => Rounding::Down,
+        1 => Rounding::Up,
+        2 => Rounding::Nearest,
+        3 => Rounding::Zero,
+        _ => return 0,
+    };
+    if !from.is_valid() || !to.is_valid() {
+        return 0;
+    }
+    rescale(value, from, to, rounding)
+}
+
+/// Exact timestamp offset in ns of frame `index` at fps_num/fps_den
+///
+/// # Safety
+/// Safe to call, pure computation.
+#[no_mangle]
+pub extern "C" fn obs_rust_frame_to_ns(fps_num: u32, fps_den: u32, index: u64) -> u64 {
+    FrameRate::new(fps_num, fps_den).frame_to_ns(index)
+}
+
 // ============================================================================
 // UTILITY FUNCTIONS
 // ============================================================================
@@ -302,7 +859,16 @@ pub unsafe extern "C" fn obs_rust_convert_uyvy_to_nv12(
 /// Returns a static string, safe to call.
 #[no_mangle]
 pub extern "C" fn obs_rust_version() -> *const c_char {
-    "0.1.0\0".as_ptr() as *const c_char
+    c"0.1.0".as_ptr()
+}
+
+/// Get the C ABI version (`OBS_RUST_ABI_VERSION`)
+///
+/// Callers should compare this against the version of the header they
+/// were built with before passing any `#[repr(C)]` structs.
+#[no_mangle]
+pub extern "C" fn obs_rust_abi_version() -> u32 {
+    OBS_RUST_ABI_VERSION
 }
 
 /// Check if AVX2 is available
@@ -338,6 +904,7 @@ pub extern "C" fn obs_rust_has_avx() -> c_int {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ffi::CStr;
 
     #[test]
     fn test_video_output_ffi() {
@@ -348,6 +915,113 @@ mod tests {
             let total = obs_rust_video_output_get_total_frames(output);
             assert_eq!(total, 0);
 
+            let info = CVideoOutputInfo {
+                width: 1280,
+                height: 720,
+                fps_num: 30,
+                fps_den: 1,
+                format: VideoFormat::NV12 as u32,
+                colorspace: 0,
+                range: 0,
+            };
+            assert_eq!(obs_rust_video_output_reconfigure(output, &info), 1);
+
+            obs_rust_video_output_destroy(output);
+        }
+    }
+
+    #[test]
+    fn test_video_timing_ffi() {
+        unsafe {
+            let output = obs_rust_video_output_create(640, 360, 100, 1);
+            obs_rust_video_output_set_timing_enabled(output, 1);
+
+            let mut frame = std::mem::zeroed::<CVideoFrame>();
+            assert_eq!(obs_rust_video_output_lock_frame(output, &mut frame), 1);
+            assert_eq!(obs_rust_video_output_unlock_frame(output, &frame, 0), 1);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+
+            let mut submit = CLatencyStats::default();
+            let mut jitter = CLatencyStats::default();
+            assert_eq!(
+                obs_rust_video_output_get_timing(output, &mut submit, &mut jitter),
+                0
+            );
+            assert_eq!(submit.recorded, 1);
+            assert!(jitter.recorded > 0);
+            assert!(jitter.p50_ns <= jitter.p99_ns);
+
+            let mut encoder = CLatencyStats::default();
+            assert_eq!(
+                obs_rust_video_output_get_encoder_latency(output, 0, &mut encoder),
+                0
+            );
+
+            obs_rust_video_output_reset_timing(output);
+            obs_rust_video_output_get_timing(output, &mut submit, ptr::null_mut());
+            assert_eq!(submit.recorded, 0);
+
+            obs_rust_video_output_destroy(output);
+        }
+    }
+
+    #[test]
+    fn test_side_data_ffi() {
+        unsafe {
+            let side_data = obs_rust_side_data_create();
+            let cll = ContentLightLevel {
+                max_cll: 1000,
+                max_fall: 400,
+            };
+            obs_rust_side_data_set_content_light(side_data, &cll);
+            obs_rust_side_data_add_caption(side_data, 0x94, 0x2C);
+            let key = c"timecode";
+            assert_eq!(
+                obs_rust_side_data_set_user_data(side_data, key.as_ptr(), [1u8, 2, 3].as_ptr(), 3),
+                1
+            );
+
+            let mut out = ContentLightLevel::default();
+            assert_eq!(obs_rust_side_data_get_content_light(side_data, &mut out), 1);
+            assert_eq!(out, cll);
+            let mut display = MasteringDisplay::default();
+            assert_eq!(
+                obs_rust_side_data_get_mastering_display(side_data, &mut display),
+                0
+            );
+            let mut len = 0;
+            let data = obs_rust_side_data_get_user_data(side_data, key.as_ptr(), &mut len);
+            assert_eq!(std::slice::from_raw_parts(data, len), &[1, 2, 3]);
+
+            // unlock_frame takes ownership of the side data
+            let output = obs_rust_video_output_create(640, 360, 100, 1);
+            let output_ref = &*(output as *const VideoOutput);
+            let encoder = output_ref.connect_encoder(1);
+
+            let mut frame = std::mem::zeroed::<CVideoFrame>();
+            assert_eq!(obs_rust_video_output_lock_frame(output, &mut frame), 1);
+            assert!(frame.side_data.is_null());
+            frame.side_data = side_data;
+            assert_eq!(obs_rust_video_output_unlock_frame(output, &frame, 0), 1);
+
+            let received = encoder
+                .recv_timeout(std::time::Duration::from_millis(500))
+                .ok()
+                .and_then(obs_video::EncoderEvent::into_frame)
+                .unwrap();
+            let received = received.side_data.unwrap();
+            assert_eq!(received.content_light, Some(cll));
+            assert_eq!(received.captions, vec![[0x94, 0x2C]]);
+
+            // Side data is still consumed when the output handle is null
+            let mut frame = std::mem::zeroed::<CVideoFrame>();
+            frame.side_data = obs_rust_side_data_create();
+            assert_eq!(
+                obs_rust_video_output_unlock_frame(ptr::null_mut(), &frame, 0),
+                0
+            );
+
+            drop(encoder);
             obs_rust_video_output_destroy(output);
         }
     }
@@ -375,6 +1049,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_thread_report_ffi() {
+        std::thread::Builder::new()
+            .name("ffi-tune-test".to_string())
+            .spawn(|| assert_eq!(obs_rust_thread_tune_current(ThreadRole::Encoder as u32), 1))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(obs_rust_thread_tune_current(99), 0);
+
+        let found = (0..obs_rust_thread_report_count()).any(|index| unsafe {
+            let mut report: CThreadReport = std::mem::zeroed();
+            assert_eq!(obs_rust_thread_report_get(index, &mut report), 1);
+            let name = CStr::from_ptr(report.name.as_ptr()).to_str().unwrap();
+            name == "ffi-tune-test"
+                && report.role == ThreadRole::Encoder as u32
+                && report.requested_core == -1
+        });
+        assert!(found);
+
+        unsafe {
+            let mut report: CThreadReport = std::mem::zeroed();
+            assert_eq!(obs_rust_thread_report_get(usize::MAX, &mut report), 0);
+            assert_eq!(
+                obs_rust_thread_load_profile(c"/nonexistent.toml".as_ptr()),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn test_rescale_ffi() {
+        let ns = Rational::new(1, 1_000_000_000);
+        let mpegts = Rational::new(1, 90_000);
+        assert_eq!(obs_rust_rescale(1_000_000_000, ns, mpegts, 0), 90_000);
+        assert_eq!(obs_rust_rescale(1, mpegts, ns, 1), 11_112);
+        assert_eq!(obs_rust_rescale(1, mpegts, Rational::new(1, 0), 0), 0);
+        assert_eq!(
+            obs_rust_frame_to_ns(30_000, 1001, 30_000),
+            1_001_000_000_000
+        );
+    }
+
     #[test]
     fn test_cpu_features() {
         let has_avx = obs_rust_has_avx();
@@ -391,6 +1108,7 @@ mod tests {
 
             let version_str = CStr::from_ptr(version).to_str().unwrap();
             assert_eq!(version_str, "0.1.0");
+            assert_eq!(obs_rust_abi_version(), 2);
         }
     }
 }
@@ -0,0 +1,50 @@
+#!/bin/sh
+# Run video_output_bench against the video thread as it was before it was
+# woken from unlock_frame (sleeping to each frame deadline), then against
+# the current tree, and let criterion report the change.
+#
+# Usage: obs-video/benches/compare_wakeup.sh [baseline-commit]
+#
+# The baseline defaults to the parent of the commit that introduced the
+# wakeup. It is checked out into a temporary worktree with this tree's
+# bench file; both runs share one target directory so criterion can
+# compare them.
+set -eu
+
+root=$(git rev-parse --show-toplevel)
+crate_dir="$root/rust-core"
+wakeup=$(git -C "$root" log -1 --format=%H \
+	--grep='Wake the video thread from unlock_frame instead of sleeping')
+baseline=${1:-$wakeup^}
+
+worktree=$(mktemp -d)
+cleanup() {
+	git -C "$root" worktree remove --force "$worktree" >/dev/null 2>&1 || true
+	rm -rf "$worktree"
+}
+trap cleanup EXIT
+
+git -C "$root" worktree add --detach "$worktree" "$baseline" >/dev/null
+mkdir -p "$worktree/rust-core/obs-video/benches"
+cp "$crate_dir/obs-video/benches/video_output_bench.rs" \
+	"$worktree/rust-core/obs-video/benches/"
+if ! grep -q 'name = "video_output_bench"' "$worktree/rust-core/obs-video/Cargo.toml"; then
+	cat >>"$worktree/rust-core/obs-video/Cargo.toml" <<'EOF'
+
+[[bench]]
+name = "video_output_bench"
+harness = false
+EOF
+fi
+if ! grep -q '^libc' "$worktree/rust-core/obs-video/Cargo.toml"; then
+	sed -i 's/^\[dev-dependencies\]$/[dev-dependencies]\nlibc = { workspace = true }/' \
+		"$worktree/rust-core/obs-video/Cargo.toml"
+fi
+
+export CARGO_TARGET_DIR="$crate_dir/target"
+echo "== baseline: $(git -C "$root" log -1 --format='%h %s' "$baseline")"
+(cd "$worktree/rust-core" &&
+	cargo bench -p obs-video --bench video_output_bench -- --save-baseline sleeping)
+echo "== current tree"
+(cd "$crate_dir" &&
+	cargo bench -p obs-video --bench video_output_bench -- --baseline sleeping)
@@ -0,0 +1,54 @@
+//! Benchmarks for `VideoScaler`
+//!
+//! Converts a 1080p NV12 output frame the way an encoder connection asking
+//! for a smaller size does. Each conversion runs once per output frame on
+//! the video thread, so it must finish well inside the 16.7ms budget of a
+//! 60fps output. On a single-core 2GHz Linux VM (SSE2 only):
+//!
+//! | bench                          | per-pixel bilinear | separable |
+//! |--------------------------------|--------------------|-----------|
+//! | scale_1080p_nv12/720p_nv12     | 190 ms             | 8.6 ms    |
+//! | scale_1080p_nv12/360p_nv12     | 21 ms              | 3.4 ms    |
+//! | scale_1080p_nv12/720p_i420_full | 194 ms            | 8.7 ms    |
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use obs_video::{ColorRange, ColorSpace, FramePool, VideoConversion, VideoFormat, VideoScaler};
+
+fn shape(width: u32, height: u32, format: VideoFormat) -> VideoConversion {
+    VideoConversion {
+        width,
+        height,
+        format,
+        colorspace: ColorSpace::CS709,
+        range: ColorRange::Partial,
+    }
+}
+
+fn bench_scale(c: &mut Criterion) {
+    let source = shape(1920, 1080, VideoFormat::NV12);
+    let src_pool = FramePool::new(source.format, source.width, source.height, 1);
+    let src = src_pool.acquire().unwrap();
+
+    let mut group = c.benchmark_group("scale_1080p_nv12");
+    for (name, target) in [
+        ("720p_nv12", shape(1280, 720, VideoFormat::NV12)),
+        ("360p_nv12", shape(640, 360, VideoFormat::NV12)),
+        ("720p_i420_full", {
+            let mut target = shape(1280, 720, VideoFormat::I420);
+            target.range = ColorRange::Full;
+            target
+        }),
+    ] {
+        let scaler = VideoScaler::new(source, target).unwrap();
+        let dst_pool = FramePool::new(target.format, target.width, target.height, 1);
+        let mut dst = dst_pool.acquire().unwrap();
+        group.bench_function(name, |b| {
+            // SAFETY: both frames come from pools of the scaler's shapes
+            b.iter(|| unsafe { scaler.scale(&src, &mut dst) });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scale);
+criterion_main!(benches);
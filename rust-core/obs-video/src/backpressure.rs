@@ -0,0 +1,279 @@
+//! Backpressure policies for encoder connections
+//!
+//! Decides what the video thread or an audio mix does when an encoder's
+//! channel is full. A recording encoder must never lose frames, while a
+//! stream encoder should drop rather than stall the output clock.
+
+use crossbeam::channel::{Receiver, SendTimeoutError, Sender, TrySendError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// What to do when an encoder's channel is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Drop the frame being delivered (never stalls)
+    #[default]
+    DropNewest,
+    /// Evict the oldest queued frame to make room (ring replacement)
+    DropOldest,
+    /// Wait up to the given time for space, then drop the frame
+    ///
+    /// The wait happens on the delivering thread with the encoder list
+    /// locked, so every other encoder and the output clock stall with it.
+    /// Keep the timeout well below the frame interval, and use this only
+    /// for encoders that reliably keep up on average.
+    Block(Duration),
+    /// Drop the frame, and disconnect the encoder after this many
+    /// consecutive drops
+    DisconnectAfter(u32),
+}
+
+/// Outcome of delivering one frame to an encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Delivered,
+    Dropped,
+    Disconnected,
+}
+
+/// Per-connection delivery counters, shared with the encoder's handle
+#[derive(Debug, Default)]
+pub struct DeliveryCounters {
+    pub delivered: AtomicU64,
+    pub dropped: AtomicU64,
+    pub replaced: AtomicU64,
+    pub blocked: AtomicU64,
+    pub consecutive_drops: AtomicU64,
+}
+
+impl DeliveryCounters {
+    fn record_delivered(&self) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_drops.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns the number of consecutive drops including this one
+    fn record_dropped(&self) -> u64 {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_drops.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Evict the oldest queued item that is not `pinned`, leaving the others
+/// queued in order
+///
+/// The caller must be the channel's only sender, so everything taken out
+/// fits back in. Returns false if nothing could be evicted.
+pub fn evict_oldest<T>(tx: &Sender<T>, rx: &Receiver<T>, pinned: impl Fn(&T) -> bool) -> bool {
+    let queued: Vec<T> = rx.try_iter().collect();
+    let victim = queued.iter().position(|item| !pinned(item));
+    for (index, item) in queued.into_iter().enumerate() {
+        if Some(index) != victim {
+            tx.try_send(item).ok();
+        }
+    }
+    victim.is_some()
+}
+
+/// Deliver `item` to an encoder channel according to `policy`
+///
+/// `rx` must be a clone of the encoder's receiver when the policy is
+/// `DropOldest`, so the oldest queued item can be evicted. Queued items
+/// for which `pinned` is true, such as control events, are never evicted.
+pub fn deliver<T>(
+    tx: &Sender<T>,
+    rx: Option<&Receiver<T>>,
+    pinned: impl Fn(&T) -> bool,
+    policy: BackpressurePolicy,
+    counters: &DeliveryCounters,
+    item: T,
+) -> Delivery {
+    let item = match tx.try_send(item) {
+        Ok(()) => {
+            counters.record_delivered();
+            return Delivery::Delivered;
+        }
+        Err(TrySendError::Disconnected(_)) => return Delivery::Disconnected,
+        Err(TrySendError::Full(item)) => item,
+    };
+
+    match policy {
+        BackpressurePolicy::DropNewest => {
+            counters.record_dropped();
+            Delivery::Dropped
+        }
+        BackpressurePolicy::DropOldest => {
+            let rx = match rx {
+                Some(rx) => rx,
+                None => {
+                    counters.record_dropped();
+                    return Delivery::Dropped;
+                }
+            };
+
+            let mut item = item;
+            loop {
+                let evicted = evict_oldest(tx, rx, &pinned);
+                if evicted {
+                    counters.replaced.fetch_add(1, Ordering::Relaxed);
+                }
+
+                match tx.try_send(item) {
+                    Ok(()) => {
+                        counters.record_delivered();
+                        return Delivery::Delivered;
+                    }
+                    Err(TrySendError::Disconnected(_)) => return Delivery::Disconnected,
+                    Err(TrySendError::Full(back)) if evicted => item = back,
+                    Err(TrySendError::Full(_)) => {
+                        counters.record_dropped();
+                        return Delivery::Dropped;
+                    }
+                }
+            }
+        }
+        BackpressurePolicy::Block(timeout) => {
+            counters.blocked.fetch_add(1, Ordering::Relaxed);
+
+            match tx.send_timeout(item, timeout) {
+                Ok(()) => {
+                    counters.record_delivered();
+                    Delivery::Delivered
+                }
+                Err(SendTimeoutError::Timeout(_)) => {
+                    counters.record_dropped();
+                    Delivery::Dropped
+                }
+                Err(SendTimeoutError::Disconnected(_)) => Delivery::Disconnected,
+            }
+        }
+        BackpressurePolicy::DisconnectAfter(max_drops) => {
+            if counters.record_dropped() >= max_drops as u64 {
+                Delivery::Disconnected
+            } else {
+                Delivery::Dropped
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel;
+
+    fn unpinned(_: &i32) -> bool {
+        false
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let (tx, rx) = channel::bounded(2);
+        let counters = DeliveryCounters::default();
+
+        for i in 0..4 {
+            deliver(
+                &tx,
+                None,
+                unpinned,
+                BackpressurePolicy::DropNewest,
+                &counters,
+                i,
+            );
+        }
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(counters.delivered.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let (tx, rx) = channel::bounded(2);
+        let counters = DeliveryCounters::default();
+
+        for i in 0..4 {
+            let result = deliver(
+                &tx,
+                Some(&rx),
+                unpinned,
+                BackpressurePolicy::DropOldest,
+                &counters,
+                i,
+            );
+            assert_eq!(result, Delivery::Delivered);
+        }
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(counters.replaced.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.dropped.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_drop_oldest_keeps_pinned() {
+        let (tx, rx) = channel::bounded(3);
+        let counters = DeliveryCounters::default();
+        let pinned = |item: &i32| *item < 0;
+        let policy = BackpressurePolicy::DropOldest;
+
+        for item in [-1, 1, -2, 2, 3, 4] {
+            deliver(&tx, Some(&rx), pinned, policy, &counters, item);
+        }
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![-1, -2, 4]);
+
+        // Nothing left to evict: the new item is dropped instead
+        for item in [-1, -2, -3, 5] {
+            deliver(&tx, Some(&rx), pinned, policy, &counters, item);
+        }
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![-1, -2, -3]);
+        assert_eq!(counters.dropped.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_block_times_out() {
+        let (tx, _rx) = channel::bounded(1);
+        let counters = DeliveryCounters::default();
+        let policy = BackpressurePolicy::Block(Duration::from_millis(5));
+
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 0),
+            Delivery::Delivered
+        );
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 1),
+            Delivery::Dropped
+        );
+        assert_eq!(counters.blocked.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_disconnect_after_consecutive_drops() {
+        let (tx, rx) = channel::bounded(1);
+        let counters = DeliveryCounters::default();
+        let policy = BackpressurePolicy::DisconnectAfter(2);
+
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 0),
+            Delivery::Delivered
+        );
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 1),
+            Delivery::Dropped
+        );
+
+        // A successful delivery resets the run of drops
+        rx.try_recv().unwrap();
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 2),
+            Delivery::Delivered
+        );
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 3),
+            Delivery::Dropped
+        );
+        assert_eq!(
+            deliver(&tx, None, unpinned, policy, &counters, 4),
+            Delivery::Disconnected
+        );
+    }
+}
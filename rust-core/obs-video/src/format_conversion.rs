@@ -14,6 +14,7 @@ use std::arch::x86_64::*;
 /// Requires AVX2 CPU support. Input/output buffers must be properly aligned and sized.
 #[target_feature(enable = "avx2")]
 #[cfg(target_arch = "x86_64")]
+#[allow(clippy::too_many_arguments)]
 pub unsafe fn compress_uyvy_to_nv12_avx2(
     input: &[u8],
     output_y: &mut [u8],
@@ -127,6 +128,7 @@ pub unsafe fn compress_uyvy_to_nv12_avx2(
 /// Requires AVX2 CPU support. All pointers must be valid. Width must be multiple of 16.
 #[target_feature(enable = "avx2")]
 #[cfg(target_arch = "x86_64")]
+#[allow(clippy::too_many_arguments)]
 pub unsafe fn compress_uyvy_to_i420_avx2(
     input: &[u8],
     output_y: &mut [u8],
@@ -218,6 +220,7 @@ pub unsafe fn compress_uyvy_to_i420_avx2(
 }
 
 /// Auto-dispatch format conversion with runtime CPU detection
+#[allow(clippy::too_many_arguments)]
 pub fn compress_uyvy_to_nv12(
     input: &[u8],
     output_y: &mut [u8],
@@ -261,6 +264,7 @@ pub fn compress_uyvy_to_nv12(
 }
 
 /// Scalar fallback implementation (portable, slower)
+#[allow(clippy::too_many_arguments)]
 fn compress_uyvy_to_nv12_scalar(
     input: &[u8],
     output_y: &mut [u8],
@@ -322,8 +326,8 @@ mod tests {
         let mut output_uv = vec![0u8; width * height / 2];
 
         // Fill test pattern
-        for i in 0..input.len() {
-            input[i] = (i % 256) as u8;
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 256) as u8;
         }
 
         compress_uyvy_to_nv12(
@@ -3,12 +3,21 @@
 use crate::types::{VideoFormat, VideoFrame};
 use parking_lot::Mutex;
 use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::fmt;
+use std::sync::Arc;
 
-const CACHE_LINE_SIZE: usize = 64;
 const FRAME_ALIGNMENT: usize = 32; // AVX2 requires 32-byte alignment
 
 /// Pool of pre-allocated video frames to eliminate allocation churn
+///
+/// A frame is locked by `acquire` until it is either released or leased.
+/// A leased frame returns to the pool when its last clone is dropped, and
+/// the pool's memory stays allocated until every lease is gone.
 pub struct FramePool {
+    shared: Arc<PoolShared>,
+}
+
+struct PoolShared {
     frames: Mutex<Vec<PooledFrame>>,
     format: VideoFormat,
     width: u32,
@@ -20,7 +29,37 @@ struct PooledFrame {
     data: [*mut u8; 4],
     linesize: [u32; 4],
     layout: Option<Layout>,
-    in_use: bool,
+    state: SlotState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotState {
+    Free,
+    /// Acquired and not yet released or leased
+    Locked,
+    /// Owned by the clones of a leased frame
+    Leased,
+}
+
+/// Shared ownership of a pooled frame, carried by the frame and its clones
+#[derive(Clone)]
+pub struct FrameLease(Arc<Lease>);
+
+struct Lease {
+    pool: Arc<PoolShared>,
+    slot: usize,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.pool.frames.lock()[self.slot].state = SlotState::Free;
+    }
+}
+
+impl fmt::Debug for FrameLease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FrameLease").field(&self.0.slot).finish()
+    }
 }
 
 unsafe impl Send for PooledFrame {}
@@ -42,11 +81,13 @@ impl FramePool {
         }
 
         FramePool {
-            frames: Mutex::new(frames),
-            format,
-            width,
-            height,
-            capacity,
+            shared: Arc::new(PoolShared {
+                frames: Mutex::new(frames),
+                format,
+                width,
+                height,
+                capacity,
+            }),
         }
     }
 
@@ -54,7 +95,7 @@ impl FramePool {
     fn allocate_frame(format: VideoFormat, width: u32, height: u32) -> PooledFrame {
         let mut data = [std::ptr::null_mut(); 4];
         let mut linesize = [0u32; 4];
-        let mut layout = None;
+        let layout;
 
         match format {
             VideoFormat::I420 => {
@@ -116,6 +157,27 @@ impl FramePool {
                 }
             }
 
+            VideoFormat::I444 => {
+                // Three full-resolution planes
+                let plane_size = (width * height) as usize;
+                let align = FRAME_ALIGNMENT;
+
+                unsafe {
+                    let layout_obj = Layout::from_size_align_unchecked(plane_size * 3, align);
+                    let ptr = alloc_zeroed(layout_obj);
+
+                    data[0] = ptr;
+                    data[1] = ptr.add(plane_size);
+                    data[2] = ptr.add(plane_size * 2);
+
+                    linesize[0] = width;
+                    linesize[1] = width;
+                    linesize[2] = width;
+
+                    layout = Some(layout_obj);
+                }
+            }
+
             VideoFormat::RGBA | VideoFormat::BGRA | VideoFormat::BGRX => {
                 let size = (width * height * 4) as usize;
                 let align = FRAME_ALIGNMENT;
@@ -152,38 +214,68 @@ impl FramePool {
             data,
             linesize,
             layout,
-            in_use: false,
+            state: SlotState::Free,
         }
     }
 
     /// Acquire a frame from the pool
     pub fn acquire(&self) -> Option<VideoFrame> {
-        let mut frames = self.frames.lock();
+        let shared = &self.shared;
+        let mut frames = shared.frames.lock();
 
         for frame in frames.iter_mut() {
-            if !frame.in_use {
-                frame.in_use = true;
-                return Some(VideoFrame {
-                    data: frame.data,
-                    linesize: frame.linesize,
-                    width: self.width,
-                    height: self.height,
-                    format: self.format,
-                    timestamp: 0,
-                });
+            if frame.state == SlotState::Free {
+                frame.state = SlotState::Locked;
+                let mut video_frame = VideoFrame::new(shared.width, shared.height, shared.format);
+                video_frame.data = frame.data;
+                video_frame.linesize = frame.linesize;
+                return Some(video_frame);
             }
         }
 
         None // Pool exhausted
     }
 
-    /// Release a frame back to the pool
+    /// Acquire a frame that is leased from the start
+    pub fn acquire_leased(&self) -> Option<VideoFrame> {
+        let mut frame = self.acquire()?;
+        self.lease(&mut frame);
+        Some(frame)
+    }
+
+    /// Turn a locked frame into a leased one
+    ///
+    /// Returns false, leaving the frame untouched, if it is not currently
+    /// locked from this pool (e.g. it was locked from a pool since replaced).
+    pub fn lease(&self, frame: &mut VideoFrame) -> bool {
+        let mut frames = self.shared.frames.lock();
+        let slot = frames
+            .iter()
+            .position(|pooled| pooled.data[0] == frame.data[0]);
+        match slot {
+            Some(slot) if frames[slot].state == SlotState::Locked => {
+                frames[slot].state = SlotState::Leased;
+                frame.lease = Some(FrameLease(Arc::new(Lease {
+                    pool: self.shared.clone(),
+                    slot,
+                })));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Release a locked frame back to the pool
+    ///
+    /// Leased frames return by themselves once their last clone is dropped.
     pub fn release(&self, frame: &VideoFrame) {
-        let mut frames = self.frames.lock();
+        let mut frames = self.shared.frames.lock();
 
         for pooled_frame in frames.iter_mut() {
             if pooled_frame.data[0] == frame.data[0] {
-                pooled_frame.in_use = false;
+                if pooled_frame.state == SlotState::Locked {
+                    pooled_frame.state = SlotState::Free;
+                }
                 return;
             }
         }
@@ -191,20 +283,25 @@ impl FramePool {
 
     /// Get pool statistics
     pub fn stats(&self) -> PoolStats {
-        let frames = self.frames.lock();
-        let in_use = frames.iter().filter(|f| f.in_use).count();
+        let frames = self.shared.frames.lock();
+        let in_use = frames.iter().filter(|f| f.state != SlotState::Free).count();
+        let locked = frames
+            .iter()
+            .filter(|f| f.state == SlotState::Locked)
+            .count();
 
         PoolStats {
-            capacity: self.capacity,
+            capacity: self.shared.capacity,
             in_use,
-            available: self.capacity - in_use,
+            locked,
+            available: self.shared.capacity - in_use,
         }
     }
 }
 
-impl Drop for FramePool {
+impl Drop for PoolShared {
     fn drop(&mut self) {
-        let mut frames = self.frames.lock();
+        let frames = self.frames.get_mut();
 
         for frame in frames.iter_mut() {
             if let Some(layout) = frame.layout {
@@ -221,7 +318,10 @@ impl Drop for FramePool {
 #[derive(Debug, Clone, Copy)]
 pub struct PoolStats {
     pub capacity: usize,
+    /// Locked and leased frames
     pub in_use: usize,
+    /// Frames acquired and not yet released or leased
+    pub locked: usize,
     pub available: usize,
 }
 
@@ -261,6 +361,47 @@ mod tests {
         assert!(f3.is_none(), "Pool should be exhausted");
     }
 
+    #[test]
+    fn test_leased_frame_returns_on_last_drop() {
+        let pool = FramePool::new(VideoFormat::NV12, 64, 32, 1);
+
+        let mut frame = pool.acquire().unwrap();
+        assert!(pool.lease(&mut frame));
+        assert!(!pool.lease(&mut frame));
+
+        // Releasing a leased frame leaves it with its clones
+        let copy = frame.clone();
+        pool.release(&frame);
+        drop(frame);
+        assert!(pool.acquire().is_none());
+
+        drop(copy);
+        assert_eq!(pool.stats().in_use, 0);
+        assert!(pool.acquire().is_some());
+    }
+
+    #[test]
+    fn test_lease_outlives_pool() {
+        let pool = FramePool::new(VideoFormat::RGBA, 4, 4, 1);
+        let frame = pool.acquire_leased().unwrap();
+        drop(pool);
+
+        // The memory stays allocated while the frame is held
+        unsafe { frame.data[0].write_bytes(0xff, 64) };
+        drop(frame);
+    }
+
+    #[test]
+    fn test_lease_rejects_foreign_frames() {
+        let pool = FramePool::new(VideoFormat::NV12, 64, 32, 1);
+        let other = FramePool::new(VideoFormat::NV12, 64, 32, 1);
+
+        let mut foreign = other.acquire().unwrap();
+        assert!(!pool.lease(&mut foreign));
+        assert!(foreign.lease.is_none());
+        assert!(other.lease(&mut foreign));
+    }
+
     #[test]
     fn test_frame_alignment() {
         let pool = FramePool::new(VideoFormat::NV12, 1920, 1080, 1);
@@ -0,0 +1,146 @@
+//! Output frame selection for encoders running below the output rate
+//!
+//! Picks, for every frame on the encoder's target grid, the output frame
+//! closest to it in time. The decision is made from exact rational
+//! timestamps, so the rounding error never accumulates and the cadence is
+//! spread evenly (60 to 24 fps gives the familiar 2:3 pattern).
+
+use crate::rational::FrameRate;
+
+/// Rate an encoder receives frames at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EncoderRate {
+    /// Every Nth output frame, following output rate changes
+    Divisor(u32),
+    /// Fixed rate, at most the output rate
+    Target(FrameRate),
+}
+
+pub(crate) struct FrameSelector {
+    rate: EncoderRate,
+    // Output frames seen since the last reset
+    source_frames: u64,
+    // Target grid frames already filled
+    target_frames: u64,
+}
+
+impl FrameSelector {
+    pub fn new(rate: EncoderRate) -> Self {
+        Self {
+            rate,
+            source_frames: 0,
+            target_frames: 0,
+        }
+    }
+
+    /// Fixed rate, if the encoder has one
+    pub fn target_rate(&self) -> Option<FrameRate> {
+        match self.rate {
+            EncoderRate::Target(rate) => Some(rate),
+            EncoderRate::Divisor(_) => None,
+        }
+    }
+
+    /// Restart the grids, e.g. after the output rate changed
+    pub fn reset(&mut self) {
+        self.source_frames = 0;
+        self.target_frames = 0;
+    }
+
+    /// Decide whether the next output frame at `output_rate` goes to the
+    /// encoder
+    pub fn select(&mut self, output_rate: FrameRate) -> bool {
+        let (src_num, src_den) = (output_rate.num() as u128, output_rate.den() as u128);
+        let (dst_num, dst_den) = match self.rate {
+            EncoderRate::Divisor(divisor) => (src_num, src_den * divisor.max(1) as u128),
+            EncoderRate::Target(rate) => (rate.num() as u128, rate.den() as u128),
+        };
+
+        let index = self.source_frames as u128;
+        self.source_frames += 1;
+
+        // Emit once the next target frame time falls before the midpoint
+        // between this output frame and the next one:
+        //   k * dst_den / dst_num < (i + 1/2) * src_den / src_num
+        let target_time = 2 * self.target_frames as u128 * dst_den * src_num;
+        let midpoint = (2 * index + 1) * src_den * dst_num;
+        if target_time < midpoint {
+            self.target_frames += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output frame indices selected over `count` output frames
+    fn run(rate: EncoderRate, fps_num: u32, fps_den: u32, count: u64) -> Vec<u64> {
+        let mut selector = FrameSelector::new(rate);
+        let output_rate = FrameRate::new(fps_num, fps_den);
+        (0..count)
+            .filter(|_| selector.select(output_rate))
+            .collect()
+    }
+
+    #[test]
+    fn test_divisor_matches_integer_decimation() {
+        assert_eq!(run(EncoderRate::Divisor(2), 60, 1, 10), vec![0, 2, 4, 6, 8]);
+        assert_eq!(run(EncoderRate::Divisor(3), 60, 1, 7), vec![0, 3, 6]);
+        assert_eq!(run(EncoderRate::Divisor(1), 60, 1, 4), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_60_to_24_uses_2_3_cadence() {
+        let selected = run(EncoderRate::Target(FrameRate::new(24, 1)), 60, 1, 60 * 3600);
+        assert_eq!(selected.len(), 24 * 3600);
+
+        let gaps: Vec<u64> = selected.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(gaps.iter().all(|&gap| gap == 2 || gap == 3));
+        assert!(gaps.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn test_ntsc_halving_over_one_hour() {
+        // 59.94 -> 29.97 for an hour of output
+        let frames = 60_000 * 3600 / 1001;
+        let selected = run(
+            EncoderRate::Target(FrameRate::new(30_000, 1001)),
+            60_000,
+            1001,
+            frames,
+        );
+        assert_eq!(selected.len() as u64, frames.div_ceil(2));
+        assert!(selected.windows(2).all(|w| w[1] - w[0] == 2));
+    }
+
+    #[test]
+    fn test_60_to_25_stays_on_grid() {
+        let selected = run(EncoderRate::Target(FrameRate::new(25, 1)), 60, 1, 60 * 3600);
+        assert_eq!(selected.len(), 25 * 3600);
+
+        // Timestamps increase, and each selected frame is the output frame
+        // nearest its slot on the 25fps grid
+        let half_interval = FrameRate::new(60, 1).frame_to_ns(1) / 2 + 1;
+        let mut last = None;
+        for (slot, &index) in selected.iter().enumerate() {
+            let ts = FrameRate::new(60, 1).frame_to_ns(index);
+            assert!(last.is_none_or(|last| ts > last));
+            assert!(ts.abs_diff(FrameRate::new(25, 1).frame_to_ns(slot as u64)) <= half_interval);
+            last = Some(ts);
+        }
+    }
+
+    #[test]
+    fn test_reset_restarts_grid() {
+        let mut selector = FrameSelector::new(EncoderRate::Target(FrameRate::new(30, 1)));
+        assert!(selector.select(FrameRate::new(60, 1)));
+        assert!(!selector.select(FrameRate::new(60, 1)));
+
+        selector.reset();
+        assert!(selector.select(FrameRate::new(60, 1)));
+    }
+}
@@ -8,15 +8,28 @@
 //! - Zero-copy frame handling where possible
 //! - Memory pooling to reduce allocation churn
 
+pub mod backpressure;
 pub mod format_conversion;
 pub mod frame_pool;
+mod frame_selector;
+pub mod rational;
+pub mod side_data;
+pub mod telemetry;
+pub mod thread_tuning;
 pub mod types;
 pub mod video_output;
+pub mod video_scaler;
 
+pub use backpressure::*;
 pub use format_conversion::*;
 pub use frame_pool::*;
+pub use rational::*;
+pub use side_data::*;
+pub use telemetry::*;
+pub use thread_tuning::*;
 pub use types::*;
 pub use video_output::*;
+pub use video_scaler::*;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,385 @@
+//! Rational timebases and frame rates
+//!
+//! Rust counterpart of `media-io/frame-rate.h`, extended with the exact
+//! integer conversions the pipeline needs: frame index to nanoseconds and
+//! back, and rescaling between timebases (ns, 90 kHz, 1/1000) with an
+//! explicit rounding mode, so timestamps never drift.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// How to round a conversion that doesn't land on an exact value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Toward negative infinity
+    #[default]
+    Down,
+    /// Toward positive infinity
+    Up,
+    /// To the nearest value, halfway cases away from zero
+    Nearest,
+    /// Toward zero
+    Zero,
+}
+
+/// Divide with the given rounding; `den` must be positive
+fn div_round(num: i128, den: i128, rounding: Rounding) -> i128 {
+    let quot = num / den;
+    let rem = num % den;
+    if rem == 0 {
+        return quot;
+    }
+
+    match rounding {
+        Rounding::Zero => quot,
+        Rounding::Down => {
+            if num < 0 {
+                quot - 1
+            } else {
+                quot
+            }
+        }
+        Rounding::Up => {
+            if num > 0 {
+                quot + 1
+            } else {
+                quot
+            }
+        }
+        Rounding::Nearest => {
+            if rem.abs() * 2 >= den {
+                quot + num.signum()
+            } else {
+                quot
+            }
+        }
+    }
+}
+
+/// Greatest common divisor
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Exact fraction `num/den`, used as a timebase (seconds per unit)
+///
+/// Comparison and hashing are by value, so 1/1000 equals 2/2000.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Rational {
+    pub num: u32,
+    pub den: u32,
+}
+
+/// Nanosecond timebase used for all pipeline timestamps
+pub const TIMEBASE_NS: Rational = Rational::new(1, 1_000_000_000);
+
+/// MPEG-TS / RTP video timebase
+pub const TIMEBASE_90KHZ: Rational = Rational::new(1, 90_000);
+
+/// Millisecond timebase (FLV / RTMP)
+pub const TIMEBASE_MS: Rational = Rational::new(1, 1000);
+
+impl Rational {
+    pub const fn new(num: u32, den: u32) -> Self {
+        Self { num, den }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.num != 0 && self.den != 0
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Same value in lowest terms
+    pub fn reduced(&self) -> Self {
+        match gcd(self.num, self.den) {
+            0 => *self,
+            divisor => Self::new(self.num / divisor, self.den / divisor),
+        }
+    }
+
+    /// Reciprocal (den/num)
+    pub fn inverse(&self) -> Self {
+        Self::new(self.den, self.num)
+    }
+}
+
+impl PartialEq for Rational {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Rational {}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num as u64 * other.den as u64).cmp(&(other.num as u64 * self.den as u64))
+    }
+}
+
+impl Hash for Rational {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let reduced = self.reduced();
+        reduced.num.hash(state);
+        reduced.den.hash(state);
+    }
+}
+
+/// Convert `value` counted in timebase `from` to timebase `to`
+///
+/// Exact for any input: the intermediate product is computed in 128 bits
+/// and rounded once. Results outside i64 saturate.
+pub fn rescale(value: i64, from: Rational, to: Rational, rounding: Rounding) -> i64 {
+    let num = value as i128 * from.num as i128 * to.den as i128;
+    let den = from.den as i128 * to.num as i128;
+    if den == 0 {
+        return 0;
+    }
+    div_round(num, den, rounding).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Frames per second as an exact fraction (`media_frames_per_second`)
+///
+/// Comparison and hashing are by value, so 60/1 equals 120/2.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FrameRate(Rational);
+
+impl FrameRate {
+    pub const fn new(num: u32, den: u32) -> Self {
+        Self(Rational::new(num, den))
+    }
+
+    pub fn num(&self) -> u32 {
+        self.0.num
+    }
+
+    pub fn den(&self) -> u32 {
+        self.0.den
+    }
+
+    /// `media_frames_per_second_is_valid`
+    pub fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    /// `media_frames_per_second_to_fps`
+    pub fn fps(&self) -> f64 {
+        self.0.as_f64()
+    }
+
+    /// `media_frames_per_second_to_frame_interval`, in seconds
+    pub fn frame_interval(&self) -> f64 {
+        self.0.inverse().as_f64()
+    }
+
+    /// Duration of one frame as a timebase (den/num seconds)
+    pub fn timebase(&self) -> Rational {
+        self.0.inverse()
+    }
+
+    /// Timestamp offset of frame `index`, rounded down
+    ///
+    /// Computed from the full fraction on every call, so even NTSC rates
+    /// like 30000/1001 never accumulate error.
+    pub fn frame_to_ns(&self, index: u64) -> u64 {
+        self.frame_to_ns_rounded(index, Rounding::Down)
+    }
+
+    /// Timestamp offset of frame `index` with explicit rounding
+    pub fn frame_to_ns_rounded(&self, index: u64, rounding: Rounding) -> u64 {
+        if !self.is_valid() {
+            return 0;
+        }
+        let num = index as i128 * self.0.den as i128 * 1_000_000_000;
+        div_round(num, self.0.num as i128, rounding).min(u64::MAX as i128) as u64
+    }
+
+    /// Frame index at timestamp offset `ns`
+    ///
+    /// With `Rounding::Down` this is the frame whose interval contains `ns`.
+    /// The first nanosecond of frame `i` is `frame_to_ns_rounded(i, Up)`.
+    pub fn ns_to_frame(&self, ns: u64, rounding: Rounding) -> u64 {
+        if !self.is_valid() {
+            return 0;
+        }
+        let num = ns as i128 * self.0.num as i128;
+        let den = self.0.den as i128 * 1_000_000_000;
+        div_round(num, den, rounding).min(u64::MAX as i128) as u64
+    }
+
+    /// Length of one frame in nanoseconds, rounded down
+    pub fn interval_ns(&self) -> u64 {
+        self.frame_to_ns(1)
+    }
+
+    /// Whether this is an NTSC rate: an integer rate times 1000/1001
+    /// (23.976, 29.97, 59.94, ...)
+    ///
+    /// The denominator must be a multiple of 1001 and `num * 1001` must be
+    /// `k * den * 1000` for some integer rate `k`.
+    pub fn is_ntsc(&self) -> bool {
+        if !self.is_valid() || !self.0.den.is_multiple_of(1001) {
+            return false;
+        }
+        let scaled = self.0.num as u64 * 1001;
+        let unit = self.0.den as u64 * 1000;
+        let k = scaled / unit;
+        k >= 1 && scaled == k * unit
+    }
+
+    /// Integer rate this is usually labeled as (30 for 29.97)
+    pub fn nominal_fps(&self) -> u32 {
+        self.fps().round() as u32
+    }
+
+    /// Exact rate for a decimal fps value, snapping values close to an NTSC
+    /// rate (like 29.97) to it
+    pub fn from_fps(fps: f64) -> Option<Self> {
+        if !fps.is_finite() || fps <= 0.0 || fps > u32::MAX as f64 / 1000.0 {
+            return None;
+        }
+
+        let nominal = fps.round();
+        let ntsc = nominal * 1000.0 / 1001.0;
+        if nominal >= 1.0 && (fps - ntsc).abs() < 0.005 && (fps - nominal).abs() > 0.005 {
+            return Some(Self::new(nominal as u32 * 1000, 1001));
+        }
+        if (fps - nominal).abs() < 1e-6 {
+            return Some(Self::new(nominal as u32, 1));
+        }
+        Some(Self::new((fps * 1000.0).round() as u32, 1000).reduced())
+    }
+
+    /// Same rate in lowest terms
+    pub fn reduced(&self) -> Self {
+        Self(self.0.reduced())
+    }
+}
+
+impl From<FrameRate> for Rational {
+    fn from(rate: FrameRate) -> Self {
+        rate.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_time_no_drift() {
+        assert_eq!(FrameRate::new(60, 1).frame_to_ns(60), 1_000_000_000);
+        assert_eq!(FrameRate::new(60, 1).interval_ns(), 16_666_666);
+
+        // One hour of 29.97fps lands exactly on the rational boundary
+        let ntsc = FrameRate::new(30_000, 1001);
+        assert_eq!(ntsc.frame_to_ns(30_000 * 3600), 3600 * 1001 * 1_000_000_000);
+    }
+
+    #[test]
+    fn test_frame_ns_round_trip() {
+        for rate in [
+            FrameRate::new(60, 1),
+            FrameRate::new(30_000, 1001),
+            FrameRate::new(24_000, 1001),
+            FrameRate::new(25, 1),
+        ] {
+            for index in (0..1_000_000).step_by(997) {
+                let ns = rate.frame_to_ns(index);
+                assert_eq!(rate.ns_to_frame(ns, Rounding::Nearest), index);
+
+                let ns = rate.frame_to_ns_rounded(index, Rounding::Up);
+                assert_eq!(rate.ns_to_frame(ns, Rounding::Down), index);
+                // Just before the frame starts still belongs to the previous one
+                if index > 0 {
+                    assert_eq!(rate.ns_to_frame(ns - 1, Rounding::Down), index - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let rate = FrameRate::new(30_000, 1001);
+        // 33_366_666.67ns
+        assert_eq!(rate.frame_to_ns_rounded(1, Rounding::Down), 33_366_666);
+        assert_eq!(rate.frame_to_ns_rounded(1, Rounding::Up), 33_366_667);
+        assert_eq!(rate.frame_to_ns_rounded(1, Rounding::Nearest), 33_366_667);
+
+        assert_eq!(div_round(-7, 2, Rounding::Down), -4);
+        assert_eq!(div_round(-7, 2, Rounding::Up), -3);
+        assert_eq!(div_round(-7, 2, Rounding::Zero), -3);
+        assert_eq!(div_round(-7, 2, Rounding::Nearest), -4);
+        assert_eq!(div_round(5, 4, Rounding::Nearest), 1);
+    }
+
+    #[test]
+    fn test_rescale_between_timebases() {
+        // 1s in every timebase
+        assert_eq!(
+            rescale(1_000_000_000, TIMEBASE_NS, TIMEBASE_90KHZ, Rounding::Down),
+            90_000
+        );
+        assert_eq!(
+            rescale(90_000, TIMEBASE_90KHZ, TIMEBASE_MS, Rounding::Down),
+            1000
+        );
+
+        // One 29.97 frame in 90kHz ticks is exactly 3003
+        let frame = FrameRate::new(30_000, 1001).timebase();
+        assert_eq!(rescale(1, frame, TIMEBASE_90KHZ, Rounding::Down), 3003);
+
+        // 1ms of 44.1kHz audio isn't whole samples
+        let samples = Rational::new(1, 44_100);
+        assert_eq!(rescale(1, TIMEBASE_MS, samples, Rounding::Down), 44);
+        assert_eq!(rescale(1, TIMEBASE_MS, samples, Rounding::Up), 45);
+        assert_eq!(rescale(-1, TIMEBASE_MS, samples, Rounding::Down), -45);
+    }
+
+    #[test]
+    fn test_ntsc_detection() {
+        assert!(FrameRate::new(30_000, 1001).is_ntsc());
+        assert!(FrameRate::new(24_000, 1001).is_ntsc());
+        assert!(FrameRate::new(120_000, 2002).is_ntsc());
+        assert!(!FrameRate::new(30, 1).is_ntsc());
+        assert!(!FrameRate::new(2997, 100).is_ntsc());
+        assert!(!FrameRate::new(1000, 1).is_ntsc());
+        assert!(!FrameRate::new(3000, 1).is_ntsc());
+        assert!(!FrameRate::new(30_000, 1000).is_ntsc());
+        assert_eq!(FrameRate::new(60_000, 1001).nominal_fps(), 60);
+
+        assert_eq!(
+            FrameRate::from_fps(29.97),
+            Some(FrameRate::new(30_000, 1001))
+        );
+        assert_eq!(
+            FrameRate::from_fps(23.976),
+            Some(FrameRate::new(24_000, 1001))
+        );
+        assert_eq!(FrameRate::from_fps(25.0), Some(FrameRate::new(25, 1)));
+        assert_eq!(FrameRate::from_fps(12.5), Some(FrameRate::new(25, 2)));
+        assert_eq!(FrameRate::from_fps(0.0), None);
+    }
+
+    #[test]
+    fn test_compare_by_value() {
+        assert_eq!(FrameRate::new(60, 1), FrameRate::new(120, 2));
+        assert!(FrameRate::new(30_000, 1001) < FrameRate::new(30, 1));
+        assert_eq!(Rational::new(2, 2000), TIMEBASE_MS);
+        assert_eq!(FrameRate::new(60, 2).reduced().num(), 30);
+    }
+}
@@ -0,0 +1,197 @@
+//! Per-frame side data
+//!
+//! Metadata that travels with a `VideoFrame` from the graphics thread to the
+//! encoders: HDR static metadata, CEA-608 captions, a color override and
+//! keyed user data. Frames hold it behind an `Arc`, so handing a frame to
+//! several encoders does not copy it.
+
+use crate::types::{ColorRange, ColorSpace};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// SMPTE ST 2086 mastering display color volume
+///
+/// Units follow the H.264/H.265 SEI message: chromaticity in 0.00002
+/// increments, luminance in 0.0001 cd/m².
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MasteringDisplay {
+    /// (x, y) of the green, blue and red primaries, in SEI order
+    pub display_primaries: [[u16; 2]; 3],
+    pub white_point: [u16; 2],
+    pub max_luminance: u32,
+    pub min_luminance: u32,
+}
+
+impl MasteringDisplay {
+    /// mastering_display_colour_volume SEI payload
+    pub fn to_sei_payload(&self) -> [u8; 24] {
+        let mut payload = [0u8; 24];
+        for (i, [x, y]) in self.display_primaries.iter().enumerate() {
+            payload[i * 4..i * 4 + 2].copy_from_slice(&x.to_be_bytes());
+            payload[i * 4 + 2..i * 4 + 4].copy_from_slice(&y.to_be_bytes());
+        }
+        payload[12..14].copy_from_slice(&self.white_point[0].to_be_bytes());
+        payload[14..16].copy_from_slice(&self.white_point[1].to_be_bytes());
+        payload[16..20].copy_from_slice(&self.max_luminance.to_be_bytes());
+        payload[20..24].copy_from_slice(&self.min_luminance.to_be_bytes());
+        payload
+    }
+}
+
+/// CTA-861.3 content light level, in cd/m²
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContentLightLevel {
+    pub max_cll: u16,
+    pub max_fall: u16,
+}
+
+impl ContentLightLevel {
+    /// content_light_level_info SEI payload
+    pub fn to_sei_payload(&self) -> [u8; 4] {
+        let [a, b] = self.max_cll.to_be_bytes();
+        let [c, d] = self.max_fall.to_be_bytes();
+        [a, b, c, d]
+    }
+}
+
+/// Color space and range of one frame, overriding the output's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorOverride {
+    pub colorspace: ColorSpace,
+    pub range: ColorRange,
+}
+
+/// Side data attached to a frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameSideData {
+    pub mastering_display: Option<MasteringDisplay>,
+    pub content_light: Option<ContentLightLevel>,
+    /// CEA-608 byte pairs for field 1, in transmission order
+    pub captions: Vec<[u8; 2]>,
+    pub color: Option<ColorOverride>,
+    /// Opaque payloads keyed by name, e.g. registered SEI user data
+    pub user_data: BTreeMap<String, Arc<[u8]>>,
+}
+
+impl FrameSideData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mastering_display.is_none()
+            && self.content_light.is_none()
+            && self.captions.is_empty()
+            && self.color.is_none()
+            && self.user_data.is_empty()
+    }
+
+    pub fn set_user_data(&mut self, key: &str, data: &[u8]) {
+        self.user_data.insert(key.to_string(), Arc::from(data));
+    }
+
+    pub fn user_data(&self, key: &str) -> Option<&[u8]> {
+        self.user_data.get(key).map(|data| &data[..])
+    }
+
+    /// Captions as ATSC A/53 cc_data triplets (cc_valid, NTSC field 1)
+    pub fn cc_data(&self) -> Vec<u8> {
+        self.captions
+            .iter()
+            .flat_map(|&[b1, b2]| [0xFC, b1, b2])
+            .collect()
+    }
+
+    /// Side data for a repeated frame: captions were already sent once
+    pub(crate) fn for_duplicate(data: &Arc<Self>) -> Option<Arc<Self>> {
+        if data.captions.is_empty() {
+            return Some(data.clone());
+        }
+        let mut copy = Self::clone(data);
+        copy.captions.clear();
+        (!copy.is_empty()).then(|| Arc::new(copy))
+    }
+
+    /// Side data for a converted frame, which is in the conversion's color
+    /// space rather than the override's
+    pub(crate) fn for_conversion(data: &Arc<Self>) -> Option<Arc<Self>> {
+        if data.color.is_none() {
+            return Some(data.clone());
+        }
+        let mut copy = Self::clone(data);
+        copy.color = None;
+        (!copy.is_empty()).then(|| Arc::new(copy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hdr10() -> MasteringDisplay {
+        // BT.2020 primaries, D65, 1000 / 0.005 cd/m²
+        MasteringDisplay {
+            display_primaries: [[8500, 39850], [6550, 2300], [35400, 14600]],
+            white_point: [15635, 16450],
+            max_luminance: 10_000_000,
+            min_luminance: 50,
+        }
+    }
+
+    #[test]
+    fn test_sei_payloads() {
+        let payload = hdr10().to_sei_payload();
+        assert_eq!(&payload[0..4], &[0x21, 0x34, 0x9B, 0xAA]);
+        assert_eq!(&payload[12..16], &[0x3D, 0x13, 0x40, 0x42]);
+        assert_eq!(&payload[16..20], &10_000_000u32.to_be_bytes());
+        assert_eq!(&payload[20..24], &[0, 0, 0, 50]);
+
+        let cll = ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        };
+        assert_eq!(cll.to_sei_payload(), [0x03, 0xE8, 0x01, 0x90]);
+    }
+
+    #[test]
+    fn test_duplicate_drops_captions_only() {
+        let mut data = FrameSideData::new();
+        assert!(data.is_empty());
+        data.captions.push([0x94, 0x2C]);
+        data.mastering_display = Some(hdr10());
+        data.set_user_data("timecode", &[1, 2, 3]);
+        assert_eq!(data.cc_data(), vec![0xFC, 0x94, 0x2C]);
+
+        let duplicate = FrameSideData::for_duplicate(&Arc::new(data)).unwrap();
+        assert!(duplicate.captions.is_empty());
+        assert_eq!(duplicate.mastering_display, Some(hdr10()));
+        assert_eq!(duplicate.user_data("timecode"), Some(&[1, 2, 3][..]));
+
+        let captions_only = FrameSideData {
+            captions: vec![[0x80, 0x80]],
+            ..Default::default()
+        };
+        assert!(FrameSideData::for_duplicate(&Arc::new(captions_only)).is_none());
+    }
+
+    #[test]
+    fn test_conversion_drops_color_override() {
+        let unchanged = Arc::new(FrameSideData {
+            content_light: Some(ContentLightLevel::default()),
+            ..Default::default()
+        });
+        let converted = FrameSideData::for_conversion(&unchanged).unwrap();
+        assert!(Arc::ptr_eq(&converted, &unchanged));
+
+        let data = Arc::new(FrameSideData {
+            color: Some(ColorOverride {
+                colorspace: ColorSpace::CS2100PQ,
+                range: ColorRange::Full,
+            }),
+            ..Default::default()
+        });
+        assert!(FrameSideData::for_conversion(&data).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+//! Frame timing telemetry
+//!
+//! Rolling latency windows recorded by the video pipeline when telemetry is
+//! enabled. Recording is a pair of relaxed atomic operations, and when
+//! telemetry is off the pipeline skips even the clock reads, so it can stay
+//! compiled in.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Number of most recent samples a histogram keeps
+pub const LATENCY_WINDOW: usize = 1024;
+
+/// Rolling window of latency samples with lock-free recording
+pub struct LatencyHistogram {
+    samples: Box<[AtomicU64]>,
+    recorded: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            samples: (0..LATENCY_WINDOW).map(|_| AtomicU64::new(0)).collect(),
+            recorded: AtomicU64::new(0),
+        }
+    }
+
+    /// Record one sample, replacing the oldest once the window is full
+    pub fn record(&self, ns: u64) {
+        let index = self.recorded.fetch_add(1, Ordering::Relaxed) as usize % LATENCY_WINDOW;
+        self.samples[index].store(ns, Ordering::Relaxed);
+    }
+
+    /// Forget all samples
+    pub fn clear(&self) {
+        self.recorded.store(0, Ordering::Relaxed);
+    }
+
+    /// Percentiles over the current window
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let recorded = self.recorded.load(Ordering::Relaxed);
+        let len = (recorded as usize).min(LATENCY_WINDOW);
+        if len == 0 {
+            return LatencySnapshot::default();
+        }
+
+        let mut window: Vec<u64> = self.samples[..len]
+            .iter()
+            .map(|sample| sample.load(Ordering::Relaxed))
+            .collect();
+        window.sort_unstable();
+
+        // Nearest-rank percentile
+        let percentile = |p: usize| window[(len * p).div_ceil(100).max(1) - 1];
+        let sum: u128 = window.iter().map(|&ns| ns as u128).sum();
+
+        LatencySnapshot {
+            recorded,
+            window: len,
+            min_ns: window[0],
+            max_ns: window[len - 1],
+            mean_ns: (sum / len as u128) as u64,
+            p50_ns: percentile(50),
+            p95_ns: percentile(95),
+            p99_ns: percentile(99),
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Summary of a latency window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    /// Samples recorded since the histogram was created or cleared
+    pub recorded: u64,
+    /// Samples in the current window
+    pub window: usize,
+    pub min_ns: u64,
+    pub max_ns: u64,
+    pub mean_ns: u64,
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub p99_ns: u64,
+}
+
+/// Timing state shared between a `VideoOutput` and its thread
+pub(crate) struct VideoTelemetry {
+    enabled: AtomicBool,
+    epoch: Instant,
+    /// unlock_frame to pickup by the video thread
+    pub submit_latency: LatencyHistogram,
+    /// Deviation of each output interval from the nominal frame interval
+    pub frame_jitter: LatencyHistogram,
+}
+
+impl VideoTelemetry {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            epoch: Instant::now(),
+            submit_latency: LatencyHistogram::new(),
+            frame_jitter: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Current time in ns since the output was created, if enabled
+    pub fn now(&self) -> Option<u64> {
+        self.is_enabled()
+            .then(|| self.epoch.elapsed().as_nanos() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.snapshot(), LatencySnapshot::default());
+
+        for ns in 1..=100 {
+            histogram.record(ns * 1000);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.window, 100);
+        assert_eq!(snapshot.min_ns, 1000);
+        assert_eq!(snapshot.max_ns, 100_000);
+        assert_eq!(snapshot.mean_ns, 50_500);
+        assert_eq!(snapshot.p50_ns, 50_000);
+        assert_eq!(snapshot.p95_ns, 95_000);
+        assert_eq!(snapshot.p99_ns, 99_000);
+    }
+
+    #[test]
+    fn test_window_rolls_over() {
+        let histogram = LatencyHistogram::new();
+        for _ in 0..LATENCY_WINDOW {
+            histogram.record(1_000_000);
+        }
+        for _ in 0..LATENCY_WINDOW {
+            histogram.record(10);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.recorded, 2 * LATENCY_WINDOW as u64);
+        assert_eq!(snapshot.window, LATENCY_WINDOW);
+        assert_eq!(snapshot.max_ns, 10);
+
+        histogram.clear();
+        assert_eq!(histogram.snapshot().window, 0);
+    }
+
+    #[test]
+    fn test_disabled_telemetry_reads_no_clock() {
+        let telemetry = VideoTelemetry::new();
+        assert_eq!(telemetry.now(), None);
+
+        telemetry.set_enabled(true);
+        assert!(telemetry.now().is_some());
+    }
+}
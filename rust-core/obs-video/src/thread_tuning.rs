@@ -0,0 +1,530 @@
+//! Thread pinning and scheduling priority
+//!
+//! Applies the `[optimizations]` pinning and `[runtime]` scheduling settings
+//! from a build profile (see `build-profiles/*.toml`) to the pipeline's
+//! threads. Every step is best effort: without the needed privileges a
+//! thread simply keeps its default affinity or priority, and the effective
+//! result is recorded so it can be reported.
+
+use parking_lot::{Mutex, RwLock};
+use serde::Deserialize;
+use std::fmt;
+use std::thread::{self, ThreadId};
+
+/// Pipeline thread roles that can be pinned
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThreadRole {
+    Video = 0,
+    Audio = 1,
+    Render = 2,
+    Encoder = 3,
+    /// rayon pool workers
+    Worker = 4,
+}
+
+impl ThreadRole {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(ThreadRole::Video),
+            1 => Some(ThreadRole::Audio),
+            2 => Some(ThreadRole::Render),
+            3 => Some(ThreadRole::Encoder),
+            4 => Some(ThreadRole::Worker),
+            _ => None,
+        }
+    }
+
+    /// SCHED_FIFO priority requested when real-time scheduling is enabled
+    ///
+    /// Audio ranks above video so a busy frame never causes an audio gap.
+    fn realtime_priority(self) -> Option<i32> {
+        match self {
+            ThreadRole::Audio => Some(70),
+            ThreadRole::Video => Some(60),
+            ThreadRole::Render => Some(55),
+            ThreadRole::Encoder => Some(50),
+            ThreadRole::Worker => None,
+        }
+    }
+
+    /// Nice value used when SCHED_FIFO is not permitted
+    fn fallback_nice(self) -> Option<i32> {
+        match self {
+            ThreadRole::Audio => Some(-15),
+            ThreadRole::Video | ThreadRole::Render => Some(-10),
+            ThreadRole::Encoder => Some(-5),
+            ThreadRole::Worker => None,
+        }
+    }
+}
+
+/// Thread placement settings, usually loaded from a build profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadConfig {
+    /// Pin threads to their configured cores (`runtime.cpu_affinity`)
+    pub cpu_affinity: bool,
+    /// Request SCHED_FIFO, falling back to nice (`runtime.realtime_priority`)
+    pub realtime_priority: bool,
+    pub video_core: Option<usize>,
+    pub audio_core: Option<usize>,
+    pub render_core: Option<usize>,
+    pub encoder_core: Option<usize>,
+    /// Threads in the audio mixer pool (`optimizations.audio_threads`)
+    pub audio_threads: Option<usize>,
+}
+
+/// Why a build profile could not be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("could not read profile: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid profile: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Keys of a build profile that place threads
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Profile {
+    optimizations: ProfileOptimizations,
+    runtime: ProfileRuntime,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProfileOptimizations {
+    pin_video_thread: Option<usize>,
+    pin_audio_thread: Option<usize>,
+    pin_render_thread: Option<usize>,
+    pin_encoder_thread: Option<usize>,
+    audio_threads: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProfileRuntime {
+    cpu_affinity: bool,
+    realtime_priority: bool,
+}
+
+impl ThreadConfig {
+    /// Read the thread settings out of a build profile
+    ///
+    /// The profile must be valid TOML and the keys used here must have the
+    /// right types (core numbers are non-negative integers, switches are
+    /// booleans); other sections and keys are ignored.
+    pub fn from_profile_str(profile: &str) -> Result<Self, toml::de::Error> {
+        let profile: Profile = toml::from_str(profile)?;
+        let optimizations = profile.optimizations;
+        Ok(ThreadConfig {
+            cpu_affinity: profile.runtime.cpu_affinity,
+            realtime_priority: profile.runtime.realtime_priority,
+            video_core: optimizations.pin_video_thread,
+            audio_core: optimizations.pin_audio_thread,
+            render_core: optimizations.pin_render_thread,
+            encoder_core: optimizations.pin_encoder_thread,
+            audio_threads: optimizations.audio_threads,
+        })
+    }
+
+    /// Load a build profile from disk
+    pub fn from_profile_file(path: &str) -> Result<Self, ProfileError> {
+        let profile = std::fs::read_to_string(path)?;
+        Ok(Self::from_profile_str(&profile)?)
+    }
+
+    /// Core a role is pinned to, if pinning is enabled
+    pub fn core_for(&self, role: ThreadRole) -> Option<usize> {
+        if !self.cpu_affinity {
+            return None;
+        }
+        match role {
+            ThreadRole::Video => self.video_core,
+            ThreadRole::Audio => self.audio_core,
+            ThreadRole::Render => self.render_core,
+            ThreadRole::Encoder => self.encoder_core,
+            ThreadRole::Worker => None,
+        }
+    }
+
+    /// Cores left for pool workers: every available core not reserved for
+    /// a pinned role, or None when pinning is disabled
+    pub fn worker_cores(&self) -> Option<Vec<usize>> {
+        if !self.cpu_affinity {
+            return None;
+        }
+        let reserved = [
+            self.video_core,
+            self.audio_core,
+            self.render_core,
+            self.encoder_core,
+        ];
+        let cores: Vec<usize> = core_affinity::get_core_ids()?
+            .into_iter()
+            .map(|core| core.id)
+            .filter(|id| !reserved.contains(&Some(*id)))
+            .collect();
+        if cores.is_empty() {
+            None
+        } else {
+            Some(cores)
+        }
+    }
+}
+
+/// Scheduling priority a thread ended up with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Left at the OS default
+    Default,
+    /// Normal scheduling with this nice value
+    Nice(i32),
+    /// SCHED_FIFO at this priority
+    Realtime(i32),
+}
+
+impl fmt::Display for ThreadPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreadPriority::Default => write!(f, "default"),
+            ThreadPriority::Nice(nice) => write!(f, "nice {}", nice),
+            ThreadPriority::Realtime(prio) => write!(f, "SCHED_FIFO {}", prio),
+        }
+    }
+}
+
+/// Effective placement of one tuned thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadReport {
+    /// Reports are kept per thread, so same-named threads of different
+    /// outputs each have their own
+    pub thread_id: ThreadId,
+    pub name: String,
+    pub role: ThreadRole,
+    /// Core the profile asked for
+    pub requested_core: Option<usize>,
+    /// Cores the thread may actually run on (empty if unknown)
+    pub cores: Vec<usize>,
+    pub priority: ThreadPriority,
+}
+
+static THREAD_CONFIG: RwLock<Option<ThreadConfig>> = RwLock::new(None);
+static THREAD_REPORTS: Mutex<Vec<ThreadReport>> = Mutex::new(Vec::new());
+
+/// Set the process-wide thread configuration
+///
+/// Applies to threads started afterwards; running threads keep their
+/// current placement.
+pub fn set_thread_config(config: ThreadConfig) {
+    *THREAD_CONFIG.write() = Some(config);
+}
+
+/// Current process-wide thread configuration (no pinning by default)
+pub fn thread_config() -> ThreadConfig {
+    THREAD_CONFIG.read().clone().unwrap_or_default()
+}
+
+/// Reports for every tuned thread, one per thread
+pub fn thread_reports() -> Vec<ThreadReport> {
+    THREAD_REPORTS.lock().clone()
+}
+
+/// Drop the calling thread's report, for threads about to exit
+pub fn forget_current_thread() {
+    let id = thread::current().id();
+    THREAD_REPORTS
+        .lock()
+        .retain(|report| report.thread_id != id);
+}
+
+/// Tune the calling thread for `role` using the process-wide configuration
+pub fn tune_current_thread(role: ThreadRole) -> ThreadReport {
+    tune_current_thread_with(&thread_config(), role, None)
+}
+
+/// Tune the calling thread for `role`
+///
+/// `worker_core` pins a pool worker, which has no core of its own in the
+/// profile. The result is recorded in `thread_reports`.
+pub fn tune_current_thread_with(
+    config: &ThreadConfig,
+    role: ThreadRole,
+    worker_core: Option<usize>,
+) -> ThreadReport {
+    let requested_core = config.core_for(role).or(worker_core);
+
+    if let Some(core) = requested_core {
+        if !core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+            log::warn!("Could not pin {:?} thread to core {}", role, core);
+        }
+    }
+
+    let priority = if config.realtime_priority {
+        raise_priority(role)
+    } else {
+        current_priority()
+    };
+
+    let current = thread::current();
+    let report = ThreadReport {
+        thread_id: current.id(),
+        name: current.name().unwrap_or("unnamed").to_string(),
+        role,
+        requested_core,
+        cores: current_affinity(),
+        priority,
+    };
+    log::info!(
+        "Thread '{}' ({:?}): cores {:?}, priority {}",
+        report.name,
+        role,
+        report.cores,
+        report.priority
+    );
+
+    let mut reports = THREAD_REPORTS.lock();
+    reports.retain(|existing| existing.thread_id != report.thread_id);
+    reports.push(report.clone());
+    report
+}
+
+/// Build the global rayon pool with workers spread over the cores not
+/// reserved for pinned roles
+///
+/// Returns false if the global pool was already initialized.
+pub fn build_global_worker_pool(config: &ThreadConfig) -> bool {
+    let worker_cores = config.worker_cores();
+    let config = config.clone();
+
+    rayon::ThreadPoolBuilder::new()
+        .thread_name(|index| format!("obs-worker-{}", index))
+        .start_handler(move |index| {
+            let core = worker_cores
+                .as_ref()
+                .map(|cores| cores[index % cores.len()]);
+            tune_current_thread_with(&config, ThreadRole::Worker, core);
+        })
+        .build_global()
+        .is_ok()
+}
+
+#[cfg(target_os = "linux")]
+fn raise_priority(role: ThreadRole) -> ThreadPriority {
+    if let Some(prio) = role.realtime_priority() {
+        let param = libc::sched_param {
+            sched_priority: prio,
+        };
+        // SAFETY: pthread_self is always a valid handle for the caller
+        let result =
+            unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+        if result == 0 {
+            return current_priority();
+        }
+    }
+
+    if let Some(nice) = role.fallback_nice() {
+        // SAFETY: plain syscalls; on Linux setpriority with a thread id only
+        // affects that thread
+        let result = unsafe {
+            let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+            libc::setpriority(libc::PRIO_PROCESS, tid, nice)
+        };
+        if result != 0 {
+            log::warn!(
+                "No permission to raise {:?} thread priority, keeping default",
+                role
+            );
+        }
+    }
+
+    current_priority()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn raise_priority(role: ThreadRole) -> ThreadPriority {
+    log::warn!(
+        "Real-time scheduling is not supported on this platform, {:?} thread keeps default priority",
+        role
+    );
+    ThreadPriority::Default
+}
+
+#[cfg(target_os = "linux")]
+fn current_priority() -> ThreadPriority {
+    // SAFETY: out-parameters are valid locals
+    unsafe {
+        let mut policy = 0;
+        let mut param: libc::sched_param = std::mem::zeroed();
+        if libc::pthread_getschedparam(libc::pthread_self(), &mut policy, &mut param) == 0
+            && policy == libc::SCHED_FIFO
+        {
+            return ThreadPriority::Realtime(param.sched_priority);
+        }
+
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        match libc::getpriority(libc::PRIO_PROCESS, tid) {
+            0 => ThreadPriority::Default,
+            nice => ThreadPriority::Nice(nice),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_priority() -> ThreadPriority {
+    ThreadPriority::Default
+}
+
+#[cfg(target_os = "linux")]
+fn current_affinity() -> Vec<usize> {
+    // SAFETY: the cpu_set_t is a valid local of the size we pass
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Vec::new();
+        }
+        (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+            .collect()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_affinity() -> Vec<usize> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_build_profiles() {
+        let pc =
+            ThreadConfig::from_profile_str(include_str!("../../build-profiles/pc-i7-9700k.toml"))
+                .unwrap();
+        assert_eq!(
+            pc,
+            ThreadConfig {
+                cpu_affinity: true,
+                realtime_priority: false,
+                video_core: Some(0),
+                audio_core: Some(1),
+                render_core: Some(2),
+                encoder_core: Some(3),
+                audio_threads: Some(1),
+            }
+        );
+
+        let laptop = ThreadConfig::from_profile_str(include_str!(
+            "../../build-profiles/laptop-i7-1165g7.toml"
+        ))
+        .unwrap();
+        assert!(laptop.cpu_affinity);
+        assert_eq!(laptop.core_for(ThreadRole::Audio), Some(1));
+    }
+
+    #[test]
+    fn test_invalid_profiles_rejected() {
+        let parse = ThreadConfig::from_profile_str;
+        assert_eq!(parse("").unwrap(), ThreadConfig::default());
+        assert!(parse("[optimizations]\npin_video_thread = 2 # core").is_ok());
+
+        // Values the old line parser silently dropped or misread
+        assert!(parse("[optimizations]\npin_video_thread = \"2\"").is_err());
+        assert!(parse("[optimizations]\npin_video_thread = -1").is_err());
+        assert!(parse("[runtime]\ncpu_affinity = yes").is_err());
+        assert!(parse("[runtime]\ncpu_affinity = 1").is_err());
+        assert!(parse("[runtime\ncpu_affinity = true").is_err());
+        assert!(parse("[runtime]\ncpu_affinity = true\ncpu_affinity = false").is_err());
+
+        assert!(matches!(
+            ThreadConfig::from_profile_file("/nonexistent.toml"),
+            Err(ProfileError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_pinning_disabled_ignores_cores() {
+        let config = ThreadConfig {
+            cpu_affinity: false,
+            video_core: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(config.core_for(ThreadRole::Video), None);
+        assert_eq!(config.worker_cores(), None);
+    }
+
+    #[test]
+    fn test_worker_cores_exclude_pinned_roles() {
+        let cores = match core_affinity::get_core_ids() {
+            Some(cores) if cores.len() > 1 => cores,
+            _ => return,
+        };
+        let config = ThreadConfig {
+            cpu_affinity: true,
+            video_core: Some(cores[0].id),
+            ..Default::default()
+        };
+
+        let workers = config.worker_cores().unwrap();
+        assert!(!workers.contains(&cores[0].id));
+        assert_eq!(workers.len(), cores.len() - 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tune_thread_reports_effective_placement() {
+        let core = core_affinity::get_core_ids().unwrap()[0].id;
+        let config = ThreadConfig {
+            cpu_affinity: true,
+            realtime_priority: true,
+            video_core: Some(core),
+            ..Default::default()
+        };
+
+        let report = thread::Builder::new()
+            .name("tuning-test".to_string())
+            .spawn(move || tune_current_thread_with(&config, ThreadRole::Video, None))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(report.requested_core, Some(core));
+        assert_eq!(report.cores, vec![core]);
+        // Depends on privileges, but must never end up lowered
+        assert!(matches!(
+            report.priority,
+            ThreadPriority::Realtime(60) | ThreadPriority::Nice(-10) | ThreadPriority::Default
+        ));
+        assert!(thread_reports().iter().any(|r| r.name == "tuning-test"));
+    }
+
+    #[test]
+    fn test_reports_kept_per_thread() {
+        let tune = || {
+            thread::Builder::new()
+                .name("same-name-test".to_string())
+                .spawn(|| {
+                    tune_current_thread_with(&ThreadConfig::default(), ThreadRole::Video, None)
+                })
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+        let (first, second) = (tune(), tune());
+        assert_ne!(first.thread_id, second.thread_id);
+
+        let reports = thread_reports();
+        assert!(reports.contains(&first) && reports.contains(&second));
+
+        let gone = thread::spawn(|| {
+            tune_current_thread_with(&ThreadConfig::default(), ThreadRole::Audio, None);
+            forget_current_thread();
+            thread::current().id()
+        })
+        .join()
+        .unwrap();
+        assert!(thread_reports().iter().all(|r| r.thread_id != gone));
+    }
+}
@@ -1,10 +1,14 @@
 //! Video data types and constants
 
+use crate::frame_pool::FrameLease;
+use crate::rational::FrameRate;
+use crate::side_data::FrameSideData;
 use bytemuck::{Pod, Zeroable};
+use std::sync::Arc;
 
 /// Video format enumeration
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum VideoFormat {
     None = 0,
     I420 = 1,  // Planar YUV 4:2:0
@@ -86,6 +90,11 @@ pub struct VideoFrame {
     pub height: u32,
     pub format: VideoFormat,
     pub timestamp: u64,
+    /// Metadata for encoders; pooled frames start without any
+    pub side_data: Option<Arc<FrameSideData>>,
+    /// Keeps a pooled frame's memory out of the pool while any clone of
+    /// the frame is alive
+    pub(crate) lease: Option<FrameLease>,
 }
 
 unsafe impl Send for VideoFrame {}
@@ -100,24 +109,45 @@ impl VideoFrame {
             height,
             format,
             timestamp: 0,
+            side_data: None,
+            lease: None,
         }
     }
+
+    /// Attach side data, replacing any already present
+    pub fn set_side_data(&mut self, side_data: FrameSideData) {
+        self.side_data = (!side_data.is_empty()).then(|| Arc::new(side_data));
+    }
+
+    /// Side data for editing, copied first if other frames share it
+    pub fn side_data_mut(&mut self) -> &mut FrameSideData {
+        Arc::make_mut(self.side_data.get_or_insert_with(Default::default))
+    }
 }
 
 /// Video output info
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
 pub struct VideoOutputInfo {
     pub width: u32,
     pub height: u32,
     pub fps_num: u32,
     pub fps_den: u32,
-    pub format: u32, // VideoFormat as u32
+    pub format: u32,     // VideoFormat as u32
+    pub colorspace: u32, // ColorSpace as u32
+    pub range: u32,      // ColorRange as u32
+}
+
+impl VideoOutputInfo {
+    /// Output frame rate as an exact fraction
+    pub fn frame_rate(&self) -> FrameRate {
+        FrameRate::new(self.fps_num, self.fps_den)
+    }
 }
 
 /// Colorspace enumeration
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ColorSpace {
     Default = 0,
     CS601 = 1,     // BT.601 (SD)
@@ -127,11 +157,36 @@ pub enum ColorSpace {
     CS2100HLG = 5, // BT.2100 HLG (HDR)
 }
 
+impl ColorSpace {
+    /// Convert from the raw value stored in `VideoOutputInfo`
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => ColorSpace::CS601,
+            2 => ColorSpace::CS709,
+            3 => ColorSpace::SRGB,
+            4 => ColorSpace::CS2100PQ,
+            5 => ColorSpace::CS2100HLG,
+            _ => ColorSpace::Default,
+        }
+    }
+}
+
 /// Color range enumeration
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ColorRange {
     Default = 0,
     Partial = 1, // Limited range (16-235)
     Full = 2,    // Full range (0-255)
 }
+
+impl ColorRange {
+    /// Convert from the raw value stored in `VideoOutputInfo`
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => ColorRange::Partial,
+            2 => ColorRange::Full,
+            _ => ColorRange::Default,
+        }
+    }
+}
@@ -3,18 +3,41 @@
 //! Replaces mutex-based video-io.c with lock-free data structures
 //! for better performance on 8-core i7-9700K.
 
+use crate::backpressure::{deliver, evict_oldest, BackpressurePolicy, Delivery, DeliveryCounters};
 use crate::frame_pool::FramePool;
-use crate::types::{VideoFrame, VideoOutputInfo};
-use crossbeam::channel::{self, Receiver, Sender};
+use crate::frame_selector::{EncoderRate, FrameSelector};
+use crate::rational::{FrameRate, Rounding};
+use crate::side_data::FrameSideData;
+use crate::telemetry::{LatencyHistogram, LatencySnapshot, VideoTelemetry};
+use crate::thread_tuning::{forget_current_thread, tune_current_thread, ThreadRole};
+use crate::types::{ColorRange, ColorSpace, VideoFormat, VideoFrame, VideoOutputInfo};
+use crate::video_scaler::{VideoConversion, VideoScaler};
+use crossbeam::channel::{self, Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError};
 use crossbeam::queue::ArrayQueue;
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const MAX_CACHE_SIZE: usize = 16;
 
+/// Capacity of each encoder's frame channel
+const ENCODER_QUEUE_SIZE: usize = 4;
+
+/// Converted frames one encoder can hold: a full channel plus the frame it
+/// is currently working on
+const CONVERTED_IN_FLIGHT: usize = ENCODER_QUEUE_SIZE + 1;
+
+/// How long an encoder's channel may stay too full to take a format change
+/// before the encoder is disconnected
+const FORMAT_CHANGE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Maximum number of output frames the video thread will catch up on after
+/// a stall (e.g. system suspend) before jumping its clock forward instead
+const MAX_CATCHUP_FRAMES: u64 = MAX_CACHE_SIZE as u64;
+
 /// Lock-free video output
 pub struct VideoOutput {
     info: VideoOutputInfo,
@@ -26,27 +49,205 @@ pub struct VideoOutput {
     encoders: Arc<RwLock<Vec<EncoderConnection>>>,
 
     // Frame pool for zero-copy operations
-    frame_pool: Arc<FramePool>,
+    frame_pool: FramePool,
+
+    // Pools replaced by a reconfigure that still have frames locked by the
+    // graphics thread (leased frames keep their memory alive on their own)
+    retired_pools: Vec<FramePool>,
 
     // Statistics (atomic for lock-free reads)
     total_frames: Arc<AtomicU64>,
     skipped_frames: Arc<AtomicU64>,
+    lagged_frames: Arc<AtomicU64>,
+    telemetry: Arc<VideoTelemetry>,
 
     // Thread control
     running: Arc<AtomicBool>,
     thread_handle: Option<JoinHandle<()>>,
+    reconfigure_tx: Sender<Reconfigure>,
+}
+
+/// Request for the video thread to switch to a new output configuration
+struct Reconfigure {
+    info: VideoOutputInfo,
+    done: Sender<()>,
 }
 
 struct CachedFrame {
     frame: VideoFrame,
-    _timestamp: u64, // Stored for timestamp management
+    timestamp: u64,
+    // Telemetry clock at unlock_frame (None while telemetry is off)
+    submitted_ns: Option<u64>,
 }
 
 struct EncoderConnection {
     id: u64,
-    frame_rate_divisor: u32,
-    frame_count: u32,
-    tx: Sender<VideoFrame>,
+    selector: FrameSelector,
+    tx: Sender<EncoderEvent>,
+    // Receiver clone for evicting queued frames (DropOldest only)
+    evict_rx: Option<Receiver<EncoderEvent>>,
+    backpressure: BackpressurePolicy,
+    conversion: Option<VideoConversion>,
+    counters: Arc<DeliveryCounters>,
+    latency: Arc<LatencyHistogram>,
+    // Format change not yet queued, and when it was made; no frame goes
+    // out to the encoder until it is
+    pending_change: Option<(VideoFormatChange, Instant)>,
+}
+
+/// Item received on an encoder's channel
+#[derive(Debug, Clone)]
+pub enum EncoderEvent {
+    /// Next frame to encode
+    Frame(VideoFrame),
+    /// The output was reconfigured; frames after this event have the new
+    /// shape and rate
+    FormatChanged(VideoFormatChange),
+}
+
+impl EncoderEvent {
+    /// Whether this is a control event, which is never dropped
+    pub fn is_control(&self) -> bool {
+        matches!(self, EncoderEvent::FormatChanged(_))
+    }
+
+    /// The frame, if this is a frame event
+    pub fn into_frame(self) -> Option<VideoFrame> {
+        match self {
+            EncoderEvent::Frame(frame) => Some(frame),
+            EncoderEvent::FormatChanged(_) => None,
+        }
+    }
+}
+
+/// Format change sent to encoders by `VideoOutput::reconfigure`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoFormatChange {
+    /// New output configuration
+    pub info: VideoOutputInfo,
+    /// Shape of the frames this encoder receives from now on (differs from
+    /// the output's when the encoder has a conversion)
+    pub frame_shape: VideoConversion,
+}
+
+/// Options for connecting an encoder to a `VideoOutput`
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderOptions {
+    /// Deliver every Nth output frame (e.g. 2 for 30fps from a 60fps canvas)
+    pub frame_rate_divisor: u32,
+    /// Fixed encoder rate, e.g. 24/1 from a 60fps canvas; overrides
+    /// `frame_rate_divisor`
+    ///
+    /// Each frame on the target grid gets the output frame nearest to it,
+    /// so the cadence is even and never drifts. Must not exceed the output
+    /// rate.
+    pub frame_rate: Option<FrameRate>,
+    /// What to do when the encoder falls behind
+    ///
+    /// `Block` waits on the video thread, holding up every other encoder
+    /// and the fps clock for as long as this encoder's channel stays full.
+    pub backpressure: BackpressurePolicy,
+    /// Scale/convert frames for this encoder (None delivers output frames)
+    ///
+    /// Encoders asking for the same conversion share the converted frames.
+    pub conversion: Option<VideoConversion>,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self {
+            frame_rate_divisor: 1,
+            frame_rate: None,
+            backpressure: BackpressurePolicy::DropNewest,
+            conversion: None,
+        }
+    }
+}
+
+/// Owned connection between a `VideoOutput` and one encoder
+///
+/// Frames are received through the handle. Dropping it (or calling
+/// `disconnect`) removes the encoder from the output.
+pub struct EncoderHandle {
+    id: u64,
+    rx: Receiver<EncoderEvent>,
+    counters: Arc<DeliveryCounters>,
+    latency: Arc<LatencyHistogram>,
+    encoders: Weak<RwLock<Vec<EncoderConnection>>>,
+}
+
+impl EncoderHandle {
+    /// Connection ID, usable with `VideoOutput::disconnect_encoder`
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Channel the encoder receives frames and format changes on
+    pub fn receiver(&self) -> &Receiver<EncoderEvent> {
+        &self.rx
+    }
+
+    /// Receive an event without blocking
+    pub fn try_recv(&self) -> Result<EncoderEvent, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Receive an event, waiting up to `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<EncoderEvent, RecvTimeoutError> {
+        self.rx.recv_timeout(timeout)
+    }
+
+    /// Check if the output still delivers frames to this encoder
+    pub fn is_connected(&self) -> bool {
+        self.encoders
+            .upgrade()
+            .is_some_and(|encoders| encoders.read().iter().any(|enc| enc.id == self.id))
+    }
+
+    /// Get delivery statistics for this encoder
+    pub fn stats(&self) -> EncoderStats {
+        EncoderStats {
+            received_frames: self.counters.delivered.load(Ordering::Relaxed),
+            dropped_frames: self.counters.dropped.load(Ordering::Relaxed),
+            replaced_frames: self.counters.replaced.load(Ordering::Relaxed),
+            blocked_frames: self.counters.blocked.load(Ordering::Relaxed),
+            consecutive_drops: self.counters.consecutive_drops.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Time from distribution of each frame until it was queued on this
+    /// encoder's channel (recorded while timing telemetry is enabled)
+    pub fn delivery_latency(&self) -> LatencySnapshot {
+        self.latency.snapshot()
+    }
+
+    /// Disconnect from the output
+    pub fn disconnect(self) {
+        // Removal happens in Drop
+    }
+}
+
+impl Drop for EncoderHandle {
+    fn drop(&mut self) {
+        if let Some(encoders) = self.encoders.upgrade() {
+            encoders.write().retain(|enc| enc.id != self.id);
+        }
+    }
+}
+
+/// Per-encoder delivery statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncoderStats {
+    /// Frames delivered into the encoder's channel
+    pub received_frames: u64,
+    /// Frames dropped because the encoder's channel was full
+    pub dropped_frames: u64,
+    /// Queued frames evicted to make room for newer ones (DropOldest)
+    pub replaced_frames: u64,
+    /// Deliveries that had to wait for channel space (Block)
+    pub blocked_frames: u64,
+    /// Drops since the last successful delivery
+    pub consecutive_drops: u64,
 }
 
 impl VideoOutput {
@@ -57,41 +258,56 @@ impl VideoOutput {
             height,
             fps_num,
             fps_den,
-            format: crate::types::VideoFormat::NV12 as u32,
+            format: VideoFormat::NV12 as u32,
+            colorspace: ColorSpace::Default as u32,
+            range: ColorRange::Default as u32,
         };
 
         let frame_queue = Arc::new(ArrayQueue::new(MAX_CACHE_SIZE));
         let encoders = Arc::new(RwLock::new(Vec::new()));
-        let frame_pool = Arc::new(FramePool::new(
-            crate::types::VideoFormat::NV12,
+        let frame_pool = FramePool::new(
+            VideoFormat::NV12,
             width,
             height,
             MAX_CACHE_SIZE + 4, // Extra frames for in-flight encoding
-        ));
+        );
 
         let total_frames = Arc::new(AtomicU64::new(0));
         let skipped_frames = Arc::new(AtomicU64::new(0));
+        let lagged_frames = Arc::new(AtomicU64::new(0));
+        let telemetry = Arc::new(VideoTelemetry::new());
         let running = Arc::new(AtomicBool::new(true));
+        let (reconfigure_tx, reconfigure_rx) = channel::bounded(1);
 
         // Spawn video distribution thread
-        let thread_handle = Self::spawn_video_thread(
-            frame_queue.clone(),
-            encoders.clone(),
-            total_frames.clone(),
-            skipped_frames.clone(),
-            running.clone(),
+        let thread_handle = Self::spawn_video_thread(VideoThread {
+            frame_queue: frame_queue.clone(),
+            encoders: encoders.clone(),
+            total_frames: total_frames.clone(),
+            lagged_frames: lagged_frames.clone(),
+            telemetry: telemetry.clone(),
+            running: running.clone(),
+            reconfigure_rx,
             info,
-        );
+            last_frame: None,
+            last_emit_ns: None,
+            clock_base: None,
+            targets: HashMap::new(),
+        });
 
         VideoOutput {
             info,
             frame_queue,
             encoders,
             frame_pool,
+            retired_pools: Vec::new(),
             total_frames,
             skipped_frames,
+            lagged_frames,
+            telemetry,
             running,
             thread_handle: Some(thread_handle),
+            reconfigure_tx,
         }
     }
 
@@ -109,19 +325,37 @@ impl VideoOutput {
     pub fn unlock_frame(&self, mut frame: VideoFrame, timestamp: u64) -> bool {
         frame.timestamp = timestamp;
 
+        // From here on the frame is shared with encoders, and returns to
+        // the pool when the last of them drops its copy. Only frames locked
+        // from the current pool are accepted; one locked before a
+        // reconfigure goes back to the pool it came from.
+        if !self.frame_pool.lease(&mut frame) {
+            for pool in &self.retired_pools {
+                pool.release(&frame);
+            }
+            self.skipped_frames.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
         let cached = CachedFrame {
             frame: frame.clone(),
-            _timestamp: timestamp,
+            timestamp,
+            submitted_ns: self.telemetry.now(),
         };
 
         match self.frame_queue.push(cached) {
-            Ok(_) => true,
+            Ok(_) => {
+                // Unparking is lock-free; a wakeup that arrives before the
+                // thread parks is kept and makes the next park return at once
+                if let Some(handle) = &self.thread_handle {
+                    handle.thread().unpark();
+                }
+                true
+            }
             Err(_) => {
-                // Queue full - increment skip counter
+                // Queue full - increment skip counter; dropping the frame
+                // returns it to the pool
                 self.skipped_frames.fetch_add(1, Ordering::Relaxed);
-
-                // Release frame back to pool
-                self.frame_pool.release(&frame);
                 false
             }
         }
@@ -129,27 +363,152 @@ impl VideoOutput {
 
     /// Connect an encoder
     ///
-    /// Returns channel receiver that the encoder can use to receive frames.
+    /// Returns a handle that the encoder can use to receive frames. The
+    /// encoder stays connected until the handle is dropped or disconnected.
     /// frame_rate_divisor allows encoding at fractional framerates (e.g., 30fps from 60fps canvas).
-    pub fn connect_encoder(&self, frame_rate_divisor: u32) -> Receiver<VideoFrame> {
-        let (tx, rx) = channel::bounded(4); // Small buffer for encoder
+    pub fn connect_encoder(&self, frame_rate_divisor: u32) -> EncoderHandle {
+        self.add_encoder(EncoderOptions {
+            frame_rate_divisor,
+            ..Default::default()
+        })
+    }
+
+    /// Connect an encoder with explicit options
+    ///
+    /// Returns None if the requested conversion is not supported, or the
+    /// requested frame rate is invalid or above the output rate.
+    pub fn connect_encoder_with(&self, options: EncoderOptions) -> Option<EncoderHandle> {
+        if let Some(rate) = options.frame_rate {
+            if !rate.is_valid() || rate > self.info.frame_rate() {
+                return None;
+            }
+        }
+        if let Some(conversion) = options.conversion {
+            VideoScaler::new(self.output_shape(), conversion)?;
+        }
+        Some(self.add_encoder(options))
+    }
+
+    fn add_encoder(&self, options: EncoderOptions) -> EncoderHandle {
+        let (tx, rx) = channel::bounded(ENCODER_QUEUE_SIZE);
+        let counters = Arc::new(DeliveryCounters::default());
+        let latency = Arc::new(LatencyHistogram::new());
+
+        let evict_rx = match options.backpressure {
+            BackpressurePolicy::DropOldest => Some(rx.clone()),
+            _ => None,
+        };
 
         let connection = EncoderConnection {
             id: rand::random(),
-            frame_rate_divisor,
-            frame_count: 0,
+            selector: FrameSelector::new(match options.frame_rate {
+                Some(rate) => EncoderRate::Target(rate),
+                None => EncoderRate::Divisor(options.frame_rate_divisor.max(1)),
+            }),
             tx,
+            evict_rx,
+            backpressure: options.backpressure,
+            conversion: options.conversion,
+            counters: counters.clone(),
+            latency: latency.clone(),
+            pending_change: None,
         };
+        let id = connection.id;
 
         self.encoders.write().push(connection);
 
-        rx
+        EncoderHandle {
+            id,
+            rx,
+            counters,
+            latency,
+            encoders: Arc::downgrade(&self.encoders),
+        }
     }
 
     /// Disconnect an encoder by ID
-    pub fn disconnect_encoder(&self, encoder_id: u64) {
+    ///
+    /// Returns true if the encoder was connected.
+    pub fn disconnect_encoder(&self, encoder_id: u64) -> bool {
         let mut encoders = self.encoders.write();
+        let before = encoders.len();
         encoders.retain(|enc| enc.id != encoder_id);
+        encoders.len() != before
+    }
+
+    /// Get the number of connected encoders
+    pub fn encoder_count(&self) -> usize {
+        self.encoders.read().len()
+    }
+
+    /// Get the output configuration
+    pub fn info(&self) -> VideoOutputInfo {
+        self.info
+    }
+
+    /// Change the output resolution, frame rate or color settings at runtime
+    ///
+    /// Frames already submitted are delivered first, then the frame pool
+    /// and pacing clock are replaced and every encoder receives an
+    /// `EncoderEvent::FormatChanged` before any frame in the new format.
+    /// Encoder connections are kept; one whose channel stays too full to
+    /// take the change is disconnected after a while, without holding up
+    /// the others. Frames locked before the change are rejected by
+    /// `unlock_frame`, and their old pool stays allocated until then.
+    ///
+    /// Returns false if the configuration is invalid (only NV12 output is
+    /// supported), or if an encoder connected with a fixed `frame_rate`
+    /// would be above the new output rate; disconnect it first.
+    pub fn reconfigure(&mut self, info: VideoOutputInfo) -> bool {
+        if info.width == 0
+            || info.height == 0
+            || !info.frame_rate().is_valid()
+            || info.format != VideoFormat::NV12 as u32
+        {
+            return false;
+        }
+        // The same check connect_encoder_with makes; reconfigure holds
+        // `&mut self`, so no encoder can connect in between
+        let too_fast = self.encoders.read().iter().any(|encoder| {
+            encoder
+                .selector
+                .target_rate()
+                .is_some_and(|rate| rate > info.frame_rate())
+        });
+        if too_fast {
+            return false;
+        }
+
+        let frame_pool = FramePool::new(
+            VideoFormat::NV12,
+            info.width,
+            info.height,
+            MAX_CACHE_SIZE + 4,
+        );
+
+        if let Some(handle) = &self.thread_handle {
+            let (done_tx, done_rx) = channel::bounded(1);
+            let request = Reconfigure {
+                info,
+                done: done_tx,
+            };
+            if self.reconfigure_tx.send(request).is_ok() {
+                handle.thread().unpark();
+                done_rx.recv().ok();
+            }
+        }
+
+        let retired = std::mem::replace(&mut self.frame_pool, frame_pool);
+        self.retired_pools.push(retired);
+        // Leased frames keep their memory alive by themselves
+        self.retired_pools.retain(|pool| pool.stats().locked > 0);
+        self.info = info;
+        true
+    }
+
+    /// Shape of the frames this output produces
+    pub fn output_shape(&self) -> VideoConversion {
+        output_shape(&self.info)
     }
 
     /// Get statistics
@@ -157,56 +516,52 @@ impl VideoOutput {
         VideoOutputStats {
             total_frames: self.total_frames.load(Ordering::Relaxed),
             skipped_frames: self.skipped_frames.load(Ordering::Relaxed),
+            lagged_frames: self.lagged_frames.load(Ordering::Relaxed),
             queued_frames: self.frame_queue.len(),
             pool_stats: self.frame_pool.stats(),
         }
     }
 
+    /// Turn per-frame timing telemetry on or off (off by default)
+    pub fn set_timing_enabled(&self, enabled: bool) {
+        self.telemetry.set_enabled(enabled);
+    }
+
+    /// Clear all recorded timing samples, including per-encoder ones
+    pub fn reset_timing(&self) {
+        self.telemetry.submit_latency.clear();
+        self.telemetry.frame_jitter.clear();
+        for encoder in self.encoders.read().iter() {
+            encoder.latency.clear();
+        }
+    }
+
+    /// Snapshot of the timing telemetry
+    pub fn timing(&self) -> VideoTimingStats {
+        VideoTimingStats {
+            enabled: self.telemetry.is_enabled(),
+            submit_latency: self.telemetry.submit_latency.snapshot(),
+            frame_jitter: self.telemetry.frame_jitter.snapshot(),
+            encoders: self
+                .encoders
+                .read()
+                .iter()
+                .map(|encoder| EncoderTiming {
+                    id: encoder.id,
+                    delivery_latency: encoder.latency.snapshot(),
+                })
+                .collect(),
+        }
+    }
+
     /// Spawn video distribution thread
-    fn spawn_video_thread(
-        frame_queue: Arc<ArrayQueue<CachedFrame>>,
-        encoders: Arc<RwLock<Vec<EncoderConnection>>>,
-        total_frames: Arc<AtomicU64>,
-        skipped_frames: Arc<AtomicU64>,
-        running: Arc<AtomicBool>,
-        info: VideoOutputInfo,
-    ) -> JoinHandle<()> {
+    fn spawn_video_thread(ctx: VideoThread) -> JoinHandle<()> {
         thread::Builder::new()
             .name("obs-video-output".to_string())
             .spawn(move || {
-                // Calculate frame interval
-                let frame_interval_ns = (info.fps_den as u64 * 1_000_000_000) / info.fps_num as u64;
-                let frame_interval = Duration::from_nanos(frame_interval_ns);
-
-                while running.load(Ordering::Relaxed) {
-                    // Try to get a frame (non-blocking)
-                    if let Some(cached) = frame_queue.pop() {
-                        // Distribute to all encoders
-                        let mut encoders_lock = encoders.write();
-
-                        for encoder in encoders_lock.iter_mut() {
-                            // Frame rate divisor logic
-                            encoder.frame_count += 1;
-                            if encoder.frame_count >= encoder.frame_rate_divisor {
-                                encoder.frame_count = 0;
-
-                                // Send frame to encoder (non-blocking)
-                                // If encoder is slow, this will fail and we skip
-                                let _ = encoder.tx.try_send(cached.frame.clone());
-                            }
-                        }
-
-                        // Clean up disconnected encoders
-                        encoders_lock.retain(|enc| !enc.tx.is_full());
-
-                        total_frames.fetch_add(1, Ordering::Relaxed);
-                    } else {
-                        // No frames available, sleep briefly
-                        thread::sleep(Duration::from_micros(100));
-                    }
-                }
-
-                log::info!("Video output thread exiting");
+                tune_current_thread(ThreadRole::Video);
+                ctx.run();
+                forget_current_thread();
             })
             .expect("Failed to spawn video thread")
     }
@@ -216,6 +571,7 @@ impl VideoOutput {
         self.running.store(false, Ordering::Relaxed);
 
         if let Some(handle) = self.thread_handle.take() {
+            handle.thread().unpark();
             handle.join().ok();
         }
     }
@@ -227,17 +583,444 @@ impl Drop for VideoOutput {
     }
 }
 
+/// Queue an encoder's pending format change without waiting
+///
+/// A ring-buffer encoder makes room by losing its oldest frame; others keep
+/// the change pending and get no frames until there is room. Returns false
+/// if the encoder is gone or has refused the change for too long.
+fn flush_format_change(encoder: &mut EncoderConnection) -> bool {
+    let Some((change, since)) = encoder.pending_change else {
+        return true;
+    };
+
+    let mut event = EncoderEvent::FormatChanged(change);
+    loop {
+        match encoder.tx.try_send(event) {
+            Ok(()) => {
+                encoder.pending_change = None;
+                return true;
+            }
+            Err(TrySendError::Disconnected(_)) => return false,
+            Err(TrySendError::Full(back)) => event = back,
+        }
+
+        let evicted = encoder
+            .evict_rx
+            .as_ref()
+            .is_some_and(|rx| evict_oldest(&encoder.tx, rx, EncoderEvent::is_control));
+        if !evicted {
+            break;
+        }
+        encoder.counters.replaced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if since.elapsed() < FORMAT_CHANGE_TIMEOUT {
+        return true;
+    }
+    log::warn!(
+        "Encoder {} did not accept the format change, disconnecting",
+        encoder.id
+    );
+    false
+}
+
+fn output_shape(info: &VideoOutputInfo) -> VideoConversion {
+    VideoConversion {
+        width: info.width,
+        height: info.height,
+        format: VideoFormat::NV12,
+        colorspace: ColorSpace::from_u32(info.colorspace),
+        range: ColorRange::from_u32(info.range),
+    }
+}
+
+/// Converted frames for one distinct encoder conversion
+struct ConversionTarget {
+    scaler: VideoScaler,
+    pool: FramePool,
+    // Source frame (by data pointer) the newest converted frame came from,
+    // so duplicated output frames aren't converted again
+    converted_from: Option<(usize, VideoFrame)>,
+}
+
+impl ConversionTarget {
+    fn new(scaler: VideoScaler) -> Self {
+        let dst = scaler.dst();
+        Self {
+            pool: FramePool::new(dst.format, dst.width, dst.height, CONVERTED_IN_FLIGHT + 2),
+            scaler,
+            converted_from: None,
+        }
+    }
+
+    /// Get `source` converted to this target's shape
+    fn convert(&mut self, source: &VideoFrame) -> Option<VideoFrame> {
+        let source_key = source.data[0] as usize;
+        if let Some((key, frame)) = &self.converted_from {
+            if *key == source_key {
+                return Some(frame.clone());
+            }
+        }
+
+        // Frames return to the pool once every encoder has dropped them
+        let mut frame = self.pool.acquire_leased()?;
+        // SAFETY: both frames come from pools allocated for the scaler's
+        // source and destination shapes
+        unsafe { self.scaler.scale(source, &mut frame) };
+
+        self.converted_from = Some((source_key, frame.clone()));
+        Some(frame)
+    }
+}
+
+/// Video distribution thread state
+///
+/// Runs on the output's fps clock, like `video_thread` in video-io.c: every
+/// interval it emits exactly one frame, duplicating the previous one when
+/// the graphics thread hasn't submitted a new frame in time. The thread
+/// parks between frames and `unlock_frame` unparks it, so a frame submitted
+/// in an open interval goes out immediately instead of at the next poll.
+struct VideoThread {
+    frame_queue: Arc<ArrayQueue<CachedFrame>>,
+    encoders: Arc<RwLock<Vec<EncoderConnection>>>,
+    total_frames: Arc<AtomicU64>,
+    lagged_frames: Arc<AtomicU64>,
+    telemetry: Arc<VideoTelemetry>,
+    running: Arc<AtomicBool>,
+    reconfigure_rx: Receiver<Reconfigure>,
+    info: VideoOutputInfo,
+
+    // Most recent frame, re-sent when the graphics thread is late
+    last_frame: Option<VideoFrame>,
+
+    // Telemetry clock when the previous frame was emitted, for jitter
+    last_emit_ns: Option<u64>,
+
+    // (tick, timestamp) of the first submitted frame; output timestamps are
+    // derived from this so they stay monotonic and drift-free
+    clock_base: Option<(u64, u64)>,
+
+    // Per-conversion scalers and frame pools, shared by encoders asking
+    // for identical output
+    targets: HashMap<VideoConversion, ConversionTarget>,
+}
+
+impl VideoThread {
+    fn run(mut self) {
+        let mut start = Instant::now();
+        let mut tick: u64 = 0;
+
+        while self.running.load(Ordering::Relaxed) {
+            if let Ok(request) = self.reconfigure_rx.try_recv() {
+                self.reconfigure(tick, request);
+                // The new clock starts with the first frame in the new format
+                start = Instant::now();
+                tick = 0;
+                continue;
+            }
+
+            let rate = self.info.frame_rate();
+            let window_start = rate.frame_to_ns(tick);
+            let window_end = rate.frame_to_ns(tick + 1);
+            let elapsed = start.elapsed().as_nanos() as u64;
+
+            if elapsed < window_start {
+                // This tick's frame went out early; hold new frames until
+                // the next interval opens
+                thread::park_timeout(Duration::from_nanos(window_start - elapsed));
+                continue;
+            }
+
+            if elapsed >= window_end {
+                // After a long stall, jump the clock forward rather than
+                // flooding encoders with a burst of duplicates
+                let late_ticks = self.tick_at(elapsed) - tick;
+                if late_ticks > MAX_CATCHUP_FRAMES {
+                    self.lagged_frames.fetch_add(late_ticks, Ordering::Relaxed);
+                    tick += late_ticks;
+                }
+
+                // Interval closed without a new frame: duplicate the last one
+                self.output_frame(tick);
+                tick += 1;
+                continue;
+            }
+
+            if !self.frame_queue.is_empty() {
+                self.output_frame(tick);
+                tick += 1;
+                continue;
+            }
+
+            if self.last_frame.is_none() {
+                // Nothing to duplicate yet, so sleep until the first frame
+                // arrives and pick the clock up from there. Format changes
+                // that did not fit a full channel are retried meanwhile.
+                if self.flush_pending_changes() {
+                    thread::park_timeout(Duration::from_nanos(rate.interval_ns()));
+                } else {
+                    thread::park();
+                }
+                tick = self.tick_at(start.elapsed().as_nanos() as u64);
+                continue;
+            }
+
+            // Woken early by unlock_frame, or at the end of the interval
+            thread::park_timeout(Duration::from_nanos(window_end - elapsed));
+        }
+
+        log::info!("Video output thread exiting");
+    }
+
+    /// Switch to a new output configuration between frames
+    fn reconfigure(&mut self, mut tick: u64, request: Reconfigure) {
+        // Deliver everything submitted in the old format first
+        while !self.frame_queue.is_empty() {
+            self.output_frame(tick);
+            tick += 1;
+        }
+
+        self.last_frame = None;
+        self.clock_base = None;
+        self.last_emit_ns = None;
+        self.info = request.info;
+
+        // Scalers convert from the output shape, so rebuild them; the
+        // converted frame pools keep their shape and stay alive
+        let source_shape = output_shape(&self.info);
+        self.targets.retain(|_, target| {
+            match VideoScaler::new(source_shape, target.scaler.dst()) {
+                Some(scaler) => {
+                    target.scaler = scaler;
+                    target.converted_from = None;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        let info = self.info;
+        let now = Instant::now();
+        self.encoders.write().retain_mut(|encoder| {
+            encoder.selector.reset();
+            let change = VideoFormatChange {
+                info,
+                frame_shape: encoder.conversion.unwrap_or(source_shape),
+            };
+            encoder.pending_change = Some((change, now));
+            flush_format_change(encoder)
+        });
+
+        request.done.send(()).ok();
+    }
+
+    /// Retry queuing format changes; true while any is still pending
+    fn flush_pending_changes(&mut self) -> bool {
+        let mut encoders = self.encoders.write();
+        encoders.retain_mut(flush_format_change);
+        encoders
+            .iter()
+            .any(|encoder| encoder.pending_change.is_some())
+    }
+
+    /// Clock tick whose interval contains `elapsed_ns`
+    fn tick_at(&self, elapsed_ns: u64) -> u64 {
+        self.info
+            .frame_rate()
+            .ns_to_frame(elapsed_ns, Rounding::Down)
+    }
+
+    /// Emit the frame for one clock tick
+    fn output_frame(&mut self, tick: u64) {
+        let distributed_ns = self.telemetry.now();
+        let mut duplicate = false;
+
+        match self.frame_queue.pop() {
+            Some(cached) => {
+                if let (Some(now), Some(submitted)) = (distributed_ns, cached.submitted_ns) {
+                    self.telemetry
+                        .submit_latency
+                        .record(now.saturating_sub(submitted));
+                }
+                if self.clock_base.is_none() {
+                    self.clock_base = Some((tick, cached.timestamp));
+                }
+                // Encoders may still hold the previous frame; it returns to
+                // the pool once they drop it
+                self.last_frame = Some(cached.frame);
+            }
+            None => {
+                // Nothing rendered yet, so there is nothing to duplicate
+                if self.last_frame.is_none() {
+                    return;
+                }
+                self.lagged_frames.fetch_add(1, Ordering::Relaxed);
+                duplicate = true;
+            }
+        }
+
+        let (base_tick, base_timestamp) = match self.clock_base {
+            Some(base) => base,
+            None => return,
+        };
+        let mut frame = match &self.last_frame {
+            Some(frame) => frame.clone(),
+            None => return,
+        };
+        frame.timestamp = base_timestamp + self.info.frame_rate().frame_to_ns(tick - base_tick);
+        if duplicate {
+            frame.side_data = frame
+                .side_data
+                .as_ref()
+                .and_then(FrameSideData::for_duplicate);
+        }
+
+        if let (Some(now), Some(last)) = (distributed_ns, self.last_emit_ns) {
+            let interval = now.saturating_sub(last);
+            self.telemetry
+                .frame_jitter
+                .record(interval.abs_diff(self.info.frame_rate().interval_ns()));
+        }
+        self.last_emit_ns = distributed_ns;
+
+        // Pick the encoders this frame goes to
+        let mut encoders_lock = self.encoders.write();
+        let targets = &mut self.targets;
+        let info = self.info;
+
+        // Forget conversions no encoder asks for any more
+        targets.retain(|conversion, _| {
+            encoders_lock
+                .iter()
+                .any(|enc| enc.conversion == Some(*conversion))
+        });
+
+        let mut selected = Vec::new();
+        encoders_lock.retain_mut(|encoder| {
+            if !flush_format_change(encoder) {
+                return false;
+            }
+            if !encoder.selector.select(info.frame_rate()) {
+                return true;
+            }
+            if encoder.pending_change.is_some() {
+                // The frame is in the new format and must wait for the change
+                encoder.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+            selected.push((encoder.id, encoder.conversion));
+            true
+        });
+        drop(encoders_lock);
+
+        // Convert without the lock, so connecting or disconnecting an
+        // encoder does not wait for a conversion
+        let source_shape = output_shape(&self.info);
+        let mut converted: HashMap<VideoConversion, Option<VideoFrame>> = HashMap::new();
+        for &(_, conversion) in &selected {
+            let Some(conversion) = conversion else {
+                continue;
+            };
+            if converted.contains_key(&conversion) {
+                continue;
+            }
+            let target = match targets.entry(conversion) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    match VideoScaler::new(source_shape, conversion) {
+                        Some(scaler) => entry.insert(ConversionTarget::new(scaler)),
+                        None => continue,
+                    }
+                }
+            };
+            let output = if target.scaler.is_identity() {
+                Some(frame.clone())
+            } else {
+                target.convert(&frame).map(|mut output| {
+                    output.timestamp = frame.timestamp;
+                    output.side_data = frame
+                        .side_data
+                        .as_ref()
+                        .and_then(FrameSideData::for_conversion);
+                    output
+                })
+            };
+            converted.insert(conversion, output);
+        }
+
+        // Deliver to the picked encoders still connected
+        let telemetry = &self.telemetry;
+        self.encoders.write().retain_mut(|encoder| {
+            let Some(&(_, conversion)) = selected.iter().find(|(id, _)| *id == encoder.id) else {
+                return true;
+            };
+            let encoder_frame = match conversion {
+                None => frame.clone(),
+                // Unsupported conversions get no frames
+                Some(conversion) => match converted.get(&conversion) {
+                    None => return true,
+                    Some(Some(output)) => output.clone(),
+                    Some(None) => {
+                        encoder.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        return true;
+                    }
+                },
+            };
+
+            // A slow encoder is handled by its backpressure policy; it is
+            // only removed once its receiver is gone or the policy says so
+            let delivery = deliver(
+                &encoder.tx,
+                encoder.evict_rx.as_ref(),
+                EncoderEvent::is_control,
+                encoder.backpressure,
+                &encoder.counters,
+                EncoderEvent::Frame(encoder_frame),
+            );
+            if let (Delivery::Delivered, Some(start)) = (delivery, distributed_ns) {
+                if let Some(now) = telemetry.now() {
+                    encoder.latency.record(now.saturating_sub(start));
+                }
+            }
+            delivery != Delivery::Disconnected
+        });
+
+        self.total_frames.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct VideoOutputStats {
     pub total_frames: u64,
     pub skipped_frames: u64,
+    /// Output frames duplicated because no new frame was ready in time
+    pub lagged_frames: u64,
     pub queued_frames: usize,
     pub pool_stats: crate::frame_pool::PoolStats,
 }
 
+/// Timing telemetry snapshot for a `VideoOutput`
+#[derive(Debug, Clone, Default)]
+pub struct VideoTimingStats {
+    pub enabled: bool,
+    /// unlock_frame until the video thread picked the frame up
+    pub submit_latency: LatencySnapshot,
+    /// Deviation of output frame intervals from the nominal interval
+    pub frame_jitter: LatencySnapshot,
+    /// Distribution until queued on each encoder's channel
+    pub encoders: Vec<EncoderTiming>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderTiming {
+    pub id: u64,
+    pub delivery_latency: LatencySnapshot,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::side_data::{ColorOverride, ContentLightLevel};
     use std::time::Duration;
 
     #[test]
@@ -270,7 +1053,7 @@ mod tests {
     fn test_encoder_connection() {
         let output = VideoOutput::new(1920, 1080, 60, 1);
 
-        let rx = output.connect_encoder(1);
+        let encoder = output.connect_encoder(1);
 
         let frame = output.lock_frame().unwrap();
         output.unlock_frame(frame, 1000);
@@ -278,12 +1061,12 @@ mod tests {
         thread::sleep(Duration::from_millis(50));
 
         // Encoder should receive frame
-        match rx.try_recv() {
-            Ok(frame) => {
+        match encoder.try_recv() {
+            Ok(EncoderEvent::Frame(frame)) => {
                 assert_eq!(frame.width, 1920);
                 assert_eq!(frame.timestamp, 1000);
             }
-            Err(_) => {
+            _ => {
                 // Frame might not have been distributed yet
             }
         }
@@ -294,7 +1077,7 @@ mod tests {
         let output = VideoOutput::new(1920, 1080, 60, 1);
 
         // Encoder at 30fps (divisor=2)
-        let rx = output.connect_encoder(2);
+        let encoder = output.connect_encoder(2);
 
         for i in 0..10 {
             if let Some(frame) = output.lock_frame() {
@@ -306,14 +1089,793 @@ mod tests {
 
         // Should receive ~5 frames (half of 10)
         let mut received = 0;
-        while rx.try_recv().is_ok() {
+        while encoder.try_recv().is_ok() {
             received += 1;
         }
 
         assert!(
-            received >= 4 && received <= 6,
+            (4..=6).contains(&received),
             "Expected ~5 frames, got {}",
             received
         );
     }
+
+    #[test]
+    fn test_fractional_frame_rate_cadence() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                frame_rate: Some(FrameRate::new(40, 1)),
+                ..Default::default()
+            })
+            .unwrap();
+        let consumer = spawn_slow_consumer(encoder, Duration::ZERO, 20);
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        let (_encoder, timestamps) = consumer.join().unwrap();
+        assert_eq!(timestamps.len(), 20);
+
+        // 100 -> 40fps alternates 2 and 3 output intervals
+        let gaps: Vec<u64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+        for pair in gaps.windows(2) {
+            let mut pair = [pair[0], pair[1]];
+            pair.sort();
+            assert_eq!(pair, [20_000_000, 30_000_000]);
+        }
+    }
+
+    #[test]
+    fn test_frame_rate_above_output_rejected() {
+        let output = VideoOutput::new(640, 360, 30_000, 1001);
+        let connect = |(num, den)| {
+            output.connect_encoder_with(EncoderOptions {
+                frame_rate: Some(FrameRate::new(num, den)),
+                ..Default::default()
+            })
+        };
+
+        assert!(connect((30, 1)).is_none());
+        assert!(connect((0, 1)).is_none());
+        assert!(connect((30_000, 1001)).is_some());
+        assert!(connect((24_000, 1001)).is_some());
+    }
+
+    #[test]
+    fn test_reconfigure_below_encoder_rate_rejected() {
+        let mut output = VideoOutput::new(640, 360, 60, 1);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                frame_rate: Some(FrameRate::new(30, 1)),
+                ..Default::default()
+            })
+            .unwrap();
+        // Divisor encoders follow the output rate
+        output.connect_encoder(2);
+
+        let info = output.info();
+        let at = |fps_num| VideoOutputInfo { fps_num, ..info };
+        assert!(!output.reconfigure(at(25)));
+        assert_eq!(output.info().fps_num, 60);
+        assert!(output.reconfigure(at(30)));
+
+        encoder.disconnect();
+        assert!(output.reconfigure(at(25)));
+    }
+
+    #[test]
+    fn test_paced_output_duplicates_late_frames() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output.connect_encoder(1);
+
+        // Submit a single frame; the clock must keep emitting it
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 5_000);
+
+        let mut timestamps = Vec::new();
+        while timestamps.len() < 8 {
+            let frame = encoder
+                .recv_timeout(Duration::from_millis(500))
+                .ok()
+                .and_then(EncoderEvent::into_frame)
+                .expect("video thread stopped emitting frames");
+            timestamps.push(frame.timestamp);
+        }
+
+        // Every timestamp sits on the clock's grid; a starved test thread
+        // may miss some to the full channel, so only require increase
+        let interval = FrameRate::new(100, 1).frame_to_ns(1);
+        assert_eq!(timestamps[0], 5_000);
+        for pair in timestamps.windows(2) {
+            assert!(pair[1] > pair[0]);
+            assert_eq!((pair[1] - 5_000) % interval, 0);
+        }
+
+        let stats = output.stats();
+        assert!(stats.lagged_frames >= 7);
+        assert_eq!(stats.skipped_frames, 0);
+        // Counted once the frame has gone to every encoder
+        assert!(wait_for(|| output.stats().total_frames >= 8));
+    }
+
+    #[test]
+    fn test_paced_output_rate() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        thread::sleep(Duration::from_millis(300));
+
+        // ~30 frames at 100fps regardless of how many were submitted
+        let total = output.stats().total_frames;
+        assert!(
+            (20..=35).contains(&total),
+            "Expected ~30 frames, got {}",
+            total
+        );
+    }
+
+    #[test]
+    fn test_submitted_frame_wakes_video_thread() {
+        // 2fps: a frame waiting for the next interval would take up to 500ms
+        let output = VideoOutput::new(640, 360, 2, 1);
+        let encoder = output.connect_encoder(1);
+
+        thread::sleep(Duration::from_millis(50));
+        let frame = output.lock_frame().unwrap();
+        let submitted = Instant::now();
+        output.unlock_frame(frame, 0);
+
+        encoder
+            .recv_timeout(Duration::from_millis(400))
+            .expect("frame was not delivered");
+        assert!(
+            submitted.elapsed() < Duration::from_millis(100),
+            "delivery took {:?}",
+            submitted.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_timing_telemetry() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output.connect_encoder(1);
+
+        // Off by default: frames flow but nothing is recorded
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        recv_frame(&encoder);
+        let timing = output.timing();
+        assert!(!timing.enabled);
+        assert_eq!(timing.submit_latency.recorded, 0);
+        assert_eq!(timing.frame_jitter.recorded, 0);
+
+        output.set_timing_enabled(true);
+        for _ in 0..5 {
+            let frame = output.lock_frame().unwrap();
+            output.unlock_frame(frame, 0);
+            recv_frame(&encoder);
+        }
+
+        let timing = output.timing();
+        assert!(timing.enabled);
+        assert!(timing.submit_latency.recorded >= 5);
+        assert!(timing.submit_latency.p99_ns < 100_000_000);
+        assert!(timing.frame_jitter.recorded > 0);
+        assert_eq!(timing.encoders.len(), 1);
+        assert!(timing.encoders[0].delivery_latency.recorded > 0);
+        assert_eq!(
+            encoder.delivery_latency().recorded,
+            timing.encoders[0].delivery_latency.recorded
+        );
+
+        output.set_timing_enabled(false);
+        output.reset_timing();
+        let timing = output.timing();
+        assert_eq!(timing.submit_latency.recorded, 0);
+        assert_eq!(timing.encoders[0].delivery_latency.recorded, 0);
+    }
+
+    #[test]
+    fn test_full_encoder_stays_connected() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output.connect_encoder(1);
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        // Never read: the channel fills up and later frames are dropped
+        assert!(wait_for(|| encoder.stats().dropped_frames > 0));
+
+        assert!(encoder.is_connected());
+        assert_eq!(output.encoder_count(), 1);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_frames, 4);
+        assert!(stats.dropped_frames > 0);
+
+        // Draining lets delivery resume
+        while encoder.try_recv().is_ok() {}
+        encoder
+            .recv_timeout(Duration::from_millis(500))
+            .expect("encoder should receive frames again");
+        // Counted just after the frame is queued
+        assert!(wait_for(|| encoder.stats().received_frames > 4));
+    }
+
+    #[test]
+    fn test_frames_held_by_encoders_are_not_reused() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output.connect_encoder(1);
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        let held = recv_frame(&encoder);
+
+        // Cycle the pool several times over while the encoder holds on
+        for ts in 1..=MAX_CACHE_SIZE as u64 * 3 {
+            let frame = output.lock_frame().unwrap();
+            assert_ne!(frame.data[0], held.data[0]);
+            output.unlock_frame(frame, ts);
+            recv_frame(&encoder);
+        }
+    }
+
+    #[test]
+    fn test_encoder_handle_disconnect() {
+        let output = VideoOutput::new(640, 360, 60, 1);
+
+        let first = output.connect_encoder(1);
+        let second = output.connect_encoder(1);
+        assert_eq!(output.encoder_count(), 2);
+
+        drop(first);
+        assert_eq!(output.encoder_count(), 1);
+
+        assert!(output.disconnect_encoder(second.id()));
+        assert!(!second.is_connected());
+        assert!(!output.disconnect_encoder(second.id()));
+
+        second.disconnect();
+        assert_eq!(output.encoder_count(), 0);
+    }
+
+    #[test]
+    fn test_encoder_handle_outlives_output() {
+        let output = VideoOutput::new(640, 360, 60, 1);
+        let encoder = output.connect_encoder(1);
+        drop(output);
+
+        assert!(!encoder.is_connected());
+        drop(encoder);
+    }
+
+    /// Poll `done` for up to two seconds
+    fn wait_for(mut done: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !done() {
+            if Instant::now() > deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        true
+    }
+
+    /// Receive the next frame, skipping nothing
+    fn recv_frame(encoder: &EncoderHandle) -> VideoFrame {
+        encoder
+            .recv_timeout(Duration::from_millis(500))
+            .ok()
+            .and_then(EncoderEvent::into_frame)
+            .expect("no frame received")
+    }
+
+    /// Connect an encoder that reads one frame every `delay`
+    fn spawn_slow_consumer(
+        encoder: EncoderHandle,
+        delay: Duration,
+        frames: usize,
+    ) -> thread::JoinHandle<(EncoderHandle, Vec<u64>)> {
+        thread::spawn(move || {
+            let mut timestamps = Vec::new();
+            while timestamps.len() < frames {
+                match encoder.recv_timeout(Duration::from_secs(1)) {
+                    Ok(EncoderEvent::Frame(frame)) => timestamps.push(frame.timestamp),
+                    _ => break,
+                }
+                thread::sleep(delay);
+            }
+            (encoder, timestamps)
+        })
+    }
+
+    fn start_output(fps: u32) -> VideoOutput {
+        let output = VideoOutput::new(640, 360, fps, 1);
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        output
+    }
+
+    #[test]
+    fn test_backpressure_drop_newest_slow_consumer() {
+        let output = VideoOutput::new(640, 360, 200, 1);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                backpressure: BackpressurePolicy::DropNewest,
+                ..Default::default()
+            })
+            .unwrap();
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        let (encoder, timestamps) = spawn_slow_consumer(encoder, Duration::from_millis(20), 10)
+            .join()
+            .unwrap();
+        assert_eq!(timestamps.len(), 10);
+
+        let stats = encoder.stats();
+        assert!(stats.dropped_frames > 0);
+        assert_eq!(stats.replaced_frames, 0);
+        assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_backpressure_drop_oldest_slow_consumer() {
+        let output = start_output(200);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                backpressure: BackpressurePolicy::DropOldest,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Let the channel fill, then check the queued frames are recent
+        thread::sleep(Duration::from_millis(100));
+        let newest_expected = output.stats().total_frames;
+
+        let stats = encoder.stats();
+        assert!(stats.replaced_frames > 0);
+        assert_eq!(stats.dropped_frames, 0);
+
+        let timestamps: Vec<u64> = encoder
+            .receiver()
+            .try_iter()
+            .filter_map(EncoderEvent::into_frame)
+            .map(|f| f.timestamp)
+            .collect();
+        assert_eq!(timestamps.len(), 4);
+        assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+
+        // The oldest frames were replaced, so what's queued is near the head
+        let frame_ns = FrameRate::new(200, 1).frame_to_ns(1);
+        let oldest_index = timestamps[0] / frame_ns;
+        assert!(oldest_index + 8 >= newest_expected);
+    }
+
+    #[test]
+    fn test_backpressure_block_never_drops() {
+        let output = start_output(200);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                backpressure: BackpressurePolicy::Block(Duration::from_secs(1)),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let (encoder, timestamps) = spawn_slow_consumer(encoder, Duration::from_millis(10), 20)
+            .join()
+            .unwrap();
+        assert_eq!(timestamps.len(), 20);
+
+        // Every frame arrives, in order, with no gaps
+        let frame_ns = FrameRate::new(200, 1).frame_to_ns(1);
+        assert!(timestamps
+            .windows(2)
+            .all(|w| w[1] - w[0] <= frame_ns + 1 && w[1] > w[0]));
+
+        let stats = encoder.stats();
+        assert_eq!(stats.dropped_frames, 0);
+        assert!(stats.blocked_frames > 0);
+    }
+
+    #[test]
+    fn test_backpressure_disconnect_after_drops() {
+        let output = start_output(200);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                backpressure: BackpressurePolicy::DisconnectAfter(3),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Never read: 4 frames fill the channel, 3 more drops disconnect
+        thread::sleep(Duration::from_millis(150));
+
+        assert!(!encoder.is_connected());
+        assert_eq!(output.encoder_count(), 0);
+
+        let stats = encoder.stats();
+        assert_eq!(stats.received_frames, 4);
+        assert_eq!(stats.dropped_frames, 3);
+    }
+
+    fn i420_720p() -> VideoConversion {
+        VideoConversion {
+            width: 1280,
+            height: 720,
+            format: VideoFormat::I420,
+            colorspace: ColorSpace::Default,
+            range: ColorRange::Default,
+        }
+    }
+
+    #[test]
+    fn test_encoder_conversion() {
+        let output = VideoOutput::new(1920, 1080, 100, 1);
+        let stream = output.connect_encoder(1);
+        let recording = output
+            .connect_encoder_with(EncoderOptions {
+                conversion: Some(i420_720p()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        let raw = recv_frame(&stream);
+        assert_eq!(
+            (raw.width, raw.height, raw.format),
+            (1920, 1080, VideoFormat::NV12)
+        );
+
+        let converted = recv_frame(&recording);
+        assert_eq!(
+            (converted.width, converted.height, converted.format),
+            (1280, 720, VideoFormat::I420)
+        );
+        assert_eq!(converted.timestamp, raw.timestamp);
+        assert_eq!(converted.linesize[..3], [1280, 640, 640]);
+    }
+
+    #[test]
+    fn test_side_data_reaches_encoders() {
+        let output = VideoOutput::new(1920, 1080, 100, 1);
+        let stream = output.connect_encoder(1);
+        let recording = output
+            .connect_encoder_with(EncoderOptions {
+                conversion: Some(i420_720p()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mut frame = output.lock_frame().unwrap();
+        assert!(frame.side_data.is_none());
+        let side_data = frame.side_data_mut();
+        side_data.content_light = Some(ContentLightLevel {
+            max_cll: 1000,
+            max_fall: 400,
+        });
+        side_data.captions.push([0x94, 0x2C]);
+        side_data.color = Some(ColorOverride {
+            colorspace: ColorSpace::CS2100PQ,
+            range: ColorRange::Full,
+        });
+        output.unlock_frame(frame, 0);
+
+        let raw = recv_frame(&stream);
+        let raw_data = raw.side_data.as_ref().unwrap();
+        assert_eq!(raw_data.captions, vec![[0x94, 0x2C]]);
+        assert!(raw_data.color.is_some());
+
+        // The scaled frame is in the conversion's color space
+        let converted = recv_frame(&recording);
+        let converted_data = converted.side_data.as_ref().unwrap();
+        assert_eq!(converted_data.content_light, raw_data.content_light);
+        assert!(converted_data.color.is_none());
+
+        // Duplicates keep HDR metadata but do not repeat captions
+        let duplicate = recv_frame(&stream);
+        let duplicate_data = duplicate.side_data.as_ref().unwrap();
+        assert!(duplicate_data.captions.is_empty());
+        assert_eq!(duplicate_data.content_light, raw_data.content_light);
+
+        // Pooled frames come back without the previous frame's side data
+        for _ in 0..MAX_CACHE_SIZE {
+            let frame = output.lock_frame().unwrap();
+            assert!(frame.side_data.is_none());
+            output.unlock_frame(frame, 0);
+            recv_frame(&stream);
+        }
+    }
+
+    #[test]
+    fn test_identical_conversions_share_frames() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let options = EncoderOptions {
+            conversion: Some(VideoConversion {
+                width: 320,
+                height: 180,
+                ..output.output_shape()
+            }),
+            ..Default::default()
+        };
+        let first = output.connect_encoder_with(options).unwrap();
+        let second = output.connect_encoder_with(options).unwrap();
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        for _ in 0..3 {
+            let a = recv_frame(&first);
+            let b = recv_frame(&second);
+            assert_eq!(a.data[0], b.data[0]);
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.width, 320);
+        }
+    }
+
+    #[test]
+    fn test_held_converted_frames_are_not_reused() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let options = EncoderOptions {
+            conversion: Some(VideoConversion {
+                width: 320,
+                height: 180,
+                ..output.output_shape()
+            }),
+            ..Default::default()
+        };
+        let slow = output.connect_encoder_with(options).unwrap();
+        let fast = output.connect_encoder_with(options).unwrap();
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        let held = recv_frame(&slow);
+        drop(slow);
+        // SAFETY: the frame is 320x180, and nothing converts into it while
+        // it is held
+        unsafe { held.data[0].write(0xab) };
+
+        // The other encoder keeps the conversion busy
+        for ts in 1..=CONVERTED_IN_FLIGHT as u64 * 3 {
+            let frame = output.lock_frame().unwrap();
+            output.unlock_frame(frame, ts);
+            recv_frame(&fast);
+        }
+        assert_eq!(unsafe { held.data[0].read() }, 0xab);
+    }
+
+    #[test]
+    fn test_identity_conversion_passes_through() {
+        let output = VideoOutput::new(640, 360, 100, 1);
+        let raw = output.connect_encoder(1);
+        let same = output
+            .connect_encoder_with(EncoderOptions {
+                conversion: Some(output.output_shape()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let frame = output.lock_frame().unwrap();
+        let data = frame.data[0];
+        output.unlock_frame(frame, 0);
+
+        assert_eq!(recv_frame(&raw).data[0], data);
+        assert_eq!(recv_frame(&same).data[0], data);
+    }
+
+    #[test]
+    fn test_unsupported_conversion_rejected() {
+        let output = VideoOutput::new(640, 360, 60, 1);
+        let options = EncoderOptions {
+            conversion: Some(VideoConversion {
+                format: VideoFormat::P010,
+                ..output.output_shape()
+            }),
+            ..Default::default()
+        };
+
+        assert!(output.connect_encoder_with(options).is_none());
+        assert_eq!(output.encoder_count(), 0);
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_connections() {
+        let mut output = VideoOutput::new(640, 360, 100, 1);
+        let stream = output.connect_encoder(1);
+        let recording = output
+            .connect_encoder_with(EncoderOptions {
+                conversion: Some(i420_720p()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        assert_eq!(recv_frame(&stream).width, 640);
+
+        let info = VideoOutputInfo {
+            width: 1280,
+            height: 720,
+            fps_num: 50,
+            fps_den: 1,
+            ..output.info()
+        };
+        assert!(output.reconfigure(info));
+        assert_eq!(output.info(), info);
+        assert_eq!(output.encoder_count(), 2);
+
+        // Every encoder sees the change before any frame in the new format
+        let next_change = |encoder: &EncoderHandle, old_width: u32| loop {
+            match encoder.recv_timeout(Duration::from_millis(500)) {
+                Ok(EncoderEvent::FormatChanged(change)) => break change,
+                Ok(EncoderEvent::Frame(frame)) => assert_eq!(frame.width, old_width),
+                Err(err) => panic!("no format change: {:?}", err),
+            }
+        };
+        let change = next_change(&stream, 640);
+        assert_eq!(change.info, info);
+        assert_eq!(change.frame_shape, output.output_shape());
+        assert_eq!(next_change(&recording, 1280).frame_shape, i420_720p());
+
+        let frame = output.lock_frame().unwrap();
+        assert_eq!((frame.width, frame.height), (1280, 720));
+        assert!(output.unlock_frame(frame, 1_000));
+        assert_eq!(recv_frame(&stream).width, 1280);
+        assert_eq!(recv_frame(&recording).format, VideoFormat::I420);
+        assert!(stream.is_connected() && recording.is_connected());
+    }
+
+    #[test]
+    fn test_reconfigure_drains_submitted_frames() {
+        // 2fps, so submitted frames are still queued when reconfiguring
+        let mut output = VideoOutput::new(640, 360, 2, 1);
+        let encoder = output.connect_encoder(1);
+
+        for ts in 0..3 {
+            let frame = output.lock_frame().unwrap();
+            output.unlock_frame(frame, ts);
+        }
+        assert!(output.reconfigure(VideoOutputInfo {
+            width: 320,
+            height: 180,
+            ..output.info()
+        }));
+
+        let events: Vec<EncoderEvent> = encoder.receiver().try_iter().collect();
+        assert_eq!(events.len(), 4);
+        assert!(events[..3]
+            .iter()
+            .all(|event| matches!(event, EncoderEvent::Frame(frame) if frame.width == 640)));
+        assert!(matches!(events[3], EncoderEvent::FormatChanged(_)));
+        assert_eq!(output.stats().queued_frames, 0);
+    }
+
+    #[test]
+    fn test_stale_frames_rejected_after_rate_change() {
+        let mut output = VideoOutput::new(640, 360, 60, 1);
+        let stale = output.lock_frame().unwrap();
+
+        // Same size, so only the pool tells the frames apart
+        for fps_num in [50, 30] {
+            assert!(output.reconfigure(VideoOutputInfo {
+                fps_num,
+                ..output.info()
+            }));
+        }
+        assert_eq!(output.retired_pools.len(), 1);
+
+        // The old pool is still allocated while the frame is locked
+        unsafe { stale.data[0].write_bytes(0x80, 640) };
+        assert!(!output.unlock_frame(stale, 0));
+        assert_eq!(output.stats().skipped_frames, 1);
+
+        assert!(output.reconfigure(output.info()));
+        assert!(output.retired_pools.is_empty());
+    }
+
+    #[test]
+    fn test_leased_frames_do_not_keep_retired_pools() {
+        let mut output = VideoOutput::new(640, 360, 100, 1);
+        let encoder = output.connect_encoder(1);
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        let held = recv_frame(&encoder);
+
+        assert!(output.reconfigure(output.info()));
+        assert_eq!(output.frame_pool.stats().in_use, 0);
+        assert!(output.retired_pools.is_empty());
+        // The lease keeps the old memory alive
+        unsafe { held.data[0].write_bytes(0x80, 640) };
+    }
+
+    #[test]
+    fn test_format_change_survives_eviction() {
+        let mut output = start_output(200);
+        let encoder = output
+            .connect_encoder_with(EncoderOptions {
+                backpressure: BackpressurePolicy::DropOldest,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(wait_for(|| encoder.stats().replaced_frames > 0));
+
+        let replaced = encoder.stats().replaced_frames;
+        assert!(output.reconfigure(VideoOutputInfo {
+            width: 320,
+            height: 180,
+            ..output.info()
+        }));
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+
+        // New frames evict the old ones around the change, never the change
+        assert!(wait_for(|| encoder.stats().replaced_frames > replaced + 8));
+        drop(output);
+        let events: Vec<EncoderEvent> = encoder.receiver().try_iter().collect();
+        let change = events
+            .iter()
+            .position(EncoderEvent::is_control)
+            .expect("format change was evicted");
+        assert!(events[change + 1..]
+            .iter()
+            .all(|event| matches!(event, EncoderEvent::Frame(frame) if frame.width == 320)));
+    }
+
+    #[test]
+    fn test_full_encoder_does_not_stall_reconfigure() {
+        let mut output = start_output(100);
+        let stuck = output.connect_encoder(1);
+        let reader = output.connect_encoder(1);
+        assert!(wait_for(|| stuck.receiver().is_full()));
+
+        let started = Instant::now();
+        assert!(output.reconfigure(VideoOutputInfo {
+            width: 320,
+            height: 180,
+            ..output.info()
+        }));
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        let frame = output.lock_frame().unwrap();
+        output.unlock_frame(frame, 0);
+        assert!(wait_for(|| matches!(
+            reader.try_recv(),
+            Ok(EncoderEvent::Frame(frame)) if frame.width == 320
+        )));
+
+        // Once it drains, the stuck encoder sees the change before any
+        // frame in the new format
+        let old: Vec<EncoderEvent> = stuck.receiver().try_iter().collect();
+        assert!(old
+            .iter()
+            .all(|event| matches!(event, EncoderEvent::Frame(frame) if frame.width == 640)));
+        let change = stuck
+            .recv_timeout(Duration::from_millis(500))
+            .expect("format change was not delivered");
+        assert!(change.is_control());
+        assert_eq!(recv_frame(&stuck).width, 320);
+        assert!(stuck.is_connected());
+    }
+
+    #[test]
+    fn test_reconfigure_rejects_stale_and_invalid() {
+        let mut output = VideoOutput::new(640, 360, 60, 1);
+        let stale = output.lock_frame().unwrap();
+
+        assert!(!output.reconfigure(VideoOutputInfo {
+            fps_num: 0,
+            ..output.info()
+        }));
+        assert!(output.reconfigure(VideoOutputInfo {
+            width: 1280,
+            height: 720,
+            ..output.info()
+        }));
+
+        assert!(!output.unlock_frame(stale, 0));
+        assert_eq!(output.stats().skipped_frames, 1);
+    }
 }
@@ -0,0 +1,754 @@
+//! Frame scaling and pixel format conversion
+//!
+//! Rust counterpart of video-scaler-ffmpeg.c. Converts between the 8-bit
+//! formats the video output produces (NV12, I420, I444, RGBA, BGRA, BGRX)
+//! with color space/range conversion, so each encoder connection can
+//! receive frames in its own shape.
+//!
+//! Resampling is separable: every destination row is filtered vertically
+//! into a row of floats per source channel, then horizontally, with the
+//! taps of both axes computed once per scaler. Axes shrunk below half size
+//! use an area filter, others bilinear. Only the source channels an output
+//! channel depends on are read.
+
+use crate::types::{ColorRange, ColorSpace, VideoFormat, VideoFrame};
+use std::ops::Range;
+
+/// Shape of a video frame: geometry, pixel format and color description
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoConversion {
+    pub width: u32,
+    pub height: u32,
+    pub format: VideoFormat,
+    pub colorspace: ColorSpace,
+    pub range: ColorRange,
+}
+
+/// 3x4 affine color matrix (rows produce output channels 0..3)
+type ColorMatrix = [[f32; 4]; 3];
+
+const IDENTITY: ColorMatrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+];
+
+/// Converts frames from one `VideoConversion` shape to another
+#[derive(Debug, Clone)]
+pub struct VideoScaler {
+    src: VideoConversion,
+    dst: VideoConversion,
+    matrix: ColorMatrix,
+    // Full-resolution grid, then the subsampled chroma grid if any
+    passes: Vec<Pass>,
+}
+
+impl VideoScaler {
+    /// Create a scaler, or None if either format is unsupported
+    pub fn new(src: VideoConversion, dst: VideoConversion) -> Option<Self> {
+        if !Self::is_supported(src.format) || !Self::is_supported(dst.format) {
+            return None;
+        }
+        if src.width == 0 || src.height == 0 || dst.width == 0 || dst.height == 0 {
+            return None;
+        }
+
+        let matrix = conversion_matrix(&src, &dst);
+        let (dst_w, dst_h) = (dst.width as usize, dst.height as usize);
+        let mut passes = Vec::with_capacity(2);
+        if is_subsampled(dst.format) {
+            passes.push(Pass::new(&src, &matrix, (dst_w, dst_h), 0..1));
+            let chroma = ((dst_w / 2).max(1), (dst_h / 2).max(1));
+            passes.push(Pass::new(&src, &matrix, chroma, 1..3));
+        } else {
+            passes.push(Pass::new(&src, &matrix, (dst_w, dst_h), 0..3));
+        }
+
+        Some(Self {
+            src,
+            dst,
+            matrix,
+            passes,
+        })
+    }
+
+    /// Check if a format can be converted from or to
+    pub fn is_supported(format: VideoFormat) -> bool {
+        matches!(
+            format,
+            VideoFormat::NV12
+                | VideoFormat::I420
+                | VideoFormat::I444
+                | VideoFormat::RGBA
+                | VideoFormat::BGRA
+                | VideoFormat::BGRX
+        )
+    }
+
+    /// True if frames can be passed through without conversion
+    pub fn is_identity(&self) -> bool {
+        self.src.width == self.dst.width
+            && self.src.height == self.dst.height
+            && self.src.format == self.dst.format
+            && self.matrix == IDENTITY
+    }
+
+    pub fn src(&self) -> VideoConversion {
+        self.src
+    }
+
+    pub fn dst(&self) -> VideoConversion {
+        self.dst
+    }
+
+    /// Scale and convert `src` into `dst`
+    ///
+    /// # Safety
+    /// Both frames' planes must be valid for their format and for the
+    /// dimensions this scaler was created with.
+    pub unsafe fn scale(&self, src: &VideoFrame, dst: &mut VideoFrame) {
+        let src_planes = Planes::new(src, self.src.width, self.src.height);
+        let dst_planes = Planes::new(dst, self.dst.width, self.dst.height);
+
+        for pass in &self.passes {
+            pass.run(&self.matrix, &src_planes, &dst_planes);
+        }
+
+        if let Some(alpha) = &dst_planes.alpha {
+            for y in 0..alpha.height {
+                for x in 0..alpha.width {
+                    alpha.write(x, y, 255);
+                }
+            }
+        }
+
+        dst.timestamp = src.timestamp;
+    }
+}
+
+/// Source samples making up each destination sample along one axis
+///
+/// Every destination sample has `width` weights, zero-padded at the end,
+/// so filtering a row is a fixed-size dot product per sample.
+#[derive(Debug, Clone)]
+struct Taps {
+    width: usize,
+    starts: Vec<usize>,
+    weights: Vec<f32>,
+}
+
+impl Taps {
+    /// Taps resampling `src` samples to `dst`, sample centers aligned
+    fn new(src: usize, dst: usize) -> Self {
+        let scale = src as f32 / dst as f32;
+        let spans: Vec<(usize, Vec<f32>)> = (0..dst)
+            .map(|i| {
+                if scale > 2.0 {
+                    // Area: the source samples the destination sample
+                    // covers, weighted by how much of each it covers
+                    let lo = i as f32 * scale;
+                    let hi = lo + scale;
+                    let start = (lo as usize).min(src - 1);
+                    let end = (hi.ceil() as usize).clamp(start + 1, src);
+                    let covered: Vec<f32> = (start..end)
+                        .map(|s| (hi.min(s as f32 + 1.0) - lo.max(s as f32)).max(0.0))
+                        .collect();
+                    let total: f32 = covered.iter().sum();
+                    (start, covered.iter().map(|w| w / total).collect())
+                } else {
+                    let p = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, (src - 1) as f32);
+                    let x0 = p as usize;
+                    let fx = p - x0 as f32;
+                    if fx > 0.0 && x0 + 1 < src {
+                        (x0, vec![1.0 - fx, fx])
+                    } else {
+                        (x0, vec![1.0])
+                    }
+                }
+            })
+            .collect();
+
+        let width = spans.iter().map(|(_, w)| w.len()).max().unwrap_or(1);
+        let mut taps = Self {
+            width,
+            starts: Vec::with_capacity(dst),
+            weights: vec![0.0; dst * width],
+        };
+        for (i, (start, weights)) in spans.into_iter().enumerate() {
+            taps.starts.push(start);
+            taps.weights[i * width..i * width + weights.len()].copy_from_slice(&weights);
+        }
+        taps
+    }
+
+    /// (source sample, weight) pairs of destination sample `i`, skipping
+    /// the padding
+    fn of(&self, i: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let weights = &self.weights[i * self.width..(i + 1) * self.width];
+        (self.starts[i]..)
+            .zip(weights.iter().copied())
+            .filter(|&(_, weight)| weight != 0.0)
+    }
+
+    /// Filter `row`, which holds `width - 1` zeros past the source
+    /// samples, into `out`
+    fn apply(&self, row: &[f32], out: &mut [f32]) {
+        // Fixed widths unroll; bilinear is 1 or 2, area mostly 3 to 5
+        match self.width {
+            1 => self.apply_fixed::<1>(row, out),
+            2 => self.apply_fixed::<2>(row, out),
+            3 => self.apply_fixed::<3>(row, out),
+            4 => self.apply_fixed::<4>(row, out),
+            5 => self.apply_fixed::<5>(row, out),
+            width => {
+                for ((out, &start), weights) in out
+                    .iter_mut()
+                    .zip(&self.starts)
+                    .zip(self.weights.chunks_exact(width))
+                {
+                    let samples = &row[start..start + width];
+                    *out = samples.iter().zip(weights).map(|(s, w)| s * w).sum();
+                }
+            }
+        }
+    }
+
+    fn apply_fixed<const N: usize>(&self, row: &[f32], out: &mut [f32]) {
+        for ((out, &start), weights) in out
+            .iter_mut()
+            .zip(&self.starts)
+            .zip(self.weights.chunks_exact(N))
+        {
+            let samples: &[f32; N] = row[start..start + N].try_into().unwrap();
+            let weights: &[f32; N] = weights.try_into().unwrap();
+            *out = (0..N).map(|i| samples[i] * weights[i]).sum();
+        }
+    }
+}
+
+/// Destination channels filled on one grid, and how to read the source
+/// channels they depend on
+#[derive(Debug, Clone)]
+struct Pass {
+    width: usize,
+    height: usize,
+    outputs: Range<usize>,
+    // Source channel with its horizontal and vertical taps
+    inputs: Vec<(usize, Taps, Taps)>,
+}
+
+impl Pass {
+    fn new(
+        src: &VideoConversion,
+        matrix: &ColorMatrix,
+        (width, height): (usize, usize),
+        outputs: Range<usize>,
+    ) -> Self {
+        let inputs = (0..3)
+            .filter(|&k| outputs.clone().any(|c| matrix[c][k] != 0.0))
+            .map(|k| {
+                let (src_w, src_h) = channel_size(src, k);
+                (k, Taps::new(src_w, width), Taps::new(src_h, height))
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            outputs,
+            inputs,
+        }
+    }
+
+    unsafe fn run(&self, m: &ColorMatrix, src: &Planes, dst: &Planes) {
+        // Source rows filtered vertically, then horizontally, per input
+        let mut columns: Vec<Vec<f32>> = self
+            .inputs
+            .iter()
+            .map(|(k, x_taps, _)| vec![0.0; src.channels[*k].width + x_taps.width - 1])
+            .collect();
+        let mut samples = vec![vec![0.0f32; self.width]; self.inputs.len()];
+        let mut values = vec![0.0f32; self.width];
+
+        for gy in 0..self.height {
+            for (i, (k, x_taps, y_taps)) in self.inputs.iter().enumerate() {
+                let channel = &src.channels[*k];
+                let column = &mut columns[i][..channel.width];
+                column.fill(0.0);
+                for (y, weight) in y_taps.of(gy) {
+                    channel.accumulate(y, weight, column);
+                }
+                x_taps.apply(&columns[i], &mut samples[i]);
+            }
+
+            for c in self.outputs.clone() {
+                let row = &m[c];
+                values.fill(row[3]);
+                for ((k, _, _), input) in self.inputs.iter().zip(&samples) {
+                    for (value, &sample) in values.iter_mut().zip(input) {
+                        *value += row[*k] * sample;
+                    }
+                }
+                dst.channels[c].store(gy, &values);
+            }
+        }
+    }
+}
+
+/// Round and clamp a channel value
+#[inline]
+fn to_u8(value: f32) -> u8 {
+    // max/min map NaN to 0 where clamp keeps it, so the value is always in
+    // range; unlike a saturating `as` cast, this vectorizes
+    #[allow(clippy::manual_clamp)]
+    let value = (value + 0.5).max(0.0).min(255.0);
+    // SAFETY: finite and within 0..=255
+    unsafe { value.to_int_unchecked::<i32>() as u8 }
+}
+
+/// One 8-bit channel inside a plane (possibly interleaved)
+struct Channel {
+    ptr: *mut u8,
+    linesize: usize,
+    width: usize,
+    height: usize,
+    step: usize,
+}
+
+impl Channel {
+    /// Bytes from the channel's first to last sample of row `y`
+    unsafe fn row(&self, y: usize) -> &[u8] {
+        let len = (self.width - 1) * self.step + 1;
+        std::slice::from_raw_parts(self.ptr.add(y * self.linesize), len)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn row_mut(&self, y: usize) -> &mut [u8] {
+        let len = (self.width - 1) * self.step + 1;
+        std::slice::from_raw_parts_mut(self.ptr.add(y * self.linesize), len)
+    }
+
+    /// Add row `y` times `weight` to `sums`
+    unsafe fn accumulate(&self, y: usize, weight: f32, sums: &mut [f32]) {
+        fn add<const STEP: usize>(row: &[u8], weight: f32, sums: &mut [f32]) {
+            for (x, sum) in sums.iter_mut().enumerate() {
+                *sum += weight * row[x * STEP] as f32;
+            }
+        }
+        let row = self.row(y);
+        // Constant steps let the loops vectorize
+        match self.step {
+            1 => add::<1>(row, weight, sums),
+            2 => add::<2>(row, weight, sums),
+            // Packed RGB
+            _ => add::<4>(row, weight, sums),
+        }
+    }
+
+    /// Round `values` into row `y`
+    unsafe fn store(&self, y: usize, values: &[f32]) {
+        fn put<const STEP: usize>(row: &mut [u8], values: &[f32]) {
+            for (x, &value) in values.iter().enumerate() {
+                row[x * STEP] = to_u8(value);
+            }
+        }
+        let row = self.row_mut(y);
+        match self.step {
+            1 => put::<1>(row, values),
+            2 => put::<2>(row, values),
+            // Packed RGB
+            _ => put::<4>(row, values),
+        }
+    }
+
+    unsafe fn write(&self, x: usize, y: usize, value: u8) {
+        *self.ptr.add(y * self.linesize + x * self.step) = value;
+    }
+}
+
+/// Channel layout of a frame: [Y, U, V] for YUV formats, [R, G, B] for RGB
+struct Planes {
+    channels: [Channel; 3],
+    alpha: Option<Channel>,
+}
+
+impl Planes {
+    fn new(frame: &VideoFrame, width: u32, height: u32) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        let (cw, ch) = (w / 2, h / 2);
+        let ls = |i: usize| frame.linesize[i] as usize;
+        let channel = |ptr: *mut u8, linesize: usize, width: usize, height: usize, step| Channel {
+            ptr,
+            linesize,
+            width: width.max(1),
+            height: height.max(1),
+            step,
+        };
+        let packed = |r: usize, g: usize, b: usize| {
+            let at = |offset| unsafe { frame.data[0].add(offset) };
+            [
+                channel(at(r), ls(0), w, h, 4),
+                channel(at(g), ls(0), w, h, 4),
+                channel(at(b), ls(0), w, h, 4),
+            ]
+        };
+
+        match frame.format {
+            VideoFormat::NV12 => Self {
+                channels: [
+                    channel(frame.data[0], ls(0), w, h, 1),
+                    channel(frame.data[1], ls(1), cw, ch, 2),
+                    channel(unsafe { frame.data[1].add(1) }, ls(1), cw, ch, 2),
+                ],
+                alpha: None,
+            },
+            VideoFormat::I420 => Self {
+                channels: [
+                    channel(frame.data[0], ls(0), w, h, 1),
+                    channel(frame.data[1], ls(1), cw, ch, 1),
+                    channel(frame.data[2], ls(2), cw, ch, 1),
+                ],
+                alpha: None,
+            },
+            VideoFormat::I444 => Self {
+                channels: [
+                    channel(frame.data[0], ls(0), w, h, 1),
+                    channel(frame.data[1], ls(1), w, h, 1),
+                    channel(frame.data[2], ls(2), w, h, 1),
+                ],
+                alpha: None,
+            },
+            VideoFormat::RGBA => Self {
+                channels: packed(0, 1, 2),
+                alpha: Some(channel(unsafe { frame.data[0].add(3) }, ls(0), w, h, 4)),
+            },
+            // BGRA and BGRX
+            _ => Self {
+                channels: packed(2, 1, 0),
+                alpha: Some(channel(unsafe { frame.data[0].add(3) }, ls(0), w, h, 4)),
+            },
+        }
+    }
+}
+
+fn is_subsampled(format: VideoFormat) -> bool {
+    matches!(format, VideoFormat::NV12 | VideoFormat::I420)
+}
+
+/// Size of source channel `k`, as `Planes::new` lays it out
+fn channel_size(shape: &VideoConversion, k: usize) -> (usize, usize) {
+    let (w, h) = (shape.width as usize, shape.height as usize);
+    if k > 0 && is_subsampled(shape.format) {
+        ((w / 2).max(1), (h / 2).max(1))
+    } else {
+        (w, h)
+    }
+}
+
+fn is_yuv(format: VideoFormat) -> bool {
+    matches!(
+        format,
+        VideoFormat::NV12 | VideoFormat::I420 | VideoFormat::I444
+    )
+}
+
+/// Luma coefficients (Kr, Kb) for a color space, as video-matrices.c does
+fn luma_coefficients(colorspace: ColorSpace) -> (f32, f32) {
+    match colorspace {
+        ColorSpace::CS601 => (0.299, 0.114),
+        ColorSpace::CS2100PQ | ColorSpace::CS2100HLG => (0.2627, 0.0593),
+        ColorSpace::Default | ColorSpace::CS709 | ColorSpace::SRGB => (0.2126, 0.0722),
+    }
+}
+
+/// (luma scale, luma offset, chroma scale) mapping YUV codes to 0..255 RGB
+fn range_scale(range: ColorRange) -> (f32, f32, f32) {
+    match range {
+        ColorRange::Full => (1.0, 0.0, 1.0),
+        ColorRange::Default | ColorRange::Partial => (255.0 / 219.0, 16.0, 255.0 / 224.0),
+    }
+}
+
+fn yuv_to_rgb(colorspace: ColorSpace, range: ColorRange) -> ColorMatrix {
+    let (kr, kb) = luma_coefficients(colorspace);
+    let kg = 1.0 - kr - kb;
+    let (ys, yo, cs) = range_scale(range);
+
+    let r_v = 2.0 * (1.0 - kr) * cs;
+    let b_u = 2.0 * (1.0 - kb) * cs;
+    let g_u = -2.0 * (1.0 - kb) * kb / kg * cs;
+    let g_v = -2.0 * (1.0 - kr) * kr / kg * cs;
+
+    [
+        [ys, 0.0, r_v, -ys * yo - r_v * 128.0],
+        [ys, g_u, g_v, -ys * yo - (g_u + g_v) * 128.0],
+        [ys, b_u, 0.0, -ys * yo - b_u * 128.0],
+    ]
+}
+
+fn rgb_to_yuv(colorspace: ColorSpace, range: ColorRange) -> ColorMatrix {
+    let (kr, kb) = luma_coefficients(colorspace);
+    let kg = 1.0 - kr - kb;
+    let (ys, yo, cs) = range_scale(range);
+
+    let u = 2.0 * (1.0 - kb) * cs;
+    let v = 2.0 * (1.0 - kr) * cs;
+
+    [
+        [kr / ys, kg / ys, kb / ys, yo],
+        [-kr / u, -kg / u, (1.0 - kb) / u, 128.0],
+        [(1.0 - kr) / v, -kg / v, -kb / v, 128.0],
+    ]
+}
+
+/// Compose two affine color matrices: `outer(inner(x))`
+fn compose(outer: &ColorMatrix, inner: &ColorMatrix) -> ColorMatrix {
+    let mut out = [[0.0; 4]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| outer[i][k] * inner[k][j]).sum();
+        }
+        row[3] += outer[i][3];
+        // Terms that cancel out exactly, so unused channels are not read
+        for value in row.iter_mut() {
+            if value.abs() < 1e-6 {
+                *value = 0.0;
+            }
+        }
+    }
+    out
+}
+
+fn conversion_matrix(src: &VideoConversion, dst: &VideoConversion) -> ColorMatrix {
+    match (is_yuv(src.format), is_yuv(dst.format)) {
+        (false, false) => IDENTITY,
+        (true, false) => yuv_to_rgb(src.colorspace, src.range),
+        (false, true) => rgb_to_yuv(dst.colorspace, dst.range),
+        (true, true) => {
+            let same_matrix =
+                luma_coefficients(src.colorspace) == luma_coefficients(dst.colorspace);
+            let same_range = range_scale(src.range) == range_scale(dst.range);
+            if same_matrix && same_range {
+                IDENTITY
+            } else {
+                compose(
+                    &rgb_to_yuv(dst.colorspace, dst.range),
+                    &yuv_to_rgb(src.colorspace, src.range),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame_pool::FramePool;
+
+    fn shape(width: u32, height: u32, format: VideoFormat) -> VideoConversion {
+        VideoConversion {
+            width,
+            height,
+            format,
+            colorspace: ColorSpace::CS709,
+            range: ColorRange::Partial,
+        }
+    }
+
+    unsafe fn fill_plane(
+        frame: &VideoFrame,
+        plane: usize,
+        rows: usize,
+        value: impl Fn(usize, usize) -> u8,
+    ) {
+        let linesize = frame.linesize[plane] as usize;
+        for y in 0..rows {
+            for x in 0..linesize {
+                *frame.data[plane].add(y * linesize + x) = value(x, y);
+            }
+        }
+    }
+
+    unsafe fn plane_bytes(frame: &VideoFrame, plane: usize, rows: usize) -> Vec<u8> {
+        let len = frame.linesize[plane] as usize * rows;
+        std::slice::from_raw_parts(frame.data[plane], len).to_vec()
+    }
+
+    #[test]
+    fn test_nv12_to_i420_same_size_is_exact() {
+        let src_pool = FramePool::new(VideoFormat::NV12, 16, 8, 1);
+        let dst_pool = FramePool::new(VideoFormat::I420, 16, 8, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            fill_plane(&src, 0, 8, |x, y| (x * 10 + y) as u8);
+            fill_plane(&src, 1, 4, |x, y| {
+                if x % 2 == 0 {
+                    60 + y as u8
+                } else {
+                    200 - x as u8
+                }
+            });
+
+            let scaler = VideoScaler::new(
+                shape(16, 8, VideoFormat::NV12),
+                shape(16, 8, VideoFormat::I420),
+            )
+            .unwrap();
+            assert!(!scaler.is_identity());
+            scaler.scale(&src, &mut dst);
+
+            assert_eq!(plane_bytes(&dst, 0, 8), plane_bytes(&src, 0, 8));
+
+            let uv = plane_bytes(&src, 1, 4);
+            let u: Vec<u8> = uv.iter().step_by(2).copied().collect();
+            let v: Vec<u8> = uv.iter().skip(1).step_by(2).copied().collect();
+            assert_eq!(plane_bytes(&dst, 1, 4), u);
+            assert_eq!(plane_bytes(&dst, 2, 4), v);
+        }
+    }
+
+    #[test]
+    fn test_downscale_keeps_flat_color() {
+        let src_pool = FramePool::new(VideoFormat::NV12, 64, 36, 1);
+        let dst_pool = FramePool::new(VideoFormat::NV12, 32, 18, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            fill_plane(&src, 0, 36, |_, _| 90);
+            fill_plane(&src, 1, 18, |x, _| if x % 2 == 0 { 54 } else { 34 });
+
+            let scaler = VideoScaler::new(
+                shape(64, 36, VideoFormat::NV12),
+                shape(32, 18, VideoFormat::NV12),
+            )
+            .unwrap();
+            scaler.scale(&src, &mut dst);
+
+            assert!(plane_bytes(&dst, 0, 18).iter().all(|&y| y == 90));
+            let uv = plane_bytes(&dst, 1, 9);
+            assert!(uv.chunks(2).all(|c| c == [54, 34]));
+        }
+    }
+
+    #[test]
+    fn test_area_filter_below_half_size() {
+        let src_pool = FramePool::new(VideoFormat::I420, 16, 8, 1);
+        let dst_pool = FramePool::new(VideoFormat::I420, 4, 2, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            // One-pixel stripes, which a bilinear filter would alias
+            fill_plane(&src, 0, 8, |x, _| if x % 2 == 0 { 40 } else { 200 });
+            fill_plane(&src, 1, 4, |_, _| 128);
+            fill_plane(&src, 2, 4, |_, _| 128);
+
+            let scaler = VideoScaler::new(
+                shape(16, 8, VideoFormat::I420),
+                shape(4, 2, VideoFormat::I420),
+            )
+            .unwrap();
+            scaler.scale(&src, &mut dst);
+
+            assert_eq!(plane_bytes(&dst, 0, 2)[..4], [120; 4]);
+        }
+
+        // The middle destination sample covers 2/3, 1 and 2/3 source ones
+        let taps = Taps::new(7, 3);
+        let weights: Vec<(usize, f32)> = taps.of(1).collect();
+        assert_eq!(weights.iter().map(|w| w.0).collect::<Vec<_>>(), [2, 3, 4]);
+        assert!((weights[1].1 - 3.0 / 7.0).abs() < 1e-6);
+        // Bilinear at half size and above
+        assert_eq!(Taps::new(4, 2).of(0).count(), 2);
+    }
+
+    #[test]
+    fn test_rgba_to_nv12_white_and_black() {
+        let src_pool = FramePool::new(VideoFormat::RGBA, 8, 4, 1);
+        let dst_pool = FramePool::new(VideoFormat::NV12, 8, 4, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            // Left half white, right half black
+            fill_plane(&src, 0, 4, |x, _| if x < 16 { 255 } else { 0 });
+
+            let scaler = VideoScaler::new(
+                shape(8, 4, VideoFormat::RGBA),
+                shape(8, 4, VideoFormat::NV12),
+            )
+            .unwrap();
+            scaler.scale(&src, &mut dst);
+
+            let y = plane_bytes(&dst, 0, 4);
+            assert_eq!(y[0], 235);
+            assert_eq!(y[7], 16);
+            assert!(plane_bytes(&dst, 1, 2).iter().all(|&c| c == 128));
+        }
+    }
+
+    #[test]
+    fn test_partial_to_full_range() {
+        let src_pool = FramePool::new(VideoFormat::I420, 4, 2, 1);
+        let dst_pool = FramePool::new(VideoFormat::I420, 4, 2, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            fill_plane(&src, 0, 2, |x, _| if x < 2 { 16 } else { 235 });
+            fill_plane(&src, 1, 1, |_, _| 128);
+            fill_plane(&src, 2, 1, |_, _| 128);
+
+            let mut full = shape(4, 2, VideoFormat::I420);
+            full.range = ColorRange::Full;
+            let scaler = VideoScaler::new(shape(4, 2, VideoFormat::I420), full).unwrap();
+            scaler.scale(&src, &mut dst);
+            // Luma only reads luma
+            assert_eq!(scaler.passes[0].inputs.len(), 1);
+
+            assert_eq!(plane_bytes(&dst, 0, 1), vec![0, 0, 255, 255]);
+            assert_eq!(plane_bytes(&dst, 1, 1), vec![128, 128]);
+        }
+    }
+
+    #[test]
+    fn test_nv12_to_bgra_gray() {
+        let src_pool = FramePool::new(VideoFormat::NV12, 4, 2, 1);
+        let dst_pool = FramePool::new(VideoFormat::BGRA, 4, 2, 1);
+        let src = src_pool.acquire().unwrap();
+        let mut dst = dst_pool.acquire().unwrap();
+
+        unsafe {
+            fill_plane(&src, 0, 2, |_, _| 126);
+            fill_plane(&src, 1, 1, |_, _| 128);
+
+            let scaler = VideoScaler::new(
+                shape(4, 2, VideoFormat::NV12),
+                shape(4, 2, VideoFormat::BGRA),
+            )
+            .unwrap();
+            scaler.scale(&src, &mut dst);
+
+            // (126 - 16) * 255 / 219 = 128.08
+            assert!(plane_bytes(&dst, 0, 2)
+                .chunks(4)
+                .all(|px| px == [128, 128, 128, 255]));
+        }
+    }
+
+    #[test]
+    fn test_identity_and_unsupported() {
+        let nv12 = shape(1920, 1080, VideoFormat::NV12);
+        assert!(VideoScaler::new(nv12, nv12).unwrap().is_identity());
+
+        let mut default_color = nv12;
+        default_color.colorspace = ColorSpace::Default;
+        default_color.range = ColorRange::Default;
+        assert!(VideoScaler::new(nv12, default_color).unwrap().is_identity());
+
+        assert!(VideoScaler::new(nv12, shape(1920, 1080, VideoFormat::P010)).is_none());
+        assert!(VideoScaler::new(nv12, shape(0, 1080, VideoFormat::NV12)).is_none());
+    }
+}